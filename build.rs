@@ -4,4 +4,11 @@ fn main() {
     {
         compile_error!("required feature missing on Windows: rcon");
     }
+
+    // Generate gRPC service
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=res/lazymc.proto");
+        tonic_build::compile_protos("res/lazymc.proto").expect("failed to compile gRPC protocol");
+    }
 }
@@ -0,0 +1,83 @@
+use std::fs;
+
+use clap::ArgMatches;
+
+#[cfg(unix)]
+use crate::action::ctl;
+use crate::backup;
+use crate::config;
+#[cfg(unix)]
+use crate::util::cli::prompt_yes;
+#[cfg(unix)]
+use crate::util::error::quit;
+use crate::util::error::{quit_error, quit_error_msg, ErrorHintsBuilder};
+
+/// Invoke backup command.
+pub fn invoke(matches: &ArgMatches) {
+    let config = config::load(matches);
+
+    match matches.subcommand() {
+        Some(("list", _)) => list(&config),
+        #[cfg(unix)]
+        Some(("restore", matches)) => {
+            let id = matches.get_one::<String>("id").unwrap();
+            restore(&config, id, matches.get_flag("yes"));
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// List backup archives, oldest first.
+fn list(config: &config::Config) {
+    let archive_dir = match backup::archive_dir(config) {
+        Some(dir) => dir,
+        None => quit_error_msg(
+            "Server directory not configured (server.directory)",
+            ErrorHintsBuilder::default().build().unwrap(),
+        ),
+    };
+    let archives = match backup::list_archives(&archive_dir) {
+        Ok(archives) => archives,
+        Err(err) => quit_error(
+            anyhow!(err).context("Failed to list backup archives"),
+            ErrorHintsBuilder::default().build().unwrap(),
+        ),
+    };
+
+    if archives.is_empty() {
+        eprintln!("No backup archives in {}", archive_dir.display());
+        return;
+    }
+    for path in &archives {
+        let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        println!(
+            "{}  {:.1} MiB",
+            backup::archive_id(path).unwrap_or("?"),
+            size as f64 / 1024.0 / 1024.0
+        );
+    }
+}
+
+/// Restore backup archive through running lazymc instance, after confirmation.
+#[cfg(unix)]
+fn restore(config: &config::Config, id: &str, yes: bool) {
+    // Resolve ID first, to confirm the actual backup
+    let id = match backup::restore::find_archive(config, id) {
+        Ok(path) => backup::archive_id(&path).unwrap_or(id).to_string(),
+        Err(err) => quit_error_msg(err, ErrorHintsBuilder::default().build().unwrap()),
+    };
+
+    if !yes
+        && !prompt_yes(
+            &format!(
+            "Restore backup {id}? This replaces the current worlds and stops the server if running"
+        ),
+            Some(false),
+        )
+    {
+        quit();
+    }
+
+    ctl::query(config, &format!("restore {id}"));
+    eprintln!("Restoring backup {id}, see the lazymc log for progress");
+}
@@ -0,0 +1,270 @@
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use clap::ArgMatches;
+use tokio::io::{self, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::config::{self, Proxy};
+#[cfg(unix)]
+use crate::net;
+use crate::os;
+use crate::proxy::{self, ProxyHeader, ProxyOptions};
+use crate::util::error::{quit_error, ErrorHints};
+
+/// Size of chunks written by benchmark clients.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Benchmark options.
+#[derive(Debug)]
+struct Bench {
+    /// Number of concurrent connections.
+    connections: usize,
+
+    /// Bytes to send through each connection.
+    size: u64,
+
+    /// Number of round trips per connection to measure latency.
+    pings: usize,
+
+    /// Number of proxy acceptors.
+    acceptors: usize,
+
+    /// Proxy options.
+    options: ProxyOptions,
+}
+
+/// Benchmark results of a single connection.
+struct ConnectionResult {
+    /// Measured round trip times.
+    latencies: Vec<Duration>,
+
+    /// Bytes relayed in both directions.
+    bytes: u64,
+}
+
+/// Invoke benchmark command.
+///
+/// Relays synthetic connections through the proxy to a local echo server, and reports
+/// throughput, latency and CPU usage.
+pub fn invoke(matches: &ArgMatches) -> Result<(), ()> {
+    // Use proxy options from config if available, without rate limits
+    let (mut options, mut acceptors) =
+        if Path::new(matches.get_one::<String>("config").unwrap()).is_file() {
+            let config = config::load(matches);
+            (ProxyOptions::from_config(&config), config.public.acceptors)
+        } else {
+            let proxy = Proxy::default();
+            let options = ProxyOptions {
+                buffer_size: proxy.buffer_size,
+                server_socket: proxy.server,
+                ..Default::default()
+            };
+            (options, 1)
+        };
+    options.upload_limit = 0;
+    options.download_limit = 0;
+
+    // Override with command line options
+    if let Some(buffer_size) = matches.get_one::<usize>("buffer-size") {
+        options.buffer_size = *buffer_size;
+    }
+    if let Some(count) = matches.get_one::<usize>("acceptors") {
+        acceptors = *count;
+    }
+
+    let bench = Bench {
+        connections: *matches.get_one("connections").unwrap(),
+        size: *matches.get_one::<u64>("size").unwrap() * 1024 * 1024,
+        pings: *matches.get_one("pings").unwrap(),
+        acceptors: acceptors.max(1),
+        options,
+    };
+    run(bench)
+}
+
+/// Run benchmark.
+#[tokio::main(flavor = "multi_thread")]
+async fn run(bench: Bench) -> Result<(), ()> {
+    let (backend, proxy) = match setup(&bench).await {
+        Ok(addrs) => addrs,
+        Err(err) => quit_error(
+            anyhow!(err).context("Failed to set up benchmark listeners"),
+            ErrorHints::default(),
+        ),
+    };
+    eprintln!(
+        "Relaying {} connections through proxy on {} to echo server on {}...",
+        bench.connections, proxy, backend
+    );
+
+    // Drive all connections concurrently
+    let cpu_start = cpu_time();
+    let start = Instant::now();
+    let clients = (0..bench.connections)
+        .map(|_| tokio::spawn(client(proxy, bench.size, bench.pings)))
+        .collect::<Vec<_>>();
+    let mut latencies = vec![];
+    let mut bytes = 0;
+    for client in clients {
+        match client.await {
+            Ok(Ok(result)) => {
+                latencies.extend(result.latencies);
+                bytes += result.bytes;
+            }
+            Ok(Err(err)) => quit_error(
+                anyhow!(err).context("Benchmark connection failed"),
+                ErrorHints::default(),
+            ),
+            Err(_) => return Err(()),
+        }
+    }
+    let elapsed = start.elapsed();
+    let cpu = cpu_start.zip(cpu_time()).map(|(start, end)| end - start);
+
+    // Report
+    latencies.sort_unstable();
+    let percentile = |p: usize| {
+        latencies
+            .get((latencies.len() * p / 100).min(latencies.len().saturating_sub(1)))
+            .copied()
+            .unwrap_or_default()
+    };
+    let mib = bytes as f64 / 1024.0 / 1024.0;
+    println!(
+        "Options:      {} acceptors, {} byte buffer",
+        bench.acceptors, bench.options.buffer_size
+    );
+    println!(
+        "Transferred:  {:.1} MiB in {:.2}s",
+        mib,
+        elapsed.as_secs_f64()
+    );
+    println!("Throughput:   {:.1} MiB/s", mib / elapsed.as_secs_f64());
+    println!(
+        "Latency:      p50 {:?}, p99 {:?}, max {:?}",
+        percentile(50),
+        percentile(99),
+        latencies.last().copied().unwrap_or_default()
+    );
+    match cpu {
+        Some(cpu) => println!(
+            "CPU time:     {:.2}s ({:.0}% of one core)",
+            cpu.as_secs_f64(),
+            cpu.as_secs_f64() / elapsed.as_secs_f64() * 100.0
+        ),
+        None => println!("CPU time:     unknown"),
+    }
+
+    Ok(())
+}
+
+/// Bind echo server and proxy listeners, spawn their accept loops.
+///
+/// Returns the echo server and proxy addresses.
+async fn setup(bench: &Bench) -> io::Result<(SocketAddr, SocketAddr)> {
+    let localhost: SocketAddr = ([127, 0, 0, 1], 0).into();
+
+    // Echo server, relays everything back
+    let backend = TcpListener::bind(localhost).await?;
+    let backend_addr = backend.local_addr()?;
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = backend.accept().await {
+            tokio::spawn(async move {
+                let (mut reader, mut writer) = stream.split();
+                let _ = io::copy(&mut reader, &mut writer).await;
+            });
+        }
+    });
+
+    // Proxy listeners
+    let listeners = bind(localhost, bench.acceptors)?;
+    let proxy_addr = listeners[0].local_addr()?;
+    for listener in listeners {
+        let options = bench.options.clone();
+        tokio::spawn(async move {
+            while let Ok((inbound, _)) = listener.accept().await {
+                let options = options.clone();
+                tokio::spawn(async move {
+                    let _ = proxy::proxy(inbound, ProxyHeader::None, backend_addr.into(), options)
+                        .await;
+                });
+            }
+        });
+    }
+
+    Ok((backend_addr, proxy_addr))
+}
+
+/// Bind proxy listeners, with SO_REUSEPORT if more than one acceptor is used.
+#[allow(unused_variables)]
+fn bind(addr: SocketAddr, acceptors: usize) -> io::Result<Vec<TcpListener>> {
+    #[cfg(unix)]
+    if acceptors > 1 {
+        let first = net::bind_reuseport(addr)?;
+        let addr = first.local_addr()?;
+        let mut listeners = vec![first];
+        for _ in 1..acceptors {
+            listeners.push(net::bind_reuseport(addr)?);
+        }
+        return Ok(listeners);
+    }
+
+    let listener = std::net::TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    Ok(vec![TcpListener::from_std(listener)?])
+}
+
+/// Drive a single benchmark connection.
+///
+/// Measures round trip latency with single bytes, then sends and receives `size` bytes.
+async fn client(addr: SocketAddr, size: u64, pings: usize) -> io::Result<ConnectionResult> {
+    let mut stream = TcpStream::connect(addr).await?;
+    stream.set_nodelay(true)?;
+
+    // Measure round trips
+    let mut latencies = Vec::with_capacity(pings);
+    let mut byte = [0];
+    for _ in 0..pings {
+        let start = Instant::now();
+        stream.write_all(&byte).await?;
+        stream.read_exact(&mut byte).await?;
+        latencies.push(start.elapsed());
+    }
+
+    // Send and receive data concurrently
+    let (mut reader, mut writer) = stream.split();
+    let send = async {
+        let chunk = vec![0; CHUNK_SIZE];
+        let mut sent = 0;
+        while sent < size {
+            let len = (size - sent).min(CHUNK_SIZE as u64) as usize;
+            writer.write_all(&chunk[..len]).await?;
+            sent += len as u64;
+        }
+        writer.shutdown().await
+    };
+    let receive = async {
+        let mut buf = vec![0; CHUNK_SIZE];
+        let mut received = 0;
+        while received < size {
+            match reader.read(&mut buf).await? {
+                0 => break,
+                read => received += read as u64,
+            }
+        }
+        Ok::<_, io::Error>(received)
+    };
+    let ((), received) = tokio::try_join!(send, receive)?;
+
+    Ok(ConnectionResult {
+        latencies,
+        bytes: size + received + pings as u64 * 2,
+    })
+}
+
+/// Get CPU time consumed by this process, if supported on this platform.
+fn cpu_time() -> Option<Duration> {
+    os::process_stats(std::process::id()).map(|stats| stats.cpu_time)
+}
@@ -1,11 +1,16 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use clap::ArgMatches;
 
-use crate::util::cli::prompt_yes;
+use crate::mc::server_properties;
+use crate::proto;
+use crate::util::cli::{prompt_default, prompt_yes};
 use crate::util::error::{quit, quit_error, ErrorHintsBuilder};
 
+/// Default config template.
+const TEMPLATE: &str = include_str!("../../res/lazymc.toml");
+
 /// Invoke config test command.
 pub fn invoke(matches: &ArgMatches) {
     // Get config path, attempt to canonicalize
@@ -27,8 +32,15 @@ pub fn invoke(matches: &ArgMatches) {
         quit();
     }
 
+    // Ask questions to tailor config when interactive
+    let config = if !matches.get_flag("template") && atty::is(atty::Stream::Stdin) {
+        wizard(&path)
+    } else {
+        TEMPLATE.to_string()
+    };
+
     // Generate file
-    if let Err(err) = fs::write(&path, include_bytes!("../../res/lazymc.toml")) {
+    if let Err(err) = fs::write(&path, config) {
         quit_error(
             anyhow!(err).context("Failed to generate config file"),
             ErrorHintsBuilder::default().build().unwrap(),
@@ -37,3 +49,147 @@ pub fn invoke(matches: &ArgMatches) {
 
     eprintln!("Config saved at: {}", path.to_str().unwrap_or("?"));
 }
+
+/// Probe server directory and ask questions, return tailored config.
+fn wizard(path: &Path) -> String {
+    let config_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut config = TEMPLATE.to_string();
+
+    // Server directory, relative to config
+    let directory = prompt_default("Minecraft server directory", ".");
+    let dir = config_dir.join(&directory);
+    if !dir.is_dir() {
+        eprintln!("Directory does not exist yet, using defaults");
+    }
+    config = set_value(&config, "server", "directory", &quote(&directory));
+
+    // Probe server.properties for the port players use now, lazymc takes it over
+    let properties = dir.join(server_properties::FILE);
+    let current_port = Some(&properties)
+        .filter(|file| file.is_file())
+        .and_then(|file| server_properties::read_property(file, "server-port"))
+        .and_then(|port| port.parse::<u16>().ok())
+        .unwrap_or(25565);
+    let public_port: u16 =
+        prompt_default("Public port players connect to", &current_port.to_string())
+            .parse()
+            .unwrap_or(current_port);
+    let server_port = public_port.wrapping_add(1);
+    config = set_value(
+        &config,
+        "public",
+        "address",
+        &quote(&format!("0.0.0.0:{public_port}")),
+    );
+    config = set_value(
+        &config,
+        "server",
+        "address",
+        &quote(&format!("127.0.0.1:{server_port}")),
+    );
+
+    // Probe server jar, and Minecraft version from its name
+    let jar = prompt_default(
+        "Server jar file",
+        &find_jar(&dir).unwrap_or_else(|| "server.jar".into()),
+    );
+    let memory = prompt_default("Server memory", "1G");
+    config = set_value(&config, "server", "memory", &quote(&memory));
+    config = set_value(
+        &config,
+        "server",
+        "command",
+        &quote(&format!(
+            "java -Xmx{{memory}} -Xms{{memory}} -jar {jar} --nogui"
+        )),
+    );
+
+    let version = prompt_default(
+        "Minecraft version",
+        &detect_version(&jar).unwrap_or_else(|| proto::PROTO_DEFAULT_VERSION.into()),
+    );
+    match proto::PROTO_VERSIONS.iter().find(|(v, _)| *v == version) {
+        Some((_, protocol)) => {
+            config = set_value(&config, "public", "version", &quote(&version));
+            config = set_value(&config, "public", "protocol", &protocol.to_string());
+        }
+        None => {
+            eprintln!("Unknown Minecraft version, set public.version and public.protocol manually")
+        }
+    }
+
+    // Sleep behavior
+    let sleep_after = prompt_default("Seconds without players before sleeping", "60");
+    if let Ok(sleep_after) = sleep_after.parse::<u32>() {
+        config = set_value(&config, "time", "sleep_after", &sleep_after.to_string());
+    }
+    if !prompt_yes("Freeze server process instead of stopping it", Some(true)) {
+        config = set_value(&config, "server", "freeze_process", "false");
+    }
+
+    config
+}
+
+/// Find server jar in directory, prefers known server jar names.
+fn find_jar(dir: &Path) -> Option<String> {
+    let mut jars: Vec<String> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.ends_with(".jar"))
+        .collect();
+    jars.sort();
+
+    let known = ["server", "paper", "purpur", "fabric", "forge", "spigot"];
+    jars.iter()
+        .find(|jar| known.iter().any(|name| jar.to_lowercase().contains(name)))
+        .or_else(|| jars.first())
+        .cloned()
+}
+
+/// Detect Minecraft version from jar name, such as `paper-1.20.4-496.jar`.
+fn detect_version(jar: &str) -> Option<String> {
+    jar.split(['-', '_'])
+        .filter(|part| part.starts_with("1."))
+        .map(|part| part.trim_end_matches(".jar"))
+        .find(|part| proto::PROTO_VERSIONS.iter().any(|(v, _)| v == part))
+        .map(|part| part.to_string())
+}
+
+/// Quote string as TOML value.
+fn quote(value: &str) -> String {
+    toml::Value::String(value.into()).to_string()
+}
+
+/// Set value in config template, uncommenting it if needed.
+///
+/// The key must exist in the given section of the template.
+fn set_value(config: &str, section: &str, key: &str, value: &str) -> String {
+    let header = format!("[{section}]");
+    let mut in_section = false;
+    let mut done = false;
+
+    config
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') {
+                in_section = trimmed == header;
+            }
+
+            // Match both commented and uncommented key
+            let uncommented = trimmed.trim_start_matches('#').trim_start();
+            let is_key = uncommented
+                .split_once('=')
+                .map(|(k, _)| k.trim() == key)
+                .unwrap_or(false);
+            if in_section && !done && is_key {
+                done = true;
+                return format!("{key} = {value}");
+            }
+            line.to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        + "\n"
+}
@@ -0,0 +1,16 @@
+use crate::config::Config;
+use crate::util::error::{quit_error, ErrorHintsBuilder};
+
+/// Invoke config schema command.
+///
+/// Prints JSON Schema of the configuration to stdout.
+pub fn invoke() {
+    let schema = schemars::schema_for!(Config);
+    match serde_json::to_string_pretty(&schema) {
+        Ok(schema) => println!("{schema}"),
+        Err(err) => quit_error(
+            anyhow!(err).context("Failed to generate config schema"),
+            ErrorHintsBuilder::default().build().unwrap(),
+        ),
+    }
+}
@@ -1,9 +1,19 @@
-use std::path::PathBuf;
+use std::env;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 
 use clap::ArgMatches;
 
-use crate::config::Config;
-use crate::util::error::{quit_error, quit_error_msg, ErrorHintsBuilder};
+use crate::config::{Config, Server as ConfigServer};
+use crate::proto;
+use crate::util::error::{
+    print_error_msg, print_warning, quit_error, quit_error_msg, ErrorHintsBuilder,
+};
+use crate::util::style::highlight_info;
+
+/// File written to test whether the server directory is writable.
+const WRITE_TEST_FILE: &str = ".lazymc-write-test";
 
 /// Invoke config test command.
 pub fn invoke(matches: &ArgMatches) {
@@ -22,7 +32,7 @@ pub fn invoke(matches: &ArgMatches) {
     }
 
     // Try to load config
-    let _config = match Config::load(path) {
+    let config = match Config::load(path) {
         Ok(config) => config,
         Err(err) => {
             quit_error(
@@ -32,7 +42,242 @@ pub fn invoke(matches: &ArgMatches) {
         }
     };
 
-    // TODO: do additional config tests: server dir correct, command set?
-
     eprintln!("Config loaded successfully!");
+
+    // Run preflight checks
+    let mut report = Report::default();
+    check_command(&config, &mut report);
+    check_directory(&config, &mut report);
+    check_ports(&config, &mut report);
+    check_protocol(&config, &mut report);
+    #[cfg(feature = "rcon")]
+    check_rcon(&config, &mut report);
+
+    if report.errors > 0 {
+        quit_error_msg(
+            format!(
+                "Config test failed with {} error(s) and {} warning(s)",
+                report.errors, report.warnings
+            ),
+            ErrorHintsBuilder::default().build().unwrap(),
+        );
+    }
+
+    eprintln!("Config test passed with {} warning(s)", report.warnings);
+}
+
+/// Config test report, counts problems.
+#[derive(Default)]
+struct Report {
+    errors: usize,
+    warnings: usize,
+}
+
+impl Report {
+    /// Report passed check.
+    fn ok(&self, msg: String) {
+        eprintln!("{} {}", highlight_info("ok:"), msg);
+    }
+
+    /// Report warning with fix.
+    fn warn(&mut self, msg: String, fix: &str) {
+        self.warnings += 1;
+        print_warning(format!("{msg}\n  -> {fix}"));
+    }
+
+    /// Report error with fix.
+    fn error(&mut self, msg: String, fix: &str) {
+        self.errors += 1;
+        print_error_msg(format!("{msg}\n  -> {fix}"));
+    }
+}
+
+/// Check whether the start command binary exists.
+fn check_command(config: &Config, report: &mut Report) {
+    let program = match ConfigServer::command_args(config).and_then(|a| a.into_iter().next()) {
+        Some(program) => program,
+        None => {
+            report.error(
+                "Server command is empty or invalid".into(),
+                "set 'server.command' in the config file",
+            );
+            return;
+        }
+    };
+
+    match find_program(config, &program) {
+        Some(path) => report.ok(format!("Server command binary found: {}", path.display())),
+        None => report.error(
+            format!("Server command binary not found: {program}"),
+            "install it, add it to PATH, or use an absolute path in 'server.command'",
+        ),
+    }
+}
+
+/// Find program as it would be invoked, in server directory or PATH.
+fn find_program(config: &Config, program: &str) -> Option<PathBuf> {
+    // On Windows, the executable extension may be omitted
+    let with_extensions = |path: PathBuf| {
+        if path.is_file() {
+            return Some(path);
+        }
+        let exe = path.with_extension("exe");
+        (cfg!(windows) && exe.is_file()).then_some(exe)
+    };
+
+    // Paths are relative to the server directory
+    let path = Path::new(program);
+    if path.components().count() > 1 {
+        let path = match ConfigServer::server_directory(config) {
+            Some(dir) => dir.join(path),
+            None => path.to_path_buf(),
+        };
+        return with_extensions(path);
+    }
+
+    env::split_paths(&env::var_os("PATH")?).find_map(|dir| with_extensions(dir.join(program)))
+}
+
+/// Check whether the server directory exists and is writable.
+fn check_directory(config: &Config, report: &mut Report) {
+    let dir = match ConfigServer::server_directory(config) {
+        Some(dir) => dir,
+        None => {
+            report.warn(
+                "Server directory not set, server.properties and other files are not managed"
+                    .into(),
+                "set 'server.directory' in the config file",
+            );
+            return;
+        }
+    };
+
+    if !dir.is_dir() {
+        report.error(
+            format!("Server directory does not exist: {}", dir.display()),
+            "create it, or change 'server.directory' in the config file",
+        );
+        return;
+    }
+
+    let test_file = dir.join(WRITE_TEST_FILE);
+    match fs::write(&test_file, b"") {
+        Ok(_) => {
+            let _ = fs::remove_file(&test_file);
+            report.ok(format!("Server directory is writable: {}", dir.display()));
+        }
+        Err(err) => report.error(
+            format!(
+                "Server directory is not writable: {} ({err})",
+                dir.display()
+            ),
+            "fix the directory permissions for the user running lazymc",
+        ),
+    }
+}
+
+/// Check whether public, server and RCON ports don't collide.
+fn check_ports(config: &Config, report: &mut Report) {
+    let public = config.public.address;
+    let server = config.server.address;
+
+    if collides(public, server) {
+        report.error(
+            format!("Public address {public} collides with server address {server}"),
+            "use a different port for 'public.address' and 'server.address'",
+        );
+    } else {
+        report.ok(format!(
+            "Public address {public} and server address {server} don't collide"
+        ));
+    }
+
+    if config.rcon.enabled {
+        let rcon = SocketAddr::new(server.ip(), config.rcon.port);
+        if collides(public, rcon) || rcon == server {
+            report.error(
+                format!(
+                    "RCON port {} collides with public or server address",
+                    config.rcon.port
+                ),
+                "change 'rcon.port' in the config file",
+            );
+        }
+    }
+}
+
+/// Check whether two addresses would bind the same port.
+fn collides(a: SocketAddr, b: SocketAddr) -> bool {
+    a.port() == b.port() && (a.ip() == b.ip() || a.ip().is_unspecified() || b.ip().is_unspecified())
+}
+
+/// Check whether the configured version matches a known protocol version.
+fn check_protocol(config: &Config, report: &mut Report) {
+    let (version, protocol) = (&config.public.version, config.public.protocol);
+
+    match proto::PROTO_VERSIONS.iter().find(|(v, _)| v == version) {
+        Some((_, known)) if *known == protocol => {
+            report.ok(format!("Minecraft {version} uses protocol {protocol}"))
+        }
+        Some((_, known)) => report.warn(
+            format!("Minecraft {version} uses protocol {known}, but 'public.protocol' is {protocol}"),
+            "set 'public.protocol' to match your server version, see docs/protocol-version.md",
+        ),
+        None if proto::PROTO_VERSIONS.iter().any(|(_, p)| *p == protocol) => report.warn(
+            format!("Unknown Minecraft version '{version}' for protocol {protocol}"),
+            "set 'public.version' to match your server version",
+        ),
+        None => report.warn(
+            format!("Unknown Minecraft version '{version}' and protocol {protocol}"),
+            "set 'public.version' and 'public.protocol' to match your server version, see docs/protocol-version.md",
+        ),
+    }
+}
+
+/// Check whether RCON is reachable and the password is accepted.
+///
+/// Only possible if the server is running, this is skipped otherwise.
+#[cfg(feature = "rcon")]
+fn check_rcon(config: &Config, report: &mut Report) {
+    use std::time::Duration;
+
+    use crate::mc::rcon::Rcon;
+
+    if !config.rcon.enabled {
+        return;
+    }
+    if config.rcon.randomize_password {
+        report.ok("RCON password is randomized on start, not testing connection".into());
+        return;
+    }
+
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(_) => return,
+    };
+    let result = runtime.block_on(tokio::time::timeout(
+        Duration::from_secs(5),
+        Rcon::connect_config(config),
+    ));
+
+    match result {
+        Ok(Ok(_)) => report.ok("RCON connection and password work".into()),
+        Ok(Err(err)) if err.downcast_ref::<rust_rcon::Error>().map(|err| matches!(err, rust_rcon::Error::Auth)).unwrap_or(false) => {
+            report.error(
+                "RCON password rejected by server".into(),
+                "make 'rcon.password' in the config file match server.properties, or enable 'advanced.rewrite_server_properties'",
+            )
+        }
+        Ok(Err(err)) => report.warn(
+            format!("Could not connect to RCON, skipping test: {err}"),
+            "start the server to test RCON, or check 'rcon.port'",
+        ),
+        Err(_) => report.warn(
+            "Timed out connecting to RCON, skipping test".into(),
+            "start the server to test RCON, or check 'rcon.port'",
+        ),
+    }
 }
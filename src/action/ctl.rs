@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use clap::ArgMatches;
+use serde_json::Value;
+
+use crate::config::{self, Config, Control};
+use crate::service::control;
+use crate::util::cli::parse_duration;
+use crate::util::error::{quit_error, quit_error_msg, ErrorHintsBuilder};
+
+/// Timeout for control socket queries.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Invoke ctl command.
+///
+/// Sends a command to a running lazymc instance over its control socket.
+pub fn invoke(matches: &ArgMatches) {
+    let config = config::load(matches);
+
+    let command = match matches.subcommand() {
+        Some(("maintenance", matches)) => match matches.get_one::<String>("mode") {
+            Some(mode) => format!("maintenance {mode}"),
+            None => "maintenance".into(),
+        },
+        Some(("keep-awake", matches)) => {
+            let duration = matches.get_one::<String>("duration");
+            match duration.map(String::as_str) {
+                None => "keep-awake".into(),
+                Some("cancel") => "keep-awake cancel".into(),
+                Some(duration) => match parse_duration(duration) {
+                    Some(seconds) => format!("keep-awake {seconds}"),
+                    None => quit_error_msg(
+                        format!("Invalid duration: {duration}"),
+                        ErrorHintsBuilder::default()
+                            .add_info("use a number with unit, such as '30m' or '3h'".into())
+                            .build()
+                            .unwrap(),
+                    ),
+                },
+            }
+        }
+        Some(("switch", _)) => "switch".into(),
+        _ => unreachable!(),
+    };
+
+    let response = query(&config, &command);
+    println!("{response}");
+}
+
+/// Send command to running lazymc instance over its control socket, return response.
+///
+/// Quits with an error if the control socket can't be reached or responds with an error.
+pub fn query(config: &Config, command: &str) -> Value {
+    let path = Control::socket_path(config);
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to build tokio runtime");
+    let result = runtime.block_on(tokio::time::timeout(TIMEOUT, control::send(&path, command)));
+
+    let response = match result {
+        Ok(Ok(response)) => response,
+        Ok(Err(err)) => quit_error(
+            anyhow!(err).context(format!(
+                "Failed to query lazymc over control socket {}",
+                path.display()
+            )),
+            ErrorHintsBuilder::default()
+                .add_info("make sure lazymc is running, and 'control.enabled' is 'true'".into())
+                .build()
+                .unwrap(),
+        ),
+        Err(_) => quit_error(
+            anyhow!("timed out"),
+            ErrorHintsBuilder::default().build().unwrap(),
+        ),
+    };
+
+    if let Some(err) = response["error"].as_str() {
+        quit_error_msg(
+            err.to_string(),
+            ErrorHintsBuilder::default().build().unwrap(),
+        );
+    }
+
+    response
+}
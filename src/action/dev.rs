@@ -0,0 +1,145 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::BytesMut;
+use clap::ArgMatches;
+use minecraft_protocol::decoder::Decoder;
+use minecraft_protocol::version::v1_14_4::handshake::Handshake;
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time;
+
+use crate::proto::action;
+use crate::proto::client::{Client, ClientState};
+use crate::proto::packet::{self, RawPacket};
+use crate::proto::packets;
+use crate::proto::{PROTO_DEFAULT_PROTOCOL, PROTO_DEFAULT_VERSION};
+use crate::types;
+use crate::util::error::{quit_error, ErrorHints};
+
+/// Kick message for players joining the mock server.
+const MOCK_KICK_MESSAGE: &str = "This is a lazymc mock server, joining is not supported.";
+
+/// Mock server options.
+#[derive(Debug)]
+struct MockServer {
+    /// Address to listen on.
+    address: SocketAddr,
+
+    /// Time in seconds before the server starts accepting connections.
+    start_time: u64,
+
+    /// Reported version name.
+    version: String,
+
+    /// Reported protocol version.
+    protocol: u32,
+
+    /// Reported number of online players.
+    players: u32,
+
+    /// Reported maximum number of players.
+    max_players: u32,
+
+    /// Reported MOTD.
+    motd: String,
+}
+
+/// Invoke development command.
+pub fn invoke(matches: &ArgMatches) -> Result<(), ()> {
+    if let Some(matches) = matches.subcommand_matches("mock-server") {
+        let mock = MockServer {
+            address: *matches.get_one("address").unwrap(),
+            start_time: *matches.get_one("start-time").unwrap(),
+            version: matches
+                .get_one::<String>("version-name")
+                .cloned()
+                .unwrap_or_else(|| PROTO_DEFAULT_VERSION.into()),
+            protocol: matches
+                .get_one("protocol")
+                .copied()
+                .unwrap_or(PROTO_DEFAULT_PROTOCOL),
+            players: *matches.get_one("players").unwrap(),
+            max_players: *matches.get_one("max-players").unwrap(),
+            motd: matches.get_one::<String>("motd").unwrap().clone(),
+        };
+        return mock_server(Arc::new(mock));
+    }
+
+    unreachable!();
+}
+
+/// Run mock Minecraft server.
+///
+/// Emulates a slow starting server, only accepting connections after the start time. Responds to
+/// status and ping requests, and kicks joining players.
+#[tokio::main(flavor = "current_thread")]
+async fn mock_server(mock: Arc<MockServer>) -> Result<(), ()> {
+    info!(target: "lazymc::mock", "Starting mock server, ready in {}s...", mock.start_time);
+    time::sleep(Duration::from_secs(mock.start_time)).await;
+
+    let listener = match TcpListener::bind(mock.address).await {
+        Ok(listener) => listener,
+        Err(err) => quit_error(
+            anyhow!(err).context(format!("Failed to bind mock server to {}", mock.address)),
+            ErrorHints::default(),
+        ),
+    };
+    info!(target: "lazymc::mock", "Mock server ready on {}", mock.address);
+
+    loop {
+        let (inbound, peer) = listener.accept().await.map_err(|_| ())?;
+        let mock = mock.clone();
+        tokio::spawn(async move {
+            if serve(inbound, peer, &mock).await.is_err() {
+                debug!(target: "lazymc::mock", "Closed connection from {} with error", peer);
+            }
+        });
+    }
+}
+
+/// Serve a single client connection.
+async fn serve(mut inbound: TcpStream, peer: SocketAddr, mock: &MockServer) -> Result<(), ()> {
+    let client = Client::new(peer);
+    let (mut reader, mut writer) = inbound.split();
+    let mut buf = BytesMut::new();
+
+    loop {
+        let (packet, raw) = match packet::read_packet(&client, &mut buf, &mut reader).await? {
+            Some(packet) => packet,
+            None => return Ok(()),
+        };
+
+        match (client.state(), packet.id) {
+            (ClientState::Handshake, packets::handshake::SERVER_HANDSHAKE) => {
+                let handshake = Handshake::decode(&mut packet.data.as_slice()).map_err(|_| ())?;
+                let state = ClientState::from_id(handshake.next_state).ok_or(())?;
+                client.set_state(state);
+            }
+            (ClientState::Status, packets::status::SERVER_STATUS) => {
+                let status = json!({
+                    "version": { "name": mock.version, "protocol": mock.protocol },
+                    "players": { "online": mock.players, "max": mock.max_players, "sample": [] },
+                    "description": { "text": mock.motd },
+                })
+                .to_string();
+                let mut data = types::encode_var_int(status.len() as i32)?;
+                data.extend_from_slice(status.as_bytes());
+                let response = RawPacket::new(packets::status::CLIENT_STATUS, data)
+                    .encode_with_len(&client)?;
+                writer.write_all(&response).await.map_err(|_| ())?;
+            }
+            (ClientState::Status, packets::status::SERVER_PING) => {
+                writer.write_all(&raw).await.map_err(|_| ())?;
+            }
+            (ClientState::Login, packets::login::SERVER_LOGIN_START) => {
+                info!(target: "lazymc::mock", "Kicking player joining mock server");
+                action::kick(&client, MOCK_KICK_MESSAGE, &mut writer).await?;
+                return Ok(());
+            }
+            _ => return Err(()),
+        }
+    }
+}
@@ -1,3 +1,16 @@
+pub mod backup;
+pub mod bench;
 pub mod config_generate;
+pub mod config_schema;
 pub mod config_test;
+#[cfg(unix)]
+pub mod ctl;
+pub mod dev;
+pub mod report;
+#[cfg(windows)]
+pub mod service;
 pub mod start;
+pub mod stats;
+#[cfg(unix)]
+pub mod status;
+pub mod wake_link;
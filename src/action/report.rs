@@ -0,0 +1,80 @@
+use std::fs;
+use std::io;
+
+use clap::ArgMatches;
+
+use crate::config::{self, Stats};
+use crate::stats;
+use crate::util::cli::{format_duration, parse_duration};
+use crate::util::error::{quit_error, quit_error_msg, ErrorHintsBuilder};
+
+/// Invoke report command.
+///
+/// Prints uptime and occupancy report over the chosen period.
+pub fn invoke(matches: &ArgMatches) {
+    let config = config::load(matches);
+    let path = Stats::file_path(&config);
+
+    let period = matches.get_one::<String>("period").unwrap();
+    let seconds = match parse_duration(period) {
+        Some(seconds) => seconds,
+        None => quit_error_msg(
+            format!("Invalid period: {period}"),
+            ErrorHintsBuilder::default()
+                .add_info("use a number with unit, such as '12h', '7d' or '4w'".into())
+                .build()
+                .unwrap(),
+        ),
+    };
+
+    let data = match fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            eprintln!("No stats recorded yet at {}", path.display());
+            if !config.stats.enabled {
+                eprintln!("Stats are disabled, set 'stats.enabled' to 'true' in the config file");
+            }
+            return;
+        }
+        Err(err) => quit_error(
+            anyhow!(err).context(format!("Failed to read stats from {}", path.display())),
+            ErrorHintsBuilder::default().build().unwrap(),
+        ),
+    };
+
+    let until = stats::now();
+    let report = stats::report(&data, until.saturating_sub(seconds), until);
+    let percent = |part: u64| part as f64 / seconds.max(1) as f64 * 100.0;
+
+    println!("Report for the last {period}:");
+    println!(
+        "  Awake:            {} ({:.1}%)",
+        format_duration(report.awake),
+        percent(report.awake)
+    );
+    println!(
+        "  Asleep:           {} ({:.1}%)",
+        format_duration(report.asleep),
+        percent(report.asleep)
+    );
+    println!("  Wakes:            {}", report.wakes);
+    println!(
+        "  Average start:    {}",
+        report
+            .average_start()
+            .map(format_duration)
+            .unwrap_or_else(|| "-".into())
+    );
+    println!("  Empty but awake:  {}", format_duration(report.empty));
+    match config.stats.server_watts {
+        Some(watts) => println!(
+            "  Energy saved:     {:.1} kWh (at {} W)",
+            report.asleep as f64 / 3600.0 * watts / 1000.0,
+            watts
+        ),
+        None => println!(
+            "  CPU time saved:   {} (set 'stats.server_watts' to estimate energy)",
+            format_duration(report.asleep)
+        ),
+    }
+}
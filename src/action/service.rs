@@ -0,0 +1,202 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::ArgMatches;
+use once_cell::sync::OnceCell;
+use windows_service::service::{
+    ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode,
+    ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{
+    self, ServiceControlHandlerResult, ServiceStatusHandle,
+};
+use windows_service::service_dispatcher;
+use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+
+use crate::action;
+use crate::service::signal;
+use crate::util::error::{quit_error, ErrorHints};
+
+/// Service type, runs in its own process.
+const SERVICE_TYPE: ServiceType = ServiceType::OWN_PROCESS;
+
+/// Time to report to the service manager for pending state changes.
+const PENDING_WAIT_HINT: Duration = Duration::from_secs(30);
+
+/// Name of the service.
+static NAME: OnceCell<String> = OnceCell::new();
+
+/// CLI arguments lazymc was invoked with, used when the service manager starts the service.
+static MATCHES: OnceCell<ArgMatches> = OnceCell::new();
+
+/// Handle to report service status with, set when running as service.
+static STATUS_HANDLE: OnceCell<ServiceStatusHandle> = OnceCell::new();
+
+windows_service::define_windows_service!(ffi_service_main, service_main);
+
+/// Invoke service command.
+pub fn invoke(matches: &ArgMatches) -> Result<(), ()> {
+    let name = matches.get_one::<String>("name").unwrap();
+
+    if let Some(matches) = matches.subcommand_matches("install") {
+        install(name, matches);
+        return Ok(());
+    }
+
+    if matches.subcommand_matches("uninstall").is_some() {
+        uninstall(name);
+        return Ok(());
+    }
+
+    if let Some(matches) = matches.subcommand_matches("run") {
+        let _ = NAME.set(name.clone());
+        let _ = MATCHES.set(matches.clone());
+        if let Err(err) = service_dispatcher::start(name, ffi_service_main) {
+            quit_error(
+                anyhow!(err).context(
+                    "Failed to run as Windows service, it must be started by the service manager",
+                ),
+                ErrorHints::default(),
+            );
+        }
+        return Ok(());
+    }
+
+    unreachable!();
+}
+
+/// Register lazymc as Windows service.
+fn install(name: &str, matches: &ArgMatches) {
+    // Service must use absolute config path, it runs in a different working directory
+    let mut config = PathBuf::from(matches.get_one::<String>("config").unwrap());
+    if let Ok(path) = config.canonicalize() {
+        config = path;
+    }
+
+    let manager = ServiceManager::local_computer(
+        None::<&str>,
+        ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+    )
+    .unwrap_or_else(|err| {
+        quit_error(
+            anyhow!(err).context("Failed to connect to service manager, run as administrator"),
+            ErrorHints::default(),
+        )
+    });
+    let executable_path = std::env::current_exe().unwrap_or_else(|err| {
+        quit_error(
+            anyhow!(err).context("Failed to determine lazymc executable path"),
+            ErrorHints::default(),
+        )
+    });
+
+    let info = ServiceInfo {
+        name: OsString::from(name),
+        display_name: OsString::from(format!("lazymc ({name})")),
+        service_type: SERVICE_TYPE,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path,
+        launch_arguments: vec![
+            "service".into(),
+            "--name".into(),
+            name.into(),
+            "run".into(),
+            "--config".into(),
+            config.into(),
+        ],
+        dependencies: vec![],
+        account_name: None,
+        account_password: None,
+    };
+    if let Err(err) = manager.create_service(&info, ServiceAccess::QUERY_STATUS) {
+        quit_error(
+            anyhow!(err).context("Failed to install Windows service"),
+            ErrorHints::default(),
+        );
+    }
+
+    eprintln!("Installed Windows service '{name}', start it with: sc start {name}");
+}
+
+/// Unregister lazymc Windows service.
+fn uninstall(name: &str) {
+    let result = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+        .and_then(|manager| manager.open_service(name, ServiceAccess::DELETE))
+        .and_then(|service| service.delete());
+    if let Err(err) = result {
+        quit_error(
+            anyhow!(err).context("Failed to uninstall Windows service"),
+            ErrorHints::default(),
+        );
+    }
+
+    eprintln!("Uninstalled Windows service '{name}'");
+}
+
+/// Service entrypoint, invoked by the service manager.
+fn service_main(_arguments: Vec<OsString>) {
+    let handler = |control| match control {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            set_status(ServiceState::StopPending);
+            signal::request_shutdown();
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    };
+
+    let name = NAME.get().map(String::as_str).unwrap_or(crate_name!());
+    let handle = match service_control_handler::register(name, handler) {
+        Ok(handle) => handle,
+        Err(err) => {
+            error!(target: "lazymc", "Failed to register service control handler: {}", err);
+            return;
+        }
+    };
+    let _ = STATUS_HANDLE.set(handle);
+    set_status(ServiceState::Running);
+
+    // Run lazymc as usual, blocks until it quits
+    if let Some(matches) = MATCHES.get() {
+        let _ = action::start::invoke(matches);
+    }
+
+    report_stopped();
+}
+
+/// Report to the service manager that the service stopped, if running as service.
+pub fn report_stopped() {
+    set_status(ServiceState::Stopped);
+}
+
+/// Report service state to the service manager, if running as service.
+fn set_status(state: ServiceState) {
+    let handle = match STATUS_HANDLE.get() {
+        Some(handle) => handle,
+        None => return,
+    };
+
+    let controls_accepted = match state {
+        ServiceState::Running => ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+        _ => ServiceControlAccept::empty(),
+    };
+    let wait_hint = match state {
+        ServiceState::StopPending | ServiceState::StartPending => PENDING_WAIT_HINT,
+        _ => Duration::default(),
+    };
+
+    let result = handle.set_service_status(ServiceStatus {
+        service_type: SERVICE_TYPE,
+        current_state: state,
+        controls_accepted,
+        exit_code: ServiceExitCode::Win32(0),
+        checkpoint: 0,
+        wait_hint,
+        process_id: None,
+    });
+    if let Err(err) = result {
+        warn!(target: "lazymc", "Failed to report service status: {}", err);
+    }
+}
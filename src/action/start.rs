@@ -1,12 +1,19 @@
-use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 
 use clap::ArgMatches;
 
-use crate::config::{self, Config, Server as ConfigServer};
+use crate::config;
+#[cfg(feature = "rcon")]
+use crate::config::Config;
+use crate::daemon;
+use crate::logger;
 use crate::mc::server_properties;
-use crate::proto;
+use crate::proto::debug;
 use crate::service;
+use crate::util::error::{quit_error, ErrorHints};
+#[cfg(not(unix))]
+use crate::util::error::{quit_error_msg, ErrorHintsBuilder};
 
 /// RCON randomized password length.
 #[cfg(feature = "rcon")]
@@ -15,21 +22,74 @@ const RCON_PASSWORD_LENGTH: usize = 32;
 /// Start lazymc.
 pub fn invoke(matches: &ArgMatches) -> Result<(), ()> {
     // Load config
-    #[allow(unused_mut)]
     let mut config = config::load(matches);
+    logger::configure(&config);
+
+    // Run in background, write PID file
+    if matches.get_flag("daemon") {
+        daemonize(matches);
+    }
+    if let Some(path) = matches.get_one::<String>("pid-file") {
+        if let Err(err) = daemon::write_pid_file(Path::new(path)) {
+            quit_error(
+                anyhow!(err).context(format!("Failed to write PID file: {path}")),
+                ErrorHints::default(),
+            );
+        }
+    }
+
+    // Enable packet debugging
+    if let Some(path) = matches.get_one::<String>("debug-packets") {
+        let path = Some(Path::new(path)).filter(|path| !path.as_os_str().is_empty());
+        if let Err(err) = debug::init(path) {
+            quit_error(
+                anyhow!(err).context("Failed to open packet debug file"),
+                ErrorHints::default(),
+            );
+        }
+        warn!(target: "lazymc::packets", "Packet debugging enabled, this may log player details");
+    }
+
+    // Only log decisions in dry run mode
+    config.dry_run = matches.get_flag("dry-run");
+    if config.dry_run {
+        warn!(target: "lazymc", "Dry run, not starting, stopping, signaling or killing the server");
+    }
 
     // Prepare RCON if enabled
     #[cfg(feature = "rcon")]
     prepare_rcon(&mut config);
 
     // Rewrite server server.properties file
-    rewrite_server_properties(&config);
+    if !config.dry_run {
+        server_properties::rewrite_config(&config);
+    }
 
     // Start server service
     let config = Arc::new(config);
     service::server::service(config)
 }
 
+/// Detach lazymc to run in the background.
+///
+/// Quits with an error if not supported.
+#[allow(unused_variables)]
+fn daemonize(matches: &ArgMatches) {
+    #[cfg(unix)]
+    if let Err(err) = daemon::daemonize(matches.get_one::<String>("log-file").map(Path::new)) {
+        quit_error(
+            anyhow!(err).context("Failed to run in background"),
+            ErrorHints::default(),
+        );
+    }
+
+    #[cfg(not(unix))]
+    quit_error_msg(
+        "Running in background is only supported on Unix",
+        ErrorHintsBuilder::default().build().unwrap(),
+    );
+}
+
 /// Prepare RCON.
 #[cfg(feature = "rcon")]
 fn prepare_rcon(config: &mut Config) {
@@ -111,55 +171,3 @@ fn generate_random_password() -> String {
         .take(RCON_PASSWORD_LENGTH)
         .collect()
 }
-
-/// Rewrite server server.properties file with correct internal IP and port.
-fn rewrite_server_properties(config: &Config) {
-    // Rewrite must be enabled
-    if !config.advanced.rewrite_server_properties {
-        return;
-    }
-
-    // Ensure server directory is set, it must exist
-    let dir = match ConfigServer::server_directory(config) {
-        Some(dir) => dir,
-        None => {
-            warn!(target: "lazymc", "Not rewriting {} file, server directory not configured (server.directory)", server_properties::FILE);
-            return;
-        }
-    };
-
-    // Build list of changes
-    #[allow(unused_mut)]
-    let mut changes = HashMap::from([
-        ("server-ip", config.server.address.ip().to_string()),
-        ("server-port", config.server.address.port().to_string()),
-        ("enable-status", "true".into()),
-        ("query.port", config.server.address.port().to_string()),
-    ]);
-
-    // If connecting to server over non-loopback address, disable proxy blocking
-    if !config.server.address.ip().is_loopback() {
-        changes.extend([("prevent-proxy-connections", "false".into())]);
-    }
-
-    // Update network compression threshold for lobby mode
-    if config.join.methods.contains(&config::Method::Lobby) {
-        changes.extend([(
-            "network-compression-threshold",
-            proto::COMPRESSION_THRESHOLD.to_string(),
-        )]);
-    }
-
-    // Add RCON configuration
-    #[cfg(feature = "rcon")]
-    if config.rcon.enabled {
-        changes.extend([
-            ("rcon.port", config.rcon.port.to_string()),
-            ("rcon.password", config.rcon.password.clone()),
-            ("enable-rcon", "true".into()),
-        ]);
-    }
-
-    // Rewrite file
-    server_properties::rewrite_dir(dir, changes)
-}
@@ -0,0 +1,64 @@
+use std::fs;
+use std::io;
+
+use chrono::{Local, TimeZone};
+use clap::ArgMatches;
+
+use crate::config::{self, Stats};
+use crate::stats;
+use crate::util::error::{quit_error, ErrorHintsBuilder};
+
+/// Invoke stats command.
+///
+/// Prints recorded per-player statistics.
+pub fn invoke(matches: &ArgMatches) {
+    let config = config::load(matches);
+    let path = Stats::file_path(&config);
+
+    let data = match fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            eprintln!("No stats recorded yet at {}", path.display());
+            if !config.stats.enabled {
+                eprintln!("Stats are disabled, set 'stats.enabled' to 'true' in the config file");
+            }
+            return;
+        }
+        Err(err) => quit_error(
+            anyhow!(err).context(format!("Failed to read stats from {}", path.display())),
+            ErrorHintsBuilder::default().build().unwrap(),
+        ),
+    };
+
+    let players = stats::summarize(&data);
+    if matches.get_flag("json") {
+        println!("{}", serde_json::to_string_pretty(&players).unwrap());
+        return;
+    }
+
+    println!(
+        "{:<16} {:>8} {:>10} {:>6} {:>10}  LAST SEEN",
+        "PLAYER", "SESSIONS", "PLAYTIME", "WAKES", "TRAFFIC"
+    );
+    for (name, player) in players {
+        let last_seen = Local
+            .timestamp_opt(player.last_seen as i64, 0)
+            .single()
+            .filter(|_| player.last_seen > 0)
+            .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "-".into());
+        println!(
+            "{:<16} {:>8} {:>10} {:>6} {:>9}M  {}",
+            name,
+            player.sessions,
+            format!(
+                "{}h{:02}m",
+                player.playtime / 3600,
+                player.playtime / 60 % 60
+            ),
+            player.wakes,
+            player.bytes / 1024 / 1024,
+            last_seen,
+        );
+    }
+}
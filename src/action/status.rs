@@ -0,0 +1,80 @@
+use clap::ArgMatches;
+use serde_json::Value;
+
+use crate::action::ctl;
+use crate::config;
+use crate::util::cli::format_duration;
+
+/// Invoke status command.
+///
+/// Queries a running lazymc instance over its control socket, and prints a status snapshot.
+pub fn invoke(matches: &ArgMatches) {
+    let config = config::load(matches);
+    let status = ctl::query(&config, "status");
+
+    if matches.get_flag("json") {
+        println!("{status}");
+        return;
+    }
+
+    let state = status["state"].as_str().unwrap_or("unknown");
+    match status["state_for"].as_u64() {
+        Some(secs) => println!("State:        {state} (for {})", format_duration(secs)),
+        None => println!("State:        {state}"),
+    }
+    println!("PID:          {}", or_dash(&status["pid"]));
+    println!(
+        "Brand:        {}",
+        status["brand"].as_str().unwrap_or("unknown")
+    );
+    match (
+        status["players"]["online"].as_u64(),
+        status["players"]["max"].as_u64(),
+    ) {
+        (Some(online), Some(max)) => println!("Players:      {online}/{max}"),
+        _ => println!("Players:      -"),
+    }
+    match (
+        status["resources"]["memory"].as_u64(),
+        status["resources"]["cpu"].as_f64(),
+    ) {
+        (Some(memory), Some(cpu)) => println!(
+            "Resources:    {}MB memory, {cpu:.1}% CPU",
+            memory / 1024 / 1024
+        ),
+        _ => println!("Resources:    -"),
+    }
+    match status["sleep_in"].as_u64() {
+        Some(secs) => println!("Sleeps in:    {}", format_duration(secs)),
+        None => println!("Sleeps in:    -"),
+    }
+    if let Some(secs) = status["keep_awake"].as_u64() {
+        println!("Keep awake:   {} left", format_duration(secs));
+    }
+    if let Some(time) = status["backup"]["time"].as_str() {
+        let result = match (
+            status["backup"]["success"].as_bool(),
+            status["backup"]["uploaded"].as_bool(),
+        ) {
+            (Some(true), Some(false)) => "succeeded, upload failed",
+            (Some(true), _) => "succeeded",
+            _ => "failed",
+        };
+        println!("Last backup:  {time} ({result})");
+    }
+    if status["maintenance"] == true {
+        println!("Maintenance:  enabled");
+    }
+    println!(
+        "Last error:   {}",
+        status["last_error"].as_str().unwrap_or("-")
+    );
+}
+
+/// Show JSON value, or dash if null.
+fn or_dash(value: &Value) -> String {
+    match value {
+        Value::Null => "-".into(),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,40 @@
+use clap::ArgMatches;
+
+use crate::config;
+use crate::util::cli::parse_duration;
+use crate::util::error::{quit_error_msg, ErrorHintsBuilder};
+use crate::wake_link;
+
+/// Invoke wake link command.
+///
+/// Prints a signed wake link URL.
+pub fn invoke(matches: &ArgMatches) {
+    let config = config::load(matches);
+
+    let expires = matches.get_one::<String>("expires").unwrap();
+    let ttl = match parse_duration(expires) {
+        Some(ttl) => ttl,
+        None => quit_error_msg(
+            format!("Invalid duration: {expires}"),
+            ErrorHintsBuilder::default()
+                .add_info("use a number with unit, such as '30m' or '24h'".into())
+                .build()
+                .unwrap(),
+        ),
+    };
+
+    if !config.http.enabled {
+        eprintln!("Warning: HTTP endpoints are disabled, wake links won't work until 'http.enabled' is 'true'");
+    }
+
+    match wake_link::url(&config, ttl) {
+        Some(url) => println!("{url}"),
+        None => quit_error_msg(
+            "Wake links are disabled",
+            ErrorHintsBuilder::default()
+                .add_info("set 'wake_links.secret' in the config file".into())
+                .build()
+                .unwrap(),
+        ),
+    }
+}
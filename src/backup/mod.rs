@@ -0,0 +1,351 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use tokio::time;
+
+use crate::config::{BackupFailure, Config, Server as ConfigServer};
+use crate::server::Server;
+use crate::util;
+
+#[cfg(unix)]
+pub mod restore;
+pub mod s3;
+
+/// Backup archive file name prefix.
+const ARCHIVE_PREFIX: &str = "backup-";
+
+/// Backup archive file name suffix.
+const ARCHIVE_SUFFIX: &str = ".tar.gz";
+
+/// Status of the last backup.
+#[derive(Debug, Clone)]
+pub struct BackupStatus {
+    /// Time the backup completed at.
+    pub time: DateTime<Local>,
+
+    /// Whether the backup succeeded.
+    pub success: bool,
+
+    /// Path of created archive, if any.
+    pub archive: Option<PathBuf>,
+
+    /// Whether the archive was uploaded, if enabled.
+    pub uploaded: Option<bool>,
+}
+
+/// Run backups before the server goes to sleep.
+///
+/// Returns `true` if the server may be put to sleep, `false` if it should stay online based on the
+/// configured failure policy.
+pub async fn before_sleep(config: &Config, server: &Server) -> bool {
+    let mut success = true;
+
+    // Run backup command if configured
+    if let Some(command) = &config.backup.command {
+        if !command.trim().is_empty() {
+            success &= run_command(config, command).await;
+        }
+    }
+
+    // Archive worlds
+    let mut archive = None;
+    if config.backup.archive && success {
+        archive = archive_worlds(config, server).await;
+        success &= archive.is_some();
+    }
+
+    // Upload archive, failure does not prevent sleeping
+    let mut uploaded = None;
+    if let (true, Some(path)) = (config.backup.s3.enabled, &archive) {
+        uploaded = Some(upload_archive(config, path).await);
+    }
+
+    // Remember backup status
+    server
+        .set_backup_status(BackupStatus {
+            time: Local::now(),
+            success,
+            archive,
+            uploaded,
+        })
+        .await;
+
+    if success {
+        return true;
+    }
+
+    match config.backup.on_failure {
+        BackupFailure::Sleep => {
+            warn!(target: "lazymc::backup", "Backup failed, putting server to sleep anyway");
+            true
+        }
+        BackupFailure::Stay => {
+            warn!(target: "lazymc::backup", "Backup failed, keeping server online");
+            false
+        }
+    }
+}
+
+/// Run backup command, wait for it to complete.
+///
+/// Returns `true` if it completed successfully.
+async fn run_command(config: &Config, command: &str) -> bool {
+    info!(target: "lazymc::backup", "Running backup command before sleeping...");
+
+    let args = match ConfigServer::resolve_command(config, command) {
+        Some(args) => args,
+        None => {
+            error!(target: "lazymc::backup", "Invalid backup command: {}", command);
+            return false;
+        }
+    };
+    let dir = ConfigServer::server_directory(config);
+    let timeout = Duration::from_secs(config.backup.timeout as u64);
+    match util::command::run(&args, dir.as_deref(), timeout).await {
+        Ok(()) => {
+            info!(target: "lazymc::backup", "Backup completed");
+            true
+        }
+        Err(err) => {
+            error!(target: "lazymc::backup", "Backup failed, {}", err);
+            false
+        }
+    }
+}
+
+/// Archive configured world directories.
+///
+/// Flushes and pauses world saving through RCON while archiving if enabled.
+///
+/// Returns path to created archive on success.
+#[allow(unused_variables)]
+async fn archive_worlds(config: &Config, server: &Server) -> Option<PathBuf> {
+    // Server directory must be known
+    let dir = match ConfigServer::server_directory(config) {
+        Some(dir) => dir,
+        None => {
+            error!(target: "lazymc::backup", "Cannot archive worlds, server directory not configured (server.directory)");
+            return None;
+        }
+    };
+
+    info!(target: "lazymc::backup", "Archiving worlds before sleeping...");
+
+    // Flush and pause world saving
+    #[cfg(feature = "rcon")]
+    let paused = config.rcon.enabled
+        && server
+            .rcon_vanilla_command(config, "save-off")
+            .await
+            .is_ok()
+        && server
+            .rcon_vanilla_command(config, "save-all flush")
+            .await
+            .is_ok();
+    #[cfg(not(feature = "rcon"))]
+    let paused = false;
+    if !paused {
+        warn!(target: "lazymc::backup", "Could not pause world saving through RCON, archive may be inconsistent");
+    }
+
+    // Create archive on blocking thread
+    let worlds = config.backup.worlds.clone();
+    let archive_dir = dir.join(&config.backup.directory);
+    let keep = config.backup.keep;
+    let timeout = Duration::from_secs(config.backup.timeout as u64);
+    let cancel = Arc::new(AtomicBool::new(false));
+    let mut task = tokio::task::spawn_blocking({
+        let cancel = cancel.clone();
+        move || {
+            let path = create_archive(&dir, &worlds, &archive_dir, &cancel)?;
+            prune_archives(&archive_dir, keep)?;
+            Ok::<_, io::Error>(path)
+        }
+    });
+
+    // Cancel archiving on timeout, wait for it to stop before resuming world saving
+    let result = match time::timeout(timeout, &mut task).await {
+        Ok(result) => result,
+        Err(_) => {
+            error!(target: "lazymc::backup", "Archiving worlds timed out after {}s, cancelling", timeout.as_secs());
+            cancel.store(true, Ordering::Relaxed);
+            let _ = task.await;
+            Ok(Err(io::Error::new(io::ErrorKind::TimedOut, "timed out")))
+        }
+    };
+    let result = match result {
+        Ok(Ok(path)) => {
+            info!(target: "lazymc::backup", "Archived worlds to {}", path.display());
+            Some(path)
+        }
+        Ok(Err(err)) => {
+            error!(target: "lazymc::backup", "Failed to archive worlds: {}", err);
+            None
+        }
+        Err(err) => {
+            error!(target: "lazymc::backup", "Failed to archive worlds, task failed: {}", err);
+            None
+        }
+    };
+
+    // Resume world saving
+    #[cfg(feature = "rcon")]
+    if paused
+        && server
+            .rcon_vanilla_command(config, "save-on")
+            .await
+            .is_err()
+    {
+        warn!(target: "lazymc::backup", "Failed to resume world saving through RCON");
+    }
+
+    result
+}
+
+/// Upload archive to S3-compatible storage.
+///
+/// Returns `true` on success.
+async fn upload_archive(config: &Config, path: &Path) -> bool {
+    info!(target: "lazymc::backup", "Uploading backup archive...");
+
+    let timeout = Duration::from_secs(config.backup.timeout as u64);
+    match time::timeout(timeout, s3::upload(&config.backup.s3, path)).await {
+        Ok(Ok(())) => {
+            info!(target: "lazymc::backup", "Uploaded backup archive");
+            true
+        }
+        Ok(Err(err)) => {
+            error!(target: "lazymc::backup", "Failed to upload backup archive: {}", err);
+            false
+        }
+        Err(_) => {
+            error!(target: "lazymc::backup", "Uploading backup archive timed out after {}s", timeout.as_secs());
+            false
+        }
+    }
+}
+
+/// Create a new archive of the given world directories.
+///
+/// Stops with an error when `cancel` is set, the partial archive is removed.
+///
+/// Returns path to the created archive.
+fn create_archive(
+    dir: &Path,
+    worlds: &[PathBuf],
+    archive_dir: &Path,
+    cancel: &AtomicBool,
+) -> io::Result<PathBuf> {
+    fs::create_dir_all(archive_dir)?;
+
+    let path = archive_dir.join(format!(
+        "{}{}{}",
+        ARCHIVE_PREFIX,
+        Local::now().format("%Y%m%d-%H%M%S"),
+        ARCHIVE_SUFFIX,
+    ));
+
+    let result = write_archive(dir, worlds, &path, cancel);
+    if result.is_err() {
+        let _ = fs::remove_file(&path);
+    }
+    result.map(|_| path)
+}
+
+/// Write archive of the given world directories to the given file.
+fn write_archive(
+    dir: &Path,
+    worlds: &[PathBuf],
+    path: &Path,
+    cancel: &AtomicBool,
+) -> io::Result<()> {
+    let file = CancelWriter {
+        inner: File::create(path)?,
+        cancel,
+    };
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    for world in worlds {
+        let world_dir = dir.join(world);
+        if !world_dir.is_dir() {
+            debug!(target: "lazymc::backup", "Not archiving world, directory does not exist: {}", world_dir.display());
+            continue;
+        }
+        archive.append_dir_all(world, world_dir)?;
+    }
+    archive.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// Writer that fails once cancelled, to abort writing an archive.
+struct CancelWriter<'a, W> {
+    inner: W,
+    cancel: &'a AtomicBool,
+}
+
+impl<W: Write> Write for CancelWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Err(io::Error::new(io::ErrorKind::Other, "cancelled"));
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Directory archives are stored in.
+///
+/// Returns `None` if the server directory is not configured.
+pub fn archive_dir(config: &Config) -> Option<PathBuf> {
+    ConfigServer::server_directory(config).map(|dir| dir.join(&config.backup.directory))
+}
+
+/// Get backup ID of the given archive, its timestamp.
+pub fn archive_id(path: &Path) -> Option<&str> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix(ARCHIVE_PREFIX)?
+        .strip_suffix(ARCHIVE_SUFFIX)
+}
+
+/// List archives in the given directory, oldest first.
+pub fn list_archives(archive_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    if !archive_dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut archives: Vec<PathBuf> = fs::read_dir(archive_dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.starts_with(ARCHIVE_PREFIX) && name.ends_with(ARCHIVE_SUFFIX))
+                .unwrap_or(false)
+        })
+        .collect();
+
+    // Names contain timestamp, sort chronologically
+    archives.sort();
+    Ok(archives)
+}
+
+/// Remove old archives, keeping the given number of newest archives.
+fn prune_archives(archive_dir: &Path, keep: usize) -> io::Result<()> {
+    let archives = list_archives(archive_dir)?;
+    let remove = archives.len().saturating_sub(keep);
+    for path in archives.into_iter().take(remove) {
+        debug!(target: "lazymc::backup", "Removing old backup archive {}", path.display());
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,235 @@
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use flate2::read::GzDecoder;
+use tokio::time;
+
+use crate::config::{Config, Server as ConfigServer};
+use crate::server::{Server, State};
+
+use super::{archive_id, list_archives, ARCHIVE_PREFIX, ARCHIVE_SUFFIX};
+
+/// Suffix for world directories moved aside while restoring.
+const ASIDE_SUFFIX: &str = ".pre-restore";
+
+/// Whether a restore is in progress.
+static RESTORING: AtomicBool = AtomicBool::new(false);
+
+/// Whether a backup is being restored.
+///
+/// The server must not be started while restoring, the world may be partially extracted.
+pub fn restoring() -> bool {
+    RESTORING.load(Ordering::Relaxed)
+}
+
+/// Find backup archive by ID, or the newest archive for `latest`.
+pub fn find_archive(config: &Config, id: &str) -> Result<PathBuf, String> {
+    let archive_dir =
+        super::archive_dir(config).ok_or("server directory not configured (server.directory)")?;
+
+    if id == "latest" {
+        return list_archives(&archive_dir)
+            .map_err(|err| format!("failed to list backup archives: {err}"))?
+            .pop()
+            .ok_or_else(|| "no backup archives".into());
+    }
+
+    // IDs are timestamps, don't allow escaping archive directory
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit() || c == '-') {
+        return Err(format!("invalid backup ID: {id}"));
+    }
+    let path = archive_dir.join(format!("{ARCHIVE_PREFIX}{id}{ARCHIVE_SUFFIX}"));
+    if !path.is_file() {
+        return Err(format!("backup not found: {id}"));
+    }
+    Ok(path)
+}
+
+/// Restore backup archive into the world directories.
+///
+/// Stops the server if it is running, and starts it again afterwards. Maintenance mode is enabled
+/// while restoring so players can't wake the server, and the server refuses to start at all.
+pub async fn restore(
+    config: Arc<Config>,
+    server: Arc<Server>,
+    archive: PathBuf,
+) -> Result<(), String> {
+    let id = archive_id(&archive).unwrap_or_default().to_string();
+
+    // Only log in dry run
+    if config.dry_run {
+        info!(target: "lazymc::audit", "Dry run, would restore backup {}", id);
+        return Ok(());
+    }
+
+    if RESTORING.swap(true, Ordering::Relaxed) {
+        return Err("already restoring a backup".into());
+    }
+
+    info!(target: "lazymc::audit", "Restoring backup {}...", id);
+
+    let maintenance = server.maintenance();
+    server.set_maintenance(true);
+    let result = restore_stopped(&config, &server, &archive).await;
+    server.set_maintenance(maintenance);
+    RESTORING.store(false, Ordering::Relaxed);
+
+    match result {
+        Ok(restart) => {
+            info!(target: "lazymc::audit", "Restored backup {}", id);
+            if restart {
+                Server::start(config, server, None).await;
+            }
+            Ok(())
+        }
+        Err(err) => {
+            error!(target: "lazymc::audit", "Failed to restore backup {}: {}", id, err);
+            Err(err)
+        }
+    }
+}
+
+/// Stop the server and restore the archive.
+///
+/// Returns whether the server was running and should be started again.
+async fn restore_stopped(config: &Config, server: &Server, archive: &Path) -> Result<bool, String> {
+    let dir = ConfigServer::server_directory(config)
+        .ok_or("server directory not configured (server.directory)")?;
+    let timeout = Duration::from_secs(config.backup.timeout as u64);
+
+    // Stop server, also when frozen, wait for it to stop
+    let stopped = async {
+        let state = server
+            .wait_for_state(|state| state != State::Starting)
+            .await;
+        if state == State::Started {
+            info!(target: "lazymc::backup", "Stopping server to restore backup...");
+            server.stop(config).await;
+        }
+        server.wait_for_state(|state| state == State::Stopped).await;
+        if server.stop_frozen(config).await {
+            server.wait_for_state(|state| state == State::Stopped).await;
+        }
+        state == State::Started
+    };
+    let restart = time::timeout(timeout, stopped)
+        .await
+        .map_err(|_| format!("server did not stop within {}s", timeout.as_secs()))?;
+
+    // Extract archive on blocking thread
+    let worlds = config.backup.worlds.clone();
+    let archive = archive.to_path_buf();
+    let cancel = Arc::new(AtomicBool::new(false));
+    let mut task = tokio::task::spawn_blocking({
+        let cancel = cancel.clone();
+        move || extract_archive(&dir, &worlds, &archive, &cancel)
+    });
+
+    // Cancel extracting on timeout, wait for it to move the current worlds back
+    let result = match time::timeout(timeout, &mut task).await {
+        Ok(result) => result,
+        Err(_) => {
+            error!(target: "lazymc::backup", "Extracting archive timed out after {}s, cancelling", timeout.as_secs());
+            cancel.store(true, Ordering::Relaxed);
+            task.await
+        }
+    };
+    match result {
+        Ok(Ok(())) => Ok(restart),
+        Ok(Err(err)) => Err(format!("failed to extract archive: {err}")),
+        Err(err) => Err(format!("extract task failed: {err}")),
+    }
+}
+
+/// Extract archive into server directory, replacing the given world directories.
+///
+/// Current worlds are moved aside first, and moved back if extracting fails. Stops with an error
+/// when `cancel` is set.
+fn extract_archive(
+    dir: &Path,
+    worlds: &[PathBuf],
+    archive: &Path,
+    cancel: &AtomicBool,
+) -> io::Result<()> {
+    let mut aside = vec![];
+    let result = move_aside(dir, worlds, &mut aside).and_then(|_| {
+        let file = CancelReader {
+            inner: File::open(archive)?,
+            cancel,
+        };
+        tar::Archive::new(GzDecoder::new(file)).unpack(dir)
+    });
+
+    // Remove worlds moved aside, or move them back if restoring failed, continue on errors
+    let mut failed = vec![];
+    for (path, aside_path) in aside {
+        if result.is_ok() {
+            if let Err(err) = fs::remove_dir_all(&aside_path) {
+                warn!(target: "lazymc::backup", "Failed to remove previous world at {}: {}", aside_path.display(), err);
+            }
+            continue;
+        }
+
+        let moved = if path.exists() {
+            fs::remove_dir_all(&path)
+        } else {
+            Ok(())
+        };
+        if let Err(err) = moved.and_then(|_| fs::rename(&aside_path, &path)) {
+            error!(target: "lazymc::backup", "Failed to move back world {}, it is kept at {}: {}", path.display(), aside_path.display(), err);
+            failed.push(path.display().to_string());
+        }
+    }
+
+    match result {
+        Err(err) if !failed.is_empty() => Err(io::Error::new(
+            err.kind(),
+            format!("{err}, failed to move back worlds: {}", failed.join(", ")),
+        )),
+        result => result,
+    }
+}
+
+/// Move the given world directories in the server directory aside.
+///
+/// Moved directories are added to `aside` as original and aside path.
+fn move_aside(
+    dir: &Path,
+    worlds: &[PathBuf],
+    aside: &mut Vec<(PathBuf, PathBuf)>,
+) -> io::Result<()> {
+    for world in worlds {
+        let path = dir.join(world);
+        if !path.is_dir() {
+            continue;
+        }
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(ASIDE_SUFFIX);
+        let aside_path = path.with_file_name(name);
+        if aside_path.exists() {
+            fs::remove_dir_all(&aside_path)?;
+        }
+        fs::rename(&path, &aside_path)?;
+        aside.push((path, aside_path));
+    }
+    Ok(())
+}
+
+/// Reader that fails once cancelled, to abort extracting an archive.
+struct CancelReader<'a, R> {
+    inner: R,
+    cancel: &'a AtomicBool,
+}
+
+impl<R: Read> Read for CancelReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Err(io::Error::new(io::ErrorKind::Other, "cancelled"));
+        }
+        self.inner.read(buf)
+    }
+}
@@ -0,0 +1,243 @@
+use std::error::Error;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::header::CONTENT_LENGTH;
+use reqwest::{Method, RequestBuilder, Url};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::config::BackupS3;
+use crate::util::hex;
+
+/// Payload hash value for unsigned payloads.
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// Signed headers, sorted and lowercase.
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+/// Upload archive to S3-compatible storage, then apply retention.
+pub async fn upload(s3: &BackupS3, path: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or("invalid archive file name")?;
+    let key = format!("{}{}", s3.prefix, name);
+
+    // Upload file
+    let file = tokio::fs::File::open(path).await?;
+    let len = file.metadata().await?.len();
+    let response = request(s3, Method::PUT, &key, &[])?
+        .header(CONTENT_LENGTH, len)
+        .body(file)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(format!("upload failed with status {}", response.status()).into());
+    }
+    debug!(target: "lazymc::backup", "Uploaded backup archive to {}/{}", s3.bucket, key);
+
+    // Apply retention
+    if s3.keep > 0 {
+        prune(s3).await?;
+    }
+
+    Ok(())
+}
+
+/// Remove old archives from bucket, keeping configured number of newest archives.
+async fn prune(s3: &BackupS3) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut keys = list(s3).await?;
+    keys.retain(|key| {
+        let name = key.strip_prefix(&s3.prefix).unwrap_or(key);
+        name.starts_with(super::ARCHIVE_PREFIX) && name.ends_with(super::ARCHIVE_SUFFIX)
+    });
+
+    // Names contain timestamp, sort chronologically
+    keys.sort();
+    let remove = keys.len().saturating_sub(s3.keep);
+    for key in keys.into_iter().take(remove) {
+        debug!(target: "lazymc::backup", "Removing old backup archive {}/{}", s3.bucket, key);
+        let response = request(s3, Method::DELETE, &key, &[])?.send().await?;
+        if !response.status().is_success() {
+            return Err(format!("delete failed with status {}", response.status()).into());
+        }
+    }
+
+    Ok(())
+}
+
+/// ListObjectsV2 response.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListBucketResult {
+    /// Listed objects.
+    #[serde(default)]
+    contents: Vec<Object>,
+
+    /// Whether more objects are available.
+    #[serde(default)]
+    is_truncated: bool,
+
+    /// Token to list the next page with, if truncated.
+    next_continuation_token: Option<String>,
+}
+
+/// Listed object.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Object {
+    /// Object key.
+    key: String,
+}
+
+/// List object keys in bucket with configured prefix.
+///
+/// Follows continuation tokens to list all pages.
+async fn list(s3: &BackupS3) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+    let mut keys = vec![];
+    let mut token: Option<String> = None;
+
+    loop {
+        let mut query = vec![("list-type", "2"), ("prefix", s3.prefix.as_str())];
+        if let Some(token) = &token {
+            query.push(("continuation-token", token.as_str()));
+        }
+        let response = request(s3, Method::GET, "", &query)?.send().await?;
+        if !response.status().is_success() {
+            return Err(format!("listing failed with status {}", response.status()).into());
+        }
+        let body = response.text().await?;
+        let result: ListBucketResult = quick_xml::de::from_str(&body)?;
+        keys.extend(result.contents.into_iter().map(|object| object.key));
+
+        match result.next_continuation_token {
+            Some(next) if result.is_truncated => token = Some(next),
+            _ => break,
+        }
+    }
+
+    Ok(keys)
+}
+
+/// Build signed S3 request for the given object key.
+///
+/// Uses path-style addressing, signed with AWS signature version 4.
+fn request(
+    s3: &BackupS3,
+    method: Method,
+    key: &str,
+    query: &[(&str, &str)],
+) -> Result<RequestBuilder, Box<dyn Error + Send + Sync>> {
+    let endpoint = Url::parse(&s3.endpoint)?;
+    let host = match (endpoint.host_str(), endpoint.port()) {
+        (Some(host), Some(port)) => format!("{host}:{port}"),
+        (Some(host), None) => host.to_string(),
+        (None, _) => return Err("invalid S3 endpoint, no host".into()),
+    };
+
+    // Canonical URI and query, the endpoint may have a base path
+    let base = endpoint
+        .path_segments()
+        .into_iter()
+        .flatten()
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| format!("/{segment}"))
+        .collect::<String>();
+    let uri = if key.is_empty() {
+        format!("{base}/{}", uri_encode(&s3.bucket, false))
+    } else {
+        format!(
+            "{base}/{}/{}",
+            uri_encode(&s3.bucket, false),
+            uri_encode(key, true)
+        )
+    };
+    let mut query: Vec<(String, String)> = query
+        .iter()
+        .map(|(k, v)| (uri_encode(k, false), uri_encode(v, false)))
+        .collect();
+    query.sort();
+    let query = query
+        .into_iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    // Sign request
+    let now = Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let authorization = authorization(s3, &method, &host, &uri, &query, &now);
+
+    // Use encoded path and query as signed
+    let mut url = endpoint.clone();
+    url.set_path(&uri);
+    url.set_query((!query.is_empty()).then_some(query.as_str()));
+
+    Ok(reqwest::Client::new()
+        .request(method, url)
+        .header("host", host)
+        .header("x-amz-content-sha256", UNSIGNED_PAYLOAD)
+        .header("x-amz-date", amz_date)
+        .header("authorization", authorization))
+}
+
+/// Build AWS signature version 4 authorization header value.
+fn authorization(
+    s3: &BackupS3,
+    method: &Method,
+    host: &str,
+    uri: &str,
+    query: &str,
+    now: &DateTime<Utc>,
+) -> String {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let scope = format!("{}/{}/s3/aws4_request", date, s3.region);
+
+    let canonical_request = format!(
+        "{method}\n{uri}\n{query}\nhost:{host}\nx-amz-content-sha256:{UNSIGNED_PAYLOAD}\nx-amz-date:{amz_date}\n\n{SIGNED_HEADERS}\n{UNSIGNED_PAYLOAD}"
+    );
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes())),
+    );
+
+    // Derive signing key, sign
+    let key = hmac(format!("AWS4{}", s3.secret_key).as_bytes(), date.as_bytes());
+    let key = hmac(&key, s3.region.as_bytes());
+    let key = hmac(&key, b"s3");
+    let key = hmac(&key, b"aws4_request");
+    let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        s3.access_key, scope, SIGNED_HEADERS, signature,
+    )
+}
+
+/// HMAC-SHA256 the given data with key.
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// URI encode string as specified by AWS.
+///
+/// Slashes are kept as-is if `path` is true.
+fn uri_encode(input: &str, path: bool) -> String {
+    input
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            b'/' if path => "/".into(),
+            b => format!("%{b:02X}"),
+        })
+        .collect()
+}
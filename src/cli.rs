@@ -1,8 +1,120 @@
-use clap::{Arg, Command};
+use std::env;
+use std::net::SocketAddr;
+
+use clap::{Arg, ArgAction, Command};
+
+use crate::action;
+use crate::logger;
+
+/// Default log level if none is set.
+const LOG_DEFAULT: &str = "info";
+
+/// Run lazymc command line interface.
+///
+/// Parses arguments from the environment and invokes the intended action.
+pub fn run() -> Result<(), ()> {
+    // Initialize logger
+    init_log();
+
+    // Build clap app, invoke intended action
+    invoke_action(app())
+}
+
+/// Initialize logger.
+fn init_log() {
+    // Load .env variables
+    let _ = dotenv::dotenv();
+
+    // Set default log level if none is set
+    if env::var("RUST_LOG").is_err() {
+        env::set_var("RUST_LOG", LOG_DEFAULT);
+    }
+
+    // Initialize logger
+    logger::init();
+}
+
+/// Invoke an action.
+fn invoke_action(app: Command) -> Result<(), ()> {
+    let matches = app.get_matches();
+
+    // Config operations
+    if let Some(matches) = matches.subcommand_matches("config") {
+        if let Some(matches) = matches.subcommand_matches("generate") {
+            action::config_generate::invoke(matches);
+            return Ok(());
+        }
+
+        if let Some(matches) = matches.subcommand_matches("test") {
+            action::config_test::invoke(matches);
+            return Ok(());
+        }
+
+        if matches.subcommand_matches("schema").is_some() {
+            action::config_schema::invoke();
+            return Ok(());
+        }
+
+        unreachable!();
+    }
+
+    // Stats and reports
+    if let Some(matches) = matches.subcommand_matches("stats") {
+        action::stats::invoke(matches);
+        return Ok(());
+    }
+    if let Some(matches) = matches.subcommand_matches("report") {
+        action::report::invoke(matches);
+        return Ok(());
+    }
+
+    // Wake links
+    if let Some(matches) = matches.subcommand_matches("wake-link") {
+        action::wake_link::invoke(matches);
+        return Ok(());
+    }
+
+    // Status and control over control socket
+    #[cfg(unix)]
+    if let Some(matches) = matches.subcommand_matches("status") {
+        action::status::invoke(matches);
+        return Ok(());
+    }
+    #[cfg(unix)]
+    if let Some(matches) = matches.subcommand_matches("ctl") {
+        action::ctl::invoke(matches);
+        return Ok(());
+    }
+
+    // Backups
+    if let Some(matches) = matches.subcommand_matches("backup") {
+        action::backup::invoke(matches);
+        return Ok(());
+    }
+
+    // Proxy benchmark
+    if let Some(matches) = matches.subcommand_matches("bench") {
+        return action::bench::invoke(matches);
+    }
+
+    // Development tools
+    if let Some(matches) = matches.subcommand_matches("dev") {
+        return action::dev::invoke(matches);
+    }
+
+    // Windows service operations
+    #[cfg(windows)]
+    if let Some(matches) = matches.subcommand_matches("service") {
+        return action::service::invoke(matches);
+    }
+
+    // Start server
+    action::start::invoke(&matches)
+}
 
 /// The clap app for CLI argument parsing.
 pub fn app() -> Command {
-    Command::new(crate_name!())
+    let app = Command::new(crate_name!())
         .version(crate_version!())
         .author(crate_authors!())
         .about(crate_description!())
@@ -20,9 +132,48 @@ pub fn app() -> Command {
                 .subcommand(
                     Command::new("generate")
                         .alias("gen")
-                        .about("Generate config"),
+                        .about("Generate config")
+                        .arg(
+                            Arg::new("template")
+                                .long("template")
+                                .action(ArgAction::SetTrue)
+                                .help("Write default template, don't ask questions"),
+                        ),
                 )
-                .subcommand(Command::new("test").about("Test config")),
+                .subcommand(Command::new("test").about("Test config"))
+                .subcommand(Command::new("schema").about("Print config JSON Schema")),
+        )
+        .subcommand(
+            Command::new("stats").about("Show player statistics").arg(
+                Arg::new("json")
+                    .long("json")
+                    .action(ArgAction::SetTrue)
+                    .help("Output as JSON"),
+            ),
+        )
+        .subcommand(
+            Command::new("report")
+                .about("Show uptime and occupancy report")
+                .arg(
+                    Arg::new("period")
+                        .long("period")
+                        .short('p')
+                        .value_name("PERIOD")
+                        .default_value("7d")
+                        .help("Period to report over, such as 24h, 7d or 4w"),
+                ),
+        )
+        .subcommand(
+            Command::new("wake-link")
+                .about("Generate one-time link to wake server over HTTP")
+                .arg(
+                    Arg::new("expires")
+                        .long("expires")
+                        .short('e')
+                        .value_name("DURATION")
+                        .default_value("24h")
+                        .help("Time until link expires, such as 30m or 24h"),
+                ),
         )
         .arg(
             Arg::new("config")
@@ -35,4 +186,246 @@ pub fn app() -> Command {
                 .help("Use config file")
                 .num_args(1),
         )
+        .arg(
+            Arg::new("daemon")
+                .short('d')
+                .long("daemon")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Run in background (Unix only)"),
+        )
+        .arg(
+            Arg::new("pid-file")
+                .long("pid-file")
+                .global(true)
+                .value_name("FILE")
+                .help("Write process ID to file")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Log decisions without starting, stopping, signaling or killing the server"),
+        )
+        .arg(
+            Arg::new("debug-packets")
+                .long("debug-packets")
+                .global(true)
+                .value_name("FILE")
+                .num_args(0..=1)
+                .default_missing_value("")
+                .help("Log decoded handshake, status and login packets, optionally write to file"),
+        )
+        .arg(
+            Arg::new("log-file")
+                .long("log-file")
+                .global(true)
+                .value_name("FILE")
+                .help("Redirect output to file when running in background")
+                .num_args(1),
+        );
+
+    // Status and control over control socket
+    #[cfg(unix)]
+    let app = app
+        .subcommand(
+            Command::new("status")
+                .about("Show status of running lazymc instance")
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .action(ArgAction::SetTrue)
+                        .help("Output as JSON"),
+                ),
+        )
+        .subcommand(
+            Command::new("ctl")
+                .about("Control running lazymc instance")
+                .arg_required_else_help(true)
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("maintenance")
+                        .about("Show or toggle maintenance mode")
+                        .arg(
+                            Arg::new("mode")
+                                .value_name("MODE")
+                                .value_parser(["on", "off"])
+                                .help("Enable or disable maintenance mode"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("keep-awake")
+                        .about("Keep server awake regardless of occupancy, or cancel")
+                        .arg(
+                            Arg::new("duration")
+                                .value_name("DURATION")
+                                .help("Duration such as 3h, or 'cancel'"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("switch").about("Switch to the other blue/green server instance"),
+                ),
+        );
+
+    // Backups, restoring goes through the control socket
+    let backup = Command::new("backup")
+        .about("List or restore world backup archives")
+        .arg_required_else_help(true)
+        .subcommand_required(true)
+        .subcommand(Command::new("list").about("List backup archives"));
+    #[cfg(unix)]
+    let backup = backup.subcommand(
+        Command::new("restore")
+            .about("Restore backup archive, stops and restarts the server if running")
+            .arg(
+                Arg::new("id")
+                    .value_name("ID")
+                    .required(true)
+                    .help("Backup ID as shown by 'backup list', or 'latest'"),
+            )
+            .arg(
+                Arg::new("yes")
+                    .long("yes")
+                    .short('y')
+                    .action(ArgAction::SetTrue)
+                    .help("Don't ask for confirmation"),
+            ),
+    );
+    let app = app.subcommand(backup);
+
+    // Proxy benchmark
+    let app = app.subcommand(
+        Command::new("bench")
+            .about("Benchmark proxy throughput and latency with synthetic connections")
+            .arg(
+                Arg::new("connections")
+                    .long("connections")
+                    .short('n')
+                    .value_name("COUNT")
+                    .value_parser(value_parser!(usize))
+                    .default_value("8")
+                    .help("Number of concurrent connections"),
+            )
+            .arg(
+                Arg::new("size")
+                    .long("size")
+                    .value_name("MIB")
+                    .value_parser(value_parser!(u64))
+                    .default_value("64")
+                    .help("MiB to send through each connection"),
+            )
+            .arg(
+                Arg::new("pings")
+                    .long("pings")
+                    .value_name("COUNT")
+                    .value_parser(value_parser!(usize))
+                    .default_value("100")
+                    .help("Round trips per connection to measure latency"),
+            )
+            .arg(
+                Arg::new("buffer-size")
+                    .long("buffer-size")
+                    .value_name("BYTES")
+                    .value_parser(value_parser!(usize))
+                    .help("Relay buffer size, overrides 'proxy.buffer_size'"),
+            )
+            .arg(
+                Arg::new("acceptors")
+                    .long("acceptors")
+                    .value_name("COUNT")
+                    .value_parser(value_parser!(usize))
+                    .help("Number of acceptors, overrides 'public.acceptors'"),
+            ),
+    );
+
+    // Development tools
+    let app = app.subcommand(
+        Command::new("dev")
+            .about("Development tools")
+            .arg_required_else_help(true)
+            .subcommand_required(true)
+            .subcommand(
+                Command::new("mock-server")
+                    .about("Run mock Minecraft server that starts slowly, for testing")
+                    .arg(
+                        Arg::new("address")
+                            .long("address")
+                            .value_name("ADDRESS")
+                            .value_parser(value_parser!(SocketAddr))
+                            .default_value("127.0.0.1:25566")
+                            .help("Address to listen on"),
+                    )
+                    .arg(
+                        Arg::new("start-time")
+                            .long("start-time")
+                            .value_name("SECONDS")
+                            .value_parser(value_parser!(u64))
+                            .default_value("10")
+                            .help("Seconds before accepting connections"),
+                    )
+                    .arg(
+                        Arg::new("version-name")
+                            .long("version-name")
+                            .value_name("NAME")
+                            .help("Reported version name"),
+                    )
+                    .arg(
+                        Arg::new("protocol")
+                            .long("protocol")
+                            .value_name("VERSION")
+                            .value_parser(value_parser!(u32))
+                            .help("Reported protocol version"),
+                    )
+                    .arg(
+                        Arg::new("players")
+                            .long("players")
+                            .value_name("COUNT")
+                            .value_parser(value_parser!(u32))
+                            .default_value("0")
+                            .help("Reported number of online players"),
+                    )
+                    .arg(
+                        Arg::new("max-players")
+                            .long("max-players")
+                            .value_name("COUNT")
+                            .value_parser(value_parser!(u32))
+                            .default_value("20")
+                            .help("Reported maximum number of players"),
+                    )
+                    .arg(
+                        Arg::new("motd")
+                            .long("motd")
+                            .value_name("MOTD")
+                            .default_value("lazymc mock server")
+                            .help("Reported MOTD"),
+                    ),
+            ),
+    );
+
+    // Windows service management
+    #[cfg(windows)]
+    let app = app.subcommand(
+        Command::new("service")
+            .about("Windows service actions")
+            .arg_required_else_help(true)
+            .subcommand_required(true)
+            .arg(
+                Arg::new("name")
+                    .long("name")
+                    .global(true)
+                    .value_name("NAME")
+                    .default_value(crate_name!())
+                    .help("Service name")
+                    .num_args(1),
+            )
+            .subcommand(Command::new("install").about("Install lazymc as Windows service"))
+            .subcommand(Command::new("uninstall").about("Uninstall lazymc Windows service"))
+            .subcommand(
+                Command::new("run").about("Run as Windows service, invoked by service manager"),
+            ),
+    );
+
+    app
 }
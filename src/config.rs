@@ -1,15 +1,21 @@
+use std::collections::{HashMap, VecDeque};
 use std::fs;
-use std::io;
-use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
 
+use chrono::{Local, NaiveTime};
 use clap::ArgMatches;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use version_compare::Cmp;
 
+use crate::error::Error;
+use crate::net::Subnet;
 use crate::proto;
 use crate::util::error::{quit_error, quit_error_msg, ErrorHintsBuilder};
-use crate::util::serde::to_socket_addrs;
+use crate::util::pool::{MessagePool, Rotation};
+use crate::util::schedule::{self, Days, WeeklyTime};
+use crate::util::serde::{to_option_socket_addrs, to_socket_addrs, to_time};
 
 /// Default configuration file location.
 pub const CONFIG_FILE: &str = "lazymc.toml";
@@ -61,7 +67,7 @@ pub fn load(matches: &ArgMatches) -> Config {
 }
 
 /// Configuration.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct Config {
     /// Configuration path if known.
     ///
@@ -69,6 +75,18 @@ pub struct Config {
     #[serde(skip)]
     pub path: Option<PathBuf>,
 
+    /// Only log decisions, don't start, stop, signal or kill the server.
+    ///
+    /// Set through the `--dry-run` flag.
+    #[serde(skip)]
+    pub dry_run: bool,
+
+    /// Config files to include.
+    ///
+    /// Merged into this config when loading, see `load_value`.
+    #[serde(default)]
+    pub include: Vec<String>,
+
     /// Public configuration.
     #[serde(default)]
     pub public: Public,
@@ -80,6 +98,18 @@ pub struct Config {
     #[serde(default)]
     pub time: Time,
 
+    /// Sleep configuration.
+    #[serde(default)]
+    pub sleep: Sleep,
+
+    /// Server driver configuration.
+    #[serde(default)]
+    pub driver: Driver,
+
+    /// Blue/green switching configuration.
+    #[serde(default)]
+    pub blue_green: BlueGreen,
+
     /// MOTD configuration.
     #[serde(default)]
     pub motd: Motd,
@@ -92,10 +122,127 @@ pub struct Config {
     #[serde(default)]
     pub lockout: Lockout,
 
+    /// Maintenance mode.
+    #[serde(default)]
+    pub maintenance: Maintenance,
+
+    /// Stealth mode.
+    #[serde(default)]
+    pub stealth: Stealth,
+
+    /// Scanner honeypot.
+    #[serde(default)]
+    pub honeypot: Honeypot,
+
+    /// Login configuration.
+    #[serde(default)]
+    pub login: Login,
+
     /// RCON configuration.
     #[serde(default)]
     pub rcon: Rcon,
 
+    /// Server monitor configuration.
+    #[serde(default)]
+    pub monitor: Monitor,
+
+    /// Resource monitoring configuration.
+    #[serde(default)]
+    pub resources: Resources,
+
+    /// Hang watchdog configuration.
+    #[serde(default)]
+    pub watchdog: Watchdog,
+
+    /// Diagnostics configuration.
+    #[serde(default)]
+    pub diagnostics: Diagnostics,
+
+    /// Backup configuration.
+    #[serde(default)]
+    pub backup: Backup,
+
+    /// Properties to enforce in server.properties file.
+    #[serde(default)]
+    #[schemars(with = "HashMap<String, serde_json::Value>")]
+    pub server_properties: HashMap<String, toml::Value>,
+
+    /// Server jar updater configuration.
+    #[serde(default)]
+    pub updater: Updater,
+
+    /// Java runtime configuration.
+    #[serde(default)]
+    pub java: Java,
+
+    /// cgroup configuration.
+    #[serde(default)]
+    pub cgroup: Cgroup,
+
+    /// Process scheduling configuration.
+    #[serde(default)]
+    pub process: Process,
+
+    /// Logging configuration.
+    #[serde(default)]
+    pub log: Log,
+
+    /// Telemetry configuration.
+    #[serde(default)]
+    pub telemetry: Telemetry,
+
+    /// Metrics push configuration.
+    #[serde(default)]
+    pub metrics: Metrics,
+
+    /// State file configuration.
+    #[serde(default)]
+    pub state_file: StateFile,
+
+    /// HTTP endpoint configuration.
+    #[serde(default)]
+    pub http: Http,
+
+    /// Wake link configuration.
+    #[serde(default)]
+    pub wake_links: WakeLinks,
+
+    /// gRPC API configuration.
+    #[serde(default)]
+    pub grpc: Grpc,
+
+    /// Discord bot configuration.
+    #[serde(default)]
+    pub discord: Discord,
+
+    /// Telegram bot configuration.
+    #[serde(default)]
+    pub telegram: Telegram,
+
+    /// Plugin configuration.
+    #[serde(default)]
+    pub plugins: Plugins,
+
+    /// Script configuration.
+    #[serde(default)]
+    pub scripts: Scripts,
+
+    /// Proxy configuration.
+    #[serde(default)]
+    pub proxy: Proxy,
+
+    /// Player statistics configuration.
+    #[serde(default)]
+    pub stats: Stats,
+
+    /// Control socket configuration.
+    #[serde(default)]
+    pub control: Control,
+
+    /// Async runtime configuration.
+    #[serde(default)]
+    pub runtime: Runtime,
+
     /// Advanced configuration.
     #[serde(default)]
     pub advanced: Advanced,
@@ -107,9 +254,9 @@ pub struct Config {
 
 impl Config {
     /// Load configuration from file.
-    pub fn load(path: PathBuf) -> Result<Self, io::Error> {
-        let data = fs::read(&path)?;
-        let mut config: Config = toml::from_slice(&data)?;
+    pub fn load(path: PathBuf) -> Result<Self, Error> {
+        let value = load_value(&path, &mut vec![])?;
+        let mut config: Config = value.try_into()?;
 
         // Show warning if config version is problematic
         match &config.config.version {
@@ -130,12 +277,96 @@ impl Config {
     }
 }
 
+/// Load configuration file as TOML value, with included files merged in.
+///
+/// Files listed in `include` are merged first, in order, values in the including file take
+/// precedence. Include paths are relative to the including file and may contain glob patterns.
+fn load_value(path: &Path, stack: &mut Vec<PathBuf>) -> Result<toml::Value, Error> {
+    // Prevent include cycles
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if stack.contains(&canonical) {
+        return Err(Error::Include(format!(
+            "include cycle at {}",
+            path.display()
+        )));
+    }
+
+    let data = fs::read(path)?;
+    let mut value: toml::Value = toml::from_slice(&data)?;
+
+    // Take include list, resolve relative to this file
+    let includes = match value
+        .as_table_mut()
+        .and_then(|table| table.remove("include"))
+    {
+        Some(toml::Value::Array(includes)) => includes,
+        Some(_) => {
+            return Err(Error::Include(format!(
+                "'include' must be a list of paths in {}",
+                path.display()
+            )))
+        }
+        None => return Ok(value),
+    };
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    stack.push(canonical);
+    let mut merged = toml::Value::Table(Default::default());
+    for include in includes {
+        let pattern = include.as_str().ok_or_else(|| {
+            Error::Include(format!(
+                "include path must be a string in {}",
+                path.display()
+            ))
+        })?;
+        let pattern = dir.join(pattern);
+        let paths = glob::glob(&pattern.to_string_lossy())
+            .map_err(|err| Error::Include(format!("invalid include pattern {pattern:?}: {err}")))?;
+
+        // Sort matches for predictable merge order
+        let mut paths: Vec<PathBuf> = paths.filter_map(Result::ok).collect();
+        paths.sort();
+        if paths.is_empty() && !pattern.to_string_lossy().contains(['*', '?', '[']) {
+            return Err(Error::Include(format!(
+                "included file does not exist: {}",
+                pattern.display()
+            )));
+        }
+
+        for path in paths {
+            merge(&mut merged, load_value(&path, stack)?);
+        }
+    }
+    stack.pop();
+
+    merge(&mut merged, value);
+    Ok(merged)
+}
+
+/// Deep merge TOML value into base, tables are merged, other values are replaced.
+fn merge(base: &mut toml::Value, value: toml::Value) {
+    match (base, value) {
+        (toml::Value::Table(base), toml::Value::Table(table)) => {
+            for (key, value) in table {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, value) => *base = value,
+    }
+}
+
 /// Public configuration.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 pub struct Public {
     /// Public address.
     #[serde(deserialize_with = "to_socket_addrs")]
+    #[schemars(with = "String")]
     pub address: SocketAddr,
 
     /// Minecraft protocol version name hint.
@@ -143,6 +374,54 @@ pub struct Public {
 
     /// Minecraft protocol version hint.
     pub protocol: u32,
+
+    /// Number of accept loops, bound with SO_REUSEPORT if more than one.
+    pub acceptors: usize,
+
+    /// Seconds to wait for the handshake from a new client before disconnecting.
+    pub handshake_timeout: u32,
+
+    /// Seconds to wait for each status or login packet from a client before disconnecting.
+    pub request_timeout: u32,
+
+    /// Maximum packet length in bytes accepted from clients before login.
+    pub max_packet_size: usize,
+
+    /// Override `enforcesSecureChat` in status, server value if not set.
+    pub enforces_secure_chat: Option<bool>,
+
+    /// Override `previewsChat` in status, server value if not set.
+    pub previews_chat: Option<bool>,
+
+    /// Unix socket to also accept clients on, bridged to the public address. Unix only.
+    ///
+    /// Use `Public::socket_path()` to get the path relative to the config file.
+    pub socket: Option<PathBuf>,
+}
+
+impl Public {
+    /// Get the public Unix socket path, if configured.
+    pub fn socket_path(config: &Config) -> Option<PathBuf> {
+        let socket = config.public.socket.as_ref()?;
+        match config.path.as_ref().and_then(|p| p.parent()) {
+            Some(config_dir) => Some(config_dir.join(socket)),
+            None => Some(socket.clone()),
+        }
+    }
+
+    /// Get the address to connect to the public listener on locally.
+    ///
+    /// Uses the loopback address if the public address is unspecified.
+    pub fn local_address(config: &Config) -> SocketAddr {
+        let mut addr = config.public.address;
+        if addr.ip().is_unspecified() {
+            match addr {
+                SocketAddr::V4(_) => addr.set_ip(Ipv4Addr::LOCALHOST.into()),
+                SocketAddr::V6(_) => addr.set_ip(Ipv6Addr::LOCALHOST.into()),
+            }
+        }
+        addr
+    }
 }
 
 impl Default for Public {
@@ -151,12 +430,19 @@ impl Default for Public {
             address: "0.0.0.0:25565".parse().unwrap(),
             version: proto::PROTO_DEFAULT_VERSION.to_string(),
             protocol: proto::PROTO_DEFAULT_PROTOCOL,
+            acceptors: 1,
+            handshake_timeout: 5,
+            request_timeout: 10,
+            max_packet_size: 32 * 1024,
+            enforces_secure_chat: None,
+            previews_chat: None,
+            socket: None,
         }
     }
 }
 
 /// Server configuration.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct Server {
     /// Server directory.
     ///
@@ -165,22 +451,68 @@ pub struct Server {
     directory: Option<PathBuf>,
 
     /// Start command.
+    ///
+    /// May contain placeholders, use `Server::command_args()` to resolve them.
     pub command: String,
 
+    /// Server memory, available as `{memory}` placeholder in start command.
+    #[serde(default)]
+    pub memory: Option<String>,
+
+    /// Command to run before starting the server.
+    #[serde(default)]
+    pub pre_start_command: Option<String>,
+
+    /// Command to run after the server process stopped.
+    #[serde(default)]
+    pub post_stop_command: Option<String>,
+
+    /// Timeout in seconds for pre-start and post-stop commands.
+    #[serde(default = "u32_300")]
+    pub command_timeout: u32,
+
+    /// User to run the server process as, name or ID. Unix only.
+    #[serde(default)]
+    pub user: Option<String>,
+
+    /// Group to run the server process as, name or ID. Unix only.
+    #[serde(default)]
+    pub group: Option<String>,
+
     /// Server address.
     #[serde(
         deserialize_with = "to_socket_addrs",
         default = "server_address_default"
     )]
+    #[schemars(with = "String")]
     pub address: SocketAddr,
 
+    /// Server address for status probing and RCON, defaults to `address`.
+    ///
+    /// Use `Server::probe_address()` to get the effective address.
+    #[serde(default, deserialize_with = "to_option_socket_addrs")]
+    #[schemars(with = "Option<String>")]
+    pub probe_address: Option<SocketAddr>,
+
+    /// Unix socket the server listens on, used instead of `address` if set. Unix only.
+    ///
+    /// Use `Server::socket_path()` to get the path relative to the config file.
+    #[serde(default)]
+    pub socket: Option<PathBuf>,
+
     /// Freeze the server process instead of restarting it when no players online, making it start up faster.
-    /// Only works on Unix (Linux or MacOS)
+    /// Only works on Unix (Linux or MacOS) and Windows
     #[serde(default = "bool_true")]
     pub freeze_process: bool,
 
-    /// Immediately wake server when starting lazymc.
+    /// Method to freeze the server process with.
     #[serde(default)]
+    pub freeze_method: FreezeMethod,
+
+    /// Immediately wake server when starting lazymc.
+    ///
+    /// Also accepted as `start_on_boot`. The server follows the normal sleep rules afterwards.
+    #[serde(default, alias = "start_on_boot")]
     pub wake_on_start: bool,
 
     /// Immediately wake server after crash.
@@ -196,12 +528,25 @@ pub struct Server {
     pub forge: bool,
 
     /// Server starting timeout. Force kill server process if it takes longer.
-    #[serde(default = "u32_300")]
-    pub start_timeout: u32,
+    ///
+    /// Defaults to 300 seconds, or more for the detected brand if brand tuning is enabled.
+    #[serde(default)]
+    pub start_timeout: Option<u32>,
 
     /// Server stopping timeout. Force kill server process if it takes longer.
-    #[serde(default = "u32_150")]
-    pub stop_timeout: u32,
+    ///
+    /// Defaults to 150 seconds, or more for the detected brand if brand tuning is enabled.
+    #[serde(default)]
+    pub stop_timeout: Option<u32>,
+
+    /// Tune for the detected server brand, such as raising default timeouts for modded servers
+    /// and using namespaced RCON commands on Bukkit-based servers.
+    #[serde(default = "bool_true")]
+    pub brand_tuning: bool,
+
+    /// Learn start and stop timeouts from observed durations.
+    #[serde(default)]
+    pub adaptive_timeouts: AdaptiveTimeouts,
 
     /// To wake server, user must be in server whitelist if enabled on server.
     #[serde(default = "bool_true")]
@@ -218,9 +563,89 @@ pub struct Server {
     /// Add HAProxy v2 header to proxied connections.
     #[serde(default)]
     pub send_proxy_v2: bool,
+
+    /// Accept the Minecraft EULA by writing eula.txt before starting.
+    #[serde(default)]
+    pub accept_eula: bool,
+}
+
+/// Adaptive start and stop timeout configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct AdaptiveTimeouts {
+    /// Learn timeouts from observed durations, raises static timeouts once learned.
+    pub enabled: bool,
+
+    /// Timeout as multiple of the 95th percentile of observed durations.
+    pub factor: f64,
+
+    /// Minimum learned timeout in seconds.
+    pub min: u32,
+
+    /// Maximum learned timeout in seconds.
+    pub max: u32,
+
+    /// Number of observed durations required before learned timeouts are used.
+    pub samples: usize,
+}
+
+impl Default for AdaptiveTimeouts {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            factor: 2.0,
+            min: 60,
+            max: 1800,
+            samples: 3,
+        }
+    }
+}
+
+impl AdaptiveTimeouts {
+    /// Learn timeout in seconds from observed durations, never below the given static timeout.
+    ///
+    /// Returns `None` if disabled or if not enough durations are observed.
+    pub fn learn(&self, durations: &VecDeque<u64>, timeout: u32) -> Option<u32> {
+        if !self.enabled || durations.is_empty() || durations.len() < self.samples {
+            return None;
+        }
+
+        let mut sorted: Vec<u64> = durations.iter().copied().collect();
+        sorted.sort_unstable();
+        let p95 = sorted[(sorted.len() * 95 / 100).min(sorted.len() - 1)];
+
+        let learned = (p95 as f64 * self.factor).ceil() as u32;
+        Some(learned.clamp(self.min, self.max.max(self.min)).max(timeout))
+    }
 }
 
 impl Server {
+    /// Get the server address to probe status on and to connect to RCON with.
+    ///
+    /// For RCON, the port is replaced with the configured RCON port.
+    pub fn probe_address(config: &Config) -> SocketAddr {
+        config.server.probe_address.unwrap_or(config.server.address)
+    }
+
+    /// Get the configured server start timeout in seconds, or the default.
+    pub fn start_timeout(config: &Config) -> u32 {
+        config.server.start_timeout.unwrap_or_else(u32_300)
+    }
+
+    /// Get the configured server stop timeout in seconds, or the default.
+    pub fn stop_timeout(config: &Config) -> u32 {
+        config.server.stop_timeout.unwrap_or_else(u32_150)
+    }
+
+    /// Get the Unix socket path to connect to the server with, if configured.
+    pub fn socket_path(config: &Config) -> Option<PathBuf> {
+        let socket = config.server.socket.as_ref()?;
+        match config.path.as_ref().and_then(|p| p.parent()) {
+            Some(config_dir) => Some(config_dir.join(socket)),
+            None => Some(socket.clone()),
+        }
+    }
+
     /// Get the server directory.
     ///
     /// This does not check whether it exists.
@@ -231,10 +656,70 @@ impl Server {
             None => config.server.directory.clone(),
         }
     }
+
+    /// Get the start command arguments, with placeholders resolved.
+    ///
+    /// Returns `None` if the command is invalid.
+    pub fn command_args(config: &Config) -> Option<Vec<String>> {
+        Self::resolve_command(config, &config.server.command)
+    }
+
+    /// Split the given command into arguments, with placeholders resolved.
+    ///
+    /// Returns `None` if the command is invalid.
+    pub fn resolve_command(config: &Config, command: &str) -> Option<Vec<String>> {
+        let dir = Self::server_directory(config)
+            .and_then(|dir| dir.canonicalize().ok().or(Some(dir)))
+            .map(|dir| dir.to_string_lossy().into_owned());
+
+        #[allow(unused_mut)]
+        let mut vars = vec![
+            ("{dir}", dir),
+            ("{memory}", config.server.memory.clone()),
+            ("{address}", Some(config.server.address.to_string())),
+            ("{ip}", Some(config.server.address.ip().to_string())),
+            ("{port}", Some(config.server.address.port().to_string())),
+        ];
+        #[cfg(feature = "rcon")]
+        vars.extend([
+            ("{rcon_port}", Some(config.rcon.port.to_string())),
+            ("{rcon_password}", Some(config.rcon.password.clone())),
+        ]);
+
+        // Split first so resolved values never need quoting
+        let args = shlex::split(command)?
+            .into_iter()
+            .map(|mut arg| {
+                for (key, value) in &vars {
+                    if let Some(value) = value {
+                        arg = arg.replace(key, value);
+                    }
+                }
+                arg
+            })
+            .collect::<Vec<_>>();
+
+        (!args.is_empty()).then_some(args)
+    }
+}
+
+/// Method to freeze the server process with.
+#[derive(Debug, Default, Deserialize, JsonSchema, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FreezeMethod {
+    /// Use cgroup freezer if server runs in cgroup, signal otherwise.
+    #[default]
+    Auto,
+
+    /// Send SIGSTOP/SIGCONT signals, or suspend/resume on Windows.
+    Signal,
+
+    /// Use cgroup v2 freezer, pauses the whole process tree atomically. Linux only.
+    Cgroup,
 }
 
 /// Time configuration.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 pub struct Time {
     /// Sleep after number of seconds.
@@ -243,107 +728,481 @@ pub struct Time {
     /// Minimum time in seconds to stay online when server is started.
     #[serde(default, alias = "minimum_online_time")]
     pub min_online_time: u32,
+
+    /// Keep server online for `min_online_time` again each time a player joins.
+    pub keep_online_on_join: bool,
+
+    /// Count `sleep_after` from the moment the last proxied player disconnects.
+    pub sleep_from_disconnect: bool,
+
+    /// Fully stop frozen server after being idle for number of seconds, 0 to disable.
+    pub stop_after: u32,
+
+    /// Times to proactively wake the server at, such as `Fri 18:45`.
+    #[schemars(with = "Vec<String>")]
+    pub prewarm: Vec<WeeklyTime>,
+
+    /// Schedule overriding `sleep_after` at specific days and times, first match is used.
+    pub schedule: Vec<SleepAfterWindow>,
 }
 
-impl Default for Time {
-    fn default() -> Self {
-        Self {
-            sleep_after: 60,
-            min_online_time: 60,
-        }
+impl Time {
+    /// Get the number of seconds to sleep after, as scheduled for the current time.
+    pub fn sleep_after(config: &Config) -> u32 {
+        let now = Local::now();
+        config
+            .time
+            .schedule
+            .iter()
+            .find(|window| schedule::in_window(window.days, window.from, window.to, now))
+            .map(|window| window.sleep_after)
+            .unwrap_or(config.time.sleep_after)
     }
 }
 
-/// MOTD configuration.
-#[derive(Debug, Deserialize)]
-#[serde(default)]
-pub struct Motd {
-    /// MOTD when server is sleeping.
-    pub sleeping: String,
+/// Window overriding `time.sleep_after`.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SleepAfterWindow {
+    /// Days this window applies on, such as `Mon-Fri`, all days if not set.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    pub days: Days,
 
-    /// MOTD when server is starting.
-    pub starting: String,
+    /// Window start time.
+    #[serde(deserialize_with = "to_time")]
+    #[schemars(with = "String")]
+    pub from: NaiveTime,
 
-    /// MOTD when server is stopping.
-    pub stopping: String,
+    /// Window end time, may be before start to wrap past midnight.
+    #[serde(deserialize_with = "to_time")]
+    #[schemars(with = "String")]
+    pub to: NaiveTime,
 
-    /// Use MOTD from Minecraft server once known.
-    pub from_server: bool,
+    /// Sleep after number of seconds within this window.
+    pub sleep_after: u32,
 }
 
-impl Default for Motd {
+impl Default for Time {
     fn default() -> Self {
         Self {
-            sleeping: "☠ Server is sleeping\n§2☻ Join to start it up".into(),
-            starting: "§2☻ Server is starting...\n§7⌛ Please wait...".into(),
-            stopping: "☠ Server going to sleep...\n⌛ Please wait...".into(),
-            from_server: false,
+            sleep_after: 60,
+            min_online_time: 60,
+            keep_online_on_join: false,
+            sleep_from_disconnect: false,
+            stop_after: 0,
+            prewarm: vec![],
+            schedule: vec![],
         }
     }
 }
 
-/// Join method types.
-#[derive(Debug, Deserialize, Copy, Clone, Eq, PartialEq)]
-#[serde(rename_all = "lowercase")]
-pub enum Method {
-    /// Kick client with message.
-    Kick,
-
-    /// Hold client connection until server is ready.
-    Hold,
+/// Sleep configuration.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Sleep {
+    /// Actions to put the server to sleep with, in order.
+    ///
+    /// Use `Sleep::actions()` to include the defaults if none are configured.
+    actions: Vec<SleepAction>,
+}
 
-    /// Forward connection to another host.
-    Forward,
+impl Sleep {
+    /// Get the sleep actions to use, in order.
+    ///
+    /// If none are configured, freezes the server if enabled, or stops it through RCON or signal.
+    pub fn actions(config: &Config) -> Vec<SleepAction> {
+        if !config.sleep.actions.is_empty() {
+            return config.sleep.actions.clone();
+        }
 
-    /// Keep client in temporary fake lobby until server is ready.
-    Lobby,
+        let mut actions = vec![];
+        if config.server.freeze_process {
+            actions.push(SleepActionKind::Freeze);
+        }
+        actions.extend([SleepActionKind::Rcon, SleepActionKind::Signal]);
+        actions
+            .into_iter()
+            .map(|action| SleepAction {
+                action,
+                timeout: 0,
+                command: None,
+                container: None,
+                runtime: string_docker(),
+            })
+            .collect()
+    }
 }
 
-/// Join configuration.
-#[derive(Debug, Deserialize)]
-#[serde(default)]
-pub struct Join {
-    /// Join methods.
-    pub methods: Vec<Method>,
+/// Action to put the server to sleep with.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SleepAction {
+    /// Action kind.
+    pub action: SleepActionKind,
 
-    /// Join kick configuration.
+    /// Seconds to wait for the server to stop before trying the next action, 0 to not wait.
     #[serde(default)]
-    pub kick: JoinKick,
+    pub timeout: u32,
 
-    /// Join hold configuration.
+    /// Command to invoke for hook action.
     #[serde(default)]
-    pub hold: JoinHold,
+    pub command: Option<String>,
 
-    /// Join forward configuration.
+    /// Container to stop for container action.
     #[serde(default)]
-    pub forward: JoinForward,
+    pub container: Option<String>,
 
-    /// Join lobby configuration.
-    #[serde(default)]
-    pub lobby: JoinLobby,
+    /// Container runtime binary for container action.
+    #[serde(default = "string_docker")]
+    pub runtime: String,
 }
 
-impl Default for Join {
-    fn default() -> Self {
-        Self {
-            methods: vec![Method::Hold, Method::Kick],
-            kick: Default::default(),
-            hold: Default::default(),
-            forward: Default::default(),
-            lobby: Default::default(),
-        }
-    }
-}
+/// Sleep action kind.
+#[derive(Debug, Deserialize, JsonSchema, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SleepActionKind {
+    /// Freeze the server, if `server.freeze_process` is enabled and the driver supports it.
+    Freeze,
 
-/// Join kick configuration.
-#[derive(Debug, Deserialize)]
+    /// Stop server through RCON.
+    Rcon,
+
+    /// Stop server through its driver.
+    ///
+    /// For the process driver this sends a SIGTERM signal, or Ctrl+Break on Windows.
+    Signal,
+
+    /// Stop server container through container runtime.
+    Container,
+
+    /// Invoke custom command.
+    Hook,
+}
+
+impl SleepActionKind {
+    /// Action name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Freeze => "freeze",
+            Self::Rcon => "rcon",
+            Self::Signal => "signal",
+            Self::Container => "container",
+            Self::Hook => "hook",
+        }
+    }
+}
+
+/// Server driver configuration.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Driver {
+    /// Driver to start, stop, freeze and kill the server with.
+    pub kind: DriverKind,
+
+    /// Container driver configuration.
+    pub container: ContainerDriver,
+
+    /// systemd driver configuration.
+    pub systemd: SystemdDriver,
+
+    /// Panel driver configuration.
+    pub panel: Panel,
+}
+
+/// Server driver kind.
+#[derive(Debug, Default, Deserialize, JsonSchema, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DriverKind {
+    /// Run the server command as child process.
+    #[default]
+    Process,
+
+    /// Start an existing container through a container runtime.
+    Container,
+
+    /// Start a systemd unit.
+    Systemd,
+
+    /// Start the server through a panel API.
+    Panel,
+}
+
+impl DriverKind {
+    /// Driver name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Process => "process",
+            Self::Container => "container",
+            Self::Systemd => "systemd",
+            Self::Panel => "panel",
+        }
+    }
+}
+
+/// Container driver configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ContainerDriver {
+    /// Container name or ID.
+    pub name: String,
+
+    /// Container runtime binary.
+    pub runtime: String,
+}
+
+impl Default for ContainerDriver {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            runtime: string_docker(),
+        }
+    }
+}
+
+/// systemd driver configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct SystemdDriver {
+    /// Unit name.
+    pub unit: String,
+
+    /// Manage a user unit instead of a system unit.
+    pub user: bool,
+
+    /// Interval in seconds to poll the unit state at while the server runs.
+    pub poll_interval: u32,
+}
+
+impl Default for SystemdDriver {
+    fn default() -> Self {
+        Self {
+            unit: String::new(),
+            user: false,
+            poll_interval: 5,
+        }
+    }
+}
+
+/// Panel driver configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Panel {
+    /// Panel kind.
+    pub kind: PanelKind,
+
+    /// Panel base URL.
+    pub url: String,
+
+    /// Panel API key.
+    pub api_key: String,
+
+    /// Server identifier in the panel.
+    pub server: String,
+
+    /// Interval in seconds to poll the panel power state at while the server runs.
+    pub poll_interval: u32,
+
+    /// Panel API request timeout in seconds.
+    pub timeout: u32,
+}
+
+impl Default for Panel {
+    fn default() -> Self {
+        Self {
+            kind: PanelKind::default(),
+            url: String::new(),
+            api_key: String::new(),
+            server: String::new(),
+            poll_interval: 5,
+            timeout: 10,
+        }
+    }
+}
+
+/// Panel kind.
+#[derive(Debug, Default, Deserialize, JsonSchema, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PanelKind {
+    /// Pterodactyl, using the client API.
+    #[default]
+    Pterodactyl,
+
+    /// Crafty Controller, using the v2 API.
+    Crafty,
+}
+
+/// Blue/green switching configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct BlueGreen {
+    /// Allow switching between the server and a secondary server instance.
+    pub enabled: bool,
+
+    /// Start command of the secondary instance, defaults to `server.command`.
+    pub command: Option<String>,
+
+    /// Server directory of the secondary instance.
+    ///
+    /// Private because you should use `BlueGreen::directory()` instead.
+    directory: Option<PathBuf>,
+
+    /// Address of the secondary instance.
+    #[serde(deserialize_with = "to_socket_addrs")]
+    #[schemars(with = "String")]
+    pub address: SocketAddr,
+
+    /// RCON port of the secondary instance.
+    pub rcon_port: u16,
+
+    /// Seconds to wait for the secondary instance to come online before aborting a switch.
+    pub ready_timeout: u32,
+
+    /// Seconds to wait for players to leave the old instance before stopping it.
+    pub drain_timeout: u32,
+}
+
+impl BlueGreen {
+    /// Get the secondary instance server directory, relative to the config file.
+    pub fn directory(config: &Config) -> Option<PathBuf> {
+        let directory = config.blue_green.directory.as_ref()?;
+        match config.path.as_ref().and_then(|p| p.parent()) {
+            Some(config_dir) => Some(config_dir.join(directory)),
+            None => Some(directory.clone()),
+        }
+    }
+}
+
+impl Default for BlueGreen {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            command: None,
+            directory: None,
+            address: "127.0.0.1:25567".parse().unwrap(),
+            rcon_port: 25576,
+            ready_timeout: 300,
+            drain_timeout: 600,
+        }
+    }
+}
+
+/// MOTD configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Motd {
+    /// MOTDs when server is sleeping.
+    #[schemars(with = "Vec<String>")]
+    pub sleeping: MessagePool,
+
+    /// MOTDs when server is starting.
+    #[schemars(with = "Vec<String>")]
+    pub starting: MessagePool,
+
+    /// MOTDs when server is stopping.
+    #[schemars(with = "Vec<String>")]
+    pub stopping: MessagePool,
+
+    /// How to pick from multiple MOTDs.
+    pub rotation: Rotation,
+
+    /// Use MOTD from Minecraft server once known.
+    pub from_server: bool,
+
+    /// Version name reported while server is not online, server version if not set.
+    pub version: Option<String>,
+
+    /// Protocol version reported while server is not online, server protocol if not set.
+    pub protocol: Option<u32>,
+}
+
+impl Default for Motd {
+    fn default() -> Self {
+        Self {
+            sleeping: "☠ Server is sleeping\n§2☻ Join to start it up".into(),
+            starting: "§2☻ Server is starting...\n§7⌛ Please wait...".into(),
+            stopping: "☠ Server going to sleep...\n⌛ Please wait...".into(),
+            rotation: Rotation::Sequential,
+            from_server: false,
+            version: None,
+            protocol: None,
+        }
+    }
+}
+
+/// Join method types.
+#[derive(Debug, Deserialize, JsonSchema, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Method {
+    /// Kick client with message.
+    Kick,
+
+    /// Hold client connection until server is ready.
+    Hold,
+
+    /// Forward connection to another host.
+    Forward,
+
+    /// Keep client in temporary fake lobby until server is ready.
+    Lobby,
+}
+
+/// Join configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Join {
+    /// Join methods.
+    pub methods: Vec<Method>,
+
+    /// Join kick configuration.
+    #[serde(default)]
+    pub kick: JoinKick,
+
+    /// Join hold configuration.
+    #[serde(default)]
+    pub hold: JoinHold,
+
+    /// Join forward configuration.
+    #[serde(default)]
+    pub forward: JoinForward,
+
+    /// Join lobby configuration.
+    #[serde(default)]
+    pub lobby: JoinLobby,
+}
+
+impl Default for Join {
+    fn default() -> Self {
+        Self {
+            methods: vec![Method::Hold, Method::Kick],
+            kick: Default::default(),
+            hold: Default::default(),
+            forward: Default::default(),
+            lobby: Default::default(),
+        }
+    }
+}
+
+/// Join kick configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 pub struct JoinKick {
-    /// Kick message when server is starting.
-    pub starting: String,
+    /// Kick messages when server is starting.
+    #[schemars(with = "Vec<String>")]
+    pub starting: MessagePool,
+
+    /// Kick messages when server is stopping.
+    #[schemars(with = "Vec<String>")]
+    pub stopping: MessagePool,
+
+    /// Kick messages when server is stopping, and will start again right after.
+    #[schemars(with = "Vec<String>")]
+    pub stopping_wake: MessagePool,
+
+    /// Kick messages when server failed to start.
+    #[schemars(with = "Vec<String>")]
+    pub start_failed: MessagePool,
 
-    /// Kick message when server is stopping.
-    pub stopping: String,
+    /// How to pick from multiple kick messages.
+    pub rotation: Rotation,
 }
 
 impl Default for JoinKick {
@@ -351,30 +1210,144 @@ impl Default for JoinKick {
         Self {
             starting: "Server is starting... §c♥§r\n\nThis may take some time.\n\nPlease try to reconnect in a minute.".into(),
             stopping: "Server is going to sleep... §7☠§r\n\nPlease try to reconnect in a minute to wake it again.".into(),
+            stopping_wake: "Server is going to sleep... §7☠§r\n\nIt will start again right after.\n\nPlease try to reconnect in a minute.".into(),
+            start_failed: "Server failed to start... §c✖§r\n\nPlease contact an administrator.".into(),
+            rotation: Rotation::Sequential,
         }
     }
 }
 
 /// Join hold configuration.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 pub struct JoinHold {
     /// Hold client for number of seconds on connect while server starts.
     pub timeout: u32,
+
+    /// Send keep-alive to held clients at this interval in seconds, 0 to disable.
+    ///
+    /// Allows holding longer than the client timeout. Requires Minecraft 1.13 or newer.
+    pub keep_alive: u32,
+
+    /// What to do with the client when holding times out.
+    pub on_timeout: HoldTimeout,
+
+    /// Overrides for specific protocol versions, first match is used.
+    pub versions: Vec<JoinHoldVersion>,
 }
 
 impl Default for JoinHold {
     fn default() -> Self {
-        Self { timeout: 25 }
+        Self {
+            timeout: 25,
+            keep_alive: 0,
+            on_timeout: HoldTimeout::Next,
+            versions: vec![],
+        }
+    }
+}
+
+impl JoinHold {
+    /// Get hold behavior for a client protocol version, with overrides applied.
+    pub fn behavior(&self, protocol: Option<u32>) -> HoldBehavior {
+        let mut behavior = HoldBehavior {
+            timeout: self.timeout,
+            keep_alive: self.keep_alive,
+            on_timeout: self.on_timeout,
+        };
+
+        let version = protocol.and_then(|protocol| {
+            self.versions.iter().find(|version| {
+                version
+                    .min_protocol
+                    .map(|min| protocol >= min)
+                    .unwrap_or(true)
+                    && version
+                        .max_protocol
+                        .map(|max| protocol <= max)
+                        .unwrap_or(true)
+            })
+        });
+        if let Some(version) = version {
+            behavior.timeout = version.timeout.unwrap_or(behavior.timeout);
+            behavior.keep_alive = version.keep_alive.unwrap_or(behavior.keep_alive);
+            behavior.on_timeout = version.on_timeout.unwrap_or(behavior.on_timeout);
+        }
+
+        behavior
+    }
+}
+
+/// Join hold override for a range of protocol versions.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct JoinHoldVersion {
+    /// Lowest protocol version this applies to, inclusive.
+    pub min_protocol: Option<u32>,
+
+    /// Highest protocol version this applies to, inclusive.
+    pub max_protocol: Option<u32>,
+
+    /// Hold timeout in seconds.
+    pub timeout: Option<u32>,
+
+    /// Keep-alive interval in seconds, 0 to disable.
+    pub keep_alive: Option<u32>,
+
+    /// What to do with the client when holding times out.
+    pub on_timeout: Option<HoldTimeout>,
+}
+
+/// Hold behavior for a client.
+#[derive(Debug, Copy, Clone)]
+pub struct HoldBehavior {
+    /// Hold timeout in seconds.
+    pub timeout: u32,
+
+    /// Keep-alive interval in seconds, 0 to disable.
+    pub keep_alive: u32,
+
+    /// What to do with the client when holding times out.
+    pub on_timeout: HoldTimeout,
+}
+
+/// What to do with a held client when holding times out.
+#[derive(Debug, Default, Deserialize, JsonSchema, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HoldTimeout {
+    /// Continue with the next join method.
+    #[default]
+    Next,
+
+    /// Kick client with the starting message.
+    Kick,
+
+    /// Move client to the lobby.
+    Lobby,
+
+    /// Forward client to the forward address.
+    Forward,
+}
+
+impl HoldTimeout {
+    /// Join method to continue with, `None` to continue with the next configured method.
+    pub fn method(self) -> Option<Method> {
+        match self {
+            Self::Next => None,
+            Self::Kick => Some(Method::Kick),
+            Self::Lobby => Some(Method::Lobby),
+            Self::Forward => Some(Method::Forward),
+        }
     }
 }
 
 /// Join forward configuration.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 pub struct JoinForward {
     /// IP and port to forward to.
     #[serde(deserialize_with = "to_socket_addrs")]
+    #[schemars(with = "String")]
     pub address: SocketAddr,
 
     /// Add HAProxy v2 header to proxied connections.
@@ -391,7 +1364,7 @@ impl Default for JoinForward {
     }
 }
 /// Join lobby configuration.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 pub struct JoinLobby {
     /// Hold client in lobby for number of seconds on connect while server starts.
@@ -415,7 +1388,7 @@ impl Default for JoinLobby {
 }
 
 /// Lockout configuration.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
 pub struct Lockout {
     /// Enable to prevent everybody from connecting through lazymc. Instantly kicks player.
@@ -434,68 +1407,1242 @@ impl Default for Lockout {
     }
 }
 
-/// RCON configuration.
-#[derive(Debug, Deserialize)]
+/// Maintenance mode configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
-pub struct Rcon {
-    /// Enable sleeping server through RCON.
+pub struct Maintenance {
+    /// Start in maintenance mode. Can be toggled at runtime.
     pub enabled: bool,
 
-    /// Server RCON port.
-    pub port: u16,
+    /// MOTD shown in maintenance mode.
+    pub motd: String,
 
-    /// Server RCON password.
-    pub password: String,
+    /// Kick players with following message.
+    pub message: String,
 
-    /// Randomize server RCON password on each start.
-    pub randomize_password: bool,
+    /// Usernames still allowed to join, and wake the server.
+    pub admins: Vec<String>,
+}
 
-    /// Add HAProxy v2 header to RCON connections.
-    pub send_proxy_v2: bool,
+impl Maintenance {
+    /// Check whether the given user is a maintenance admin.
+    pub fn is_admin(&self, username: &str) -> bool {
+        self.admins
+            .iter()
+            .any(|admin| admin.eq_ignore_ascii_case(username))
+    }
 }
 
-impl Default for Rcon {
+impl Default for Maintenance {
     fn default() -> Self {
         Self {
-            enabled: cfg!(windows),
-            port: 25575,
-            password: "".into(),
-            randomize_password: true,
-            send_proxy_v2: false,
+            enabled: false,
+            motd: "§6Under maintenance §7⚒".into(),
+            message: "Server is under maintenance §7⚒§r\n\nPlease come back later.".into(),
+            admins: vec![],
         }
     }
 }
 
-/// Advanced configuration.
-#[derive(Debug, Deserialize)]
+/// Stealth mode configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
-pub struct Advanced {
-    /// Rewrite server.properties.
-    pub rewrite_server_properties: bool,
+pub struct Stealth {
+    /// Drop status requests from unknown IPs while the server is not online.
+    pub enabled: bool,
+
+    /// IPs and subnets to respond to, such as `192.168.1.0/24`.
+    #[schemars(with = "Vec<String>")]
+    pub allowed: Vec<Subnet>,
+
+    /// Also respond to IPs of players that joined through lazymc before.
+    pub learn: bool,
 }
 
-impl Default for Advanced {
+impl Default for Stealth {
     fn default() -> Self {
         Self {
-            rewrite_server_properties: true,
+            enabled: false,
+            allowed: vec![],
+            learn: true,
         }
     }
 }
 
-/// Config configuration.
-#[derive(Debug, Deserialize, Default)]
-#[serde(default)]
-pub struct ConfigConfig {
-    /// Configuration for lazymc version.
-    pub version: Option<String>,
+impl Stealth {
+    /// Check whether the given IP is in the allowed IPs and subnets.
+    pub fn is_allowed(&self, ip: IpAddr) -> bool {
+        self.allowed.iter().any(|subnet| subnet.contains(ip))
+    }
 }
 
-fn option_pathbuf_dot() -> Option<PathBuf> {
-    Some(".".into())
-}
+/// Scanner honeypot configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Honeypot {
+    /// Track repeated status requests, failed logins and malformed packets per IP.
+    pub enabled: bool,
 
-fn server_address_default() -> SocketAddr {
-    "127.0.0.1:25566".parse().unwrap()
+    /// Number of suspicious requests within the window before logging an IP.
+    pub threshold: u32,
+
+    /// Window in seconds to count suspicious requests in.
+    pub window: u32,
+
+    /// Temporarily deny connections from offending IPs.
+    pub ban: bool,
+
+    /// Time in seconds to deny connections from offending IPs.
+    pub ban_duration: u32,
+}
+
+impl Default for Honeypot {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 20,
+            window: 60,
+            ban: false,
+            ban_duration: 3600,
+        }
+    }
+}
+
+/// Login configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Login {
+    /// Kick players with usernames that are invalid by Mojang rules before waking the server.
+    pub validate_username: bool,
+
+    /// Username prefixes to ignore when validating, such as `.` for Floodgate.
+    pub username_prefixes: Vec<String>,
+
+    /// Kick players with following message if their username is invalid.
+    pub invalid_username_message: String,
+
+    /// Logins per IP denied by authorization hooks within the window before throttling, 0 to
+    /// disable.
+    pub max_failures: u32,
+
+    /// Window in seconds to count failed logins in.
+    pub failure_window: u32,
+
+    /// Kick players with following message while throttled.
+    pub throttle_message: String,
+
+    /// UUIDs of players allowed to wake the server, anyone if empty.
+    pub wake_uuids: Vec<String>,
+
+    /// Resolve player UUIDs through the Mojang API, use offline mode UUIDs if disabled.
+    pub mojang_api: bool,
+
+    /// File to cache resolved UUIDs in, relative to config file.
+    pub uuid_cache: PathBuf,
+
+    /// Time in seconds after which cached UUIDs are resolved again.
+    pub uuid_cache_ttl: u32,
+
+    /// Authorization command, run with username and IP as arguments before waking the server.
+    pub auth_command: Option<String>,
+
+    /// Kick players with following message if the authorization command denies waking.
+    pub auth_command_message: String,
+
+    /// Authorization webhook.
+    pub webhook: LoginWebhook,
+}
+
+impl Login {
+    /// Get UUID cache file path, relative to config directory if known.
+    pub fn uuid_cache_path(config: &Config) -> PathBuf {
+        match config.path.as_ref().and_then(|p| p.parent()) {
+            Some(config_dir) => config_dir.join(&config.login.uuid_cache),
+            None => config.login.uuid_cache.clone(),
+        }
+    }
+}
+
+impl Default for Login {
+    fn default() -> Self {
+        Self {
+            validate_username: false,
+            username_prefixes: vec![".".into()],
+            invalid_username_message: "Invalid username.".into(),
+            max_failures: 5,
+            failure_window: 300,
+            throttle_message: "Too many login attempts.\n\nPlease try again later.".into(),
+            wake_uuids: vec![],
+            mojang_api: true,
+            uuid_cache: "lazymc-uuids.json".into(),
+            uuid_cache_ttl: 86400,
+            auth_command: None,
+            auth_command_message: "You are not allowed to wake this server.".into(),
+            webhook: Default::default(),
+        }
+    }
+}
+
+/// Login authorization webhook configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct LoginWebhook {
+    /// URL to POST username, UUID and IP to before waking the server.
+    pub url: Option<String>,
+
+    /// Request timeout in seconds.
+    pub timeout: u32,
+
+    /// Allow waking if the webhook fails or times out.
+    pub allow_on_error: bool,
+
+    /// Kick players with following message if denied without a message.
+    pub message: String,
+}
+
+impl Default for LoginWebhook {
+    fn default() -> Self {
+        Self {
+            url: None,
+            timeout: 5,
+            allow_on_error: false,
+            message: "You are not allowed to wake this server.".into(),
+        }
+    }
+}
+
+/// RCON configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Rcon {
+    /// Enable sleeping server through RCON.
+    pub enabled: bool,
+
+    /// Server RCON port.
+    pub port: u16,
+
+    /// Server RCON password.
+    pub password: String,
+
+    /// Randomize server RCON password on each start.
+    pub randomize_password: bool,
+
+    /// Add HAProxy v2 header to RCON connections.
+    pub send_proxy_v2: bool,
+
+    /// Periodically save the world through RCON while the server is running, in seconds.
+    ///
+    /// 0 to disable.
+    pub save_interval: u32,
+
+    /// Kick players through RCON once all online players have been idle for number of seconds.
+    ///
+    /// 0 to disable.
+    pub idle_kick_after: u32,
+
+    /// Kick idle players with following message.
+    pub idle_kick_message: String,
+
+    /// Count down in titles for number of seconds before stopping while players are online.
+    ///
+    /// Aborted if more players come online. 0 to disable.
+    pub stop_countdown: u32,
+
+    /// Countdown title, `%seconds%` is replaced with the remaining seconds.
+    pub stop_countdown_title: String,
+
+    /// Countdown subtitle, `%seconds%` is replaced with the remaining seconds.
+    pub stop_countdown_subtitle: String,
+}
+
+impl Default for Rcon {
+    fn default() -> Self {
+        Self {
+            enabled: cfg!(windows),
+            port: 25575,
+            password: "".into(),
+            randomize_password: true,
+            send_proxy_v2: false,
+            save_interval: 0,
+            idle_kick_after: 0,
+            idle_kick_message: "You have been idle for too long, the server is going to sleep."
+                .into(),
+            stop_countdown: 0,
+            stop_countdown_title: "Server going to sleep".into(),
+            stop_countdown_subtitle: "in %seconds% seconds".into(),
+        }
+    }
+}
+
+/// Server monitor configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Monitor {
+    /// Poll server status every number of seconds.
+    pub interval: u32,
+
+    /// Status request timeout in seconds.
+    pub status_timeout: u32,
+
+    /// Ping request timeout in seconds.
+    pub ping_timeout: u32,
+
+    /// Consecutive failed polls before considering a started server stopped.
+    pub failures: u32,
+}
+
+impl Default for Monitor {
+    fn default() -> Self {
+        Self {
+            interval: 2,
+            status_timeout: 20,
+            ping_timeout: 10,
+            failures: 1,
+        }
+    }
+}
+
+/// Resource monitoring configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Resources {
+    /// Sample memory and CPU usage of the server process.
+    pub enabled: bool,
+
+    /// Sampling interval in seconds.
+    pub interval: u32,
+
+    /// Memory ceiling in megabytes. 0 to disable.
+    pub memory_limit: u64,
+
+    /// Action to take when the memory ceiling is exceeded.
+    pub memory_action: MemoryAction,
+}
+
+impl Default for Resources {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: 10,
+            memory_limit: 0,
+            memory_action: MemoryAction::Warn,
+        }
+    }
+}
+
+/// Action when the server process exceeds its memory ceiling.
+#[derive(Debug, Deserialize, JsonSchema, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryAction {
+    /// Only show a warning.
+    Warn,
+
+    /// Restart the server once no players are online.
+    Restart,
+}
+
+/// Hang watchdog configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Watchdog {
+    /// Detect a hung server that is started but not responding.
+    pub enabled: bool,
+
+    /// Number of seconds the server must be unresponsive before it is considered hung.
+    pub timeout: u32,
+
+    /// Force kill and restart the server when it is hung.
+    pub restart: bool,
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout: 120,
+            restart: true,
+        }
+    }
+}
+
+/// Diagnostics configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Diagnostics {
+    /// Capture JVM thread dump before force killing the server, or when it is hung.
+    pub thread_dump: bool,
+
+    /// Method to capture thread dump with.
+    pub thread_dump_method: ThreadDumpMethod,
+
+    /// Path to `jcmd` binary.
+    pub jcmd: String,
+
+    /// Directory to save diagnostics in, relative to server directory.
+    pub directory: PathBuf,
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Self {
+            thread_dump: false,
+            thread_dump_method: ThreadDumpMethod::Jcmd,
+            jcmd: "jcmd".into(),
+            directory: "lazymc-diagnostics".into(),
+        }
+    }
+}
+
+/// Thread dump capture method.
+#[derive(Debug, Deserialize, JsonSchema, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ThreadDumpMethod {
+    /// Invoke `jcmd <pid> Thread.print`, save output to file.
+    Jcmd,
+
+    /// Send `SIGQUIT`, JVM writes dump to server output.
+    Signal,
+}
+
+/// Backup configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Backup {
+    /// Command to run before the server goes to sleep.
+    pub command: Option<String>,
+
+    /// Backup timeout in seconds.
+    pub timeout: u32,
+
+    /// What to do when the backup fails.
+    pub on_failure: BackupFailure,
+
+    /// Archive world directories before the server goes to sleep.
+    pub archive: bool,
+
+    /// World directories to archive, relative to server directory.
+    pub worlds: Vec<PathBuf>,
+
+    /// Directory to store archives in, relative to server directory.
+    pub directory: PathBuf,
+
+    /// Number of archives to keep.
+    pub keep: usize,
+
+    /// Upload archives to S3-compatible storage.
+    pub s3: BackupS3,
+}
+
+impl Default for Backup {
+    fn default() -> Self {
+        Self {
+            command: None,
+            timeout: 600,
+            on_failure: BackupFailure::Sleep,
+            archive: false,
+            worlds: vec![
+                "world".into(),
+                "world_nether".into(),
+                "world_the_end".into(),
+            ],
+            directory: "backups".into(),
+            keep: 5,
+            s3: Default::default(),
+        }
+    }
+}
+
+impl Backup {
+    /// Whether any backup is configured.
+    pub fn enabled(&self) -> bool {
+        self.archive
+            || self
+                .command
+                .as_ref()
+                .map(|c| !c.trim().is_empty())
+                .unwrap_or(false)
+    }
+}
+
+/// Backup S3 upload configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct BackupS3 {
+    /// Upload archives to S3-compatible storage.
+    pub enabled: bool,
+
+    /// S3 endpoint URL.
+    pub endpoint: String,
+
+    /// Bucket name.
+    pub bucket: String,
+
+    /// Bucket region.
+    pub region: String,
+
+    /// Access key ID.
+    pub access_key: String,
+
+    /// Secret access key.
+    pub secret_key: String,
+
+    /// Object key prefix.
+    pub prefix: String,
+
+    /// Number of archives to keep in bucket, 0 to keep all.
+    pub keep: usize,
+}
+
+impl Default for BackupS3 {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "https://s3.amazonaws.com".into(),
+            bucket: "".into(),
+            region: "us-east-1".into(),
+            access_key: "".into(),
+            secret_key: "".into(),
+            prefix: "lazymc/".into(),
+            keep: 10,
+        }
+    }
+}
+
+/// Backup failure policy.
+#[derive(Debug, Deserialize, JsonSchema, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupFailure {
+    /// Put the server to sleep anyway.
+    Sleep,
+
+    /// Keep the server online, try again when it is idle for another sleep period.
+    Stay,
+}
+
+/// Server jar updater configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Updater {
+    /// Whether to automatically update the server jar.
+    pub enabled: bool,
+
+    /// Project to fetch builds for.
+    pub project: UpdaterProject,
+
+    /// Minecraft version to fetch builds for.
+    pub version: String,
+
+    /// Server jar file, relative to server directory.
+    pub jar: PathBuf,
+
+    /// Interval in seconds to check for updates while the server is sleeping.
+    pub interval: u32,
+}
+
+impl Default for Updater {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            project: UpdaterProject::Paper,
+            version: String::new(),
+            jar: "server.jar".into(),
+            interval: 21600,
+        }
+    }
+}
+
+/// Project to fetch server jar builds for.
+#[derive(Debug, Deserialize, JsonSchema, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdaterProject {
+    /// Paper, from the PaperMC download API.
+    Paper,
+
+    /// Purpur, from the PurpurMC download API.
+    Purpur,
+}
+
+impl UpdaterProject {
+    /// Project name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Paper => "paper",
+            Self::Purpur => "purpur",
+        }
+    }
+}
+
+/// Java runtime configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Java {
+    /// Run Java preflight checks before starting the server.
+    pub preflight: bool,
+
+    /// Java binary to check, defaults to the binary in the server command.
+    pub path: Option<String>,
+
+    /// Minimum required Java major version, 0 to disable.
+    pub min_version: u32,
+}
+
+impl Default for Java {
+    fn default() -> Self {
+        Self {
+            preflight: true,
+            path: None,
+            min_version: 0,
+        }
+    }
+}
+
+/// cgroup configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Cgroup {
+    /// Place the server process in its own cgroup v2. Linux only.
+    pub enabled: bool,
+
+    /// Path of cgroup to create.
+    pub path: PathBuf,
+
+    /// Memory limit in megabytes, 0 to disable.
+    pub memory_max: u64,
+
+    /// CPU limit in number of cores, 0 to disable.
+    pub cpu_max: f32,
+}
+
+impl Default for Cgroup {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "/sys/fs/cgroup/lazymc".into(),
+            memory_max: 0,
+            cpu_max: 0.0,
+        }
+    }
+}
+
+/// Process scheduling configuration.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Process {
+    /// Nice level of the server process. Unix only.
+    pub nice: Option<i32>,
+
+    /// I/O scheduling class of the server process. Linux only.
+    pub ionice_class: Option<IoniceClass>,
+
+    /// I/O scheduling priority within class, 0 (highest) to 7 (lowest). Linux only.
+    pub ionice_level: Option<u8>,
+
+    /// CPU cores to pin the server process to, empty to allow all. Linux only.
+    pub cpu_affinity: Vec<usize>,
+}
+
+/// I/O scheduling class.
+#[derive(Debug, Deserialize, JsonSchema, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum IoniceClass {
+    /// Real time, first access to disk.
+    Realtime,
+
+    /// Best effort, default class.
+    BestEffort,
+
+    /// Idle, only access disk when nobody else does.
+    Idle,
+}
+
+/// Logging configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Log {
+    /// Log output format.
+    pub format: LogFormat,
+
+    /// Server name to include in structured log output.
+    pub name: Option<String>,
+
+    /// File to write logs to, in addition to standard error.
+    pub file: Option<PathBuf>,
+
+    /// Rotate log file when it exceeds size in megabytes, 0 to disable.
+    pub max_size: u64,
+
+    /// Rotate log file based on time.
+    pub rotate: LogRotate,
+
+    /// Number of rotated log files to keep.
+    pub keep: usize,
+
+    /// Log levels per target, such as `lazymc::monitor`.
+    pub levels: HashMap<String, String>,
+}
+
+impl Default for Log {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::Pretty,
+            name: None,
+            file: None,
+            max_size: 10,
+            rotate: LogRotate::Never,
+            keep: 5,
+            levels: HashMap::new(),
+        }
+    }
+}
+
+/// Log file time-based rotation.
+#[derive(Debug, Default, Deserialize, JsonSchema, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotate {
+    /// Never rotate based on time.
+    #[default]
+    Never,
+
+    /// Rotate every day.
+    Daily,
+
+    /// Rotate every hour.
+    Hourly,
+}
+
+/// Log output format.
+#[derive(Debug, Default, Deserialize, JsonSchema, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable, colored if supported.
+    #[default]
+    Pretty,
+
+    /// One JSON object per line.
+    Json,
+}
+
+/// Telemetry configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Telemetry {
+    /// Export traces through OpenTelemetry, requires `otel` feature.
+    pub enabled: bool,
+
+    /// OTLP gRPC endpoint.
+    pub endpoint: String,
+
+    /// Service name to report.
+    pub service_name: String,
+}
+
+impl Default for Telemetry {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "http://localhost:4317".into(),
+            service_name: "lazymc".into(),
+        }
+    }
+}
+
+/// Metrics push configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Metrics {
+    /// Periodically push metrics.
+    pub enabled: bool,
+
+    /// Format and protocol to push metrics with.
+    pub format: MetricsFormat,
+
+    /// StatsD address, or InfluxDB write URL.
+    pub address: String,
+
+    /// InfluxDB API token.
+    pub token: Option<String>,
+
+    /// Metric name prefix, or InfluxDB measurement.
+    pub prefix: String,
+
+    /// Push interval in seconds.
+    pub interval: u32,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: MetricsFormat::Statsd,
+            address: "127.0.0.1:8125".into(),
+            token: None,
+            prefix: "lazymc".into(),
+            interval: 10,
+        }
+    }
+}
+
+/// Metrics push format.
+#[derive(Debug, Default, Deserialize, JsonSchema, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricsFormat {
+    /// StatsD over UDP.
+    #[default]
+    Statsd,
+
+    /// InfluxDB line protocol over HTTP.
+    Influx,
+}
+
+/// HTTP endpoint configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Http {
+    /// Serve HTTP endpoints.
+    pub enabled: bool,
+
+    /// Address to serve on.
+    #[schemars(with = "String")]
+    pub address: SocketAddr,
+
+    /// When to report ready on `/readyz`.
+    pub ready_when: ReadyWhen,
+
+    /// Status code for healthy `/healthz`.
+    pub healthy_status: u16,
+
+    /// Status code for ready `/readyz`.
+    pub ready_status: u16,
+
+    /// Status code for not ready `/readyz`.
+    pub not_ready_status: u16,
+
+    /// Token clients must send as `Authorization: Bearer <token>` header on control endpoints.
+    ///
+    /// Control endpoints are not served on non-loopback addresses without it.
+    pub token: Option<String>,
+}
+
+impl Default for Http {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "127.0.0.1:8080".parse().unwrap(),
+            ready_when: ReadyWhen::Listening,
+            healthy_status: 200,
+            ready_status: 200,
+            not_ready_status: 503,
+            token: None,
+        }
+    }
+}
+
+/// When to report ready.
+#[derive(Debug, Default, Deserialize, JsonSchema, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadyWhen {
+    /// When lazymc accepts connections, even if server is sleeping.
+    #[default]
+    Listening,
+
+    /// Only when server is started.
+    Started,
+}
+
+/// Wake link configuration.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct WakeLinks {
+    /// Secret to sign wake links with, disabled if empty.
+    pub secret: String,
+
+    /// Public base URL of the HTTP endpoints, defaults to the HTTP address.
+    pub url: Option<String>,
+}
+
+/// gRPC API configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Grpc {
+    /// Serve gRPC API, requires `grpc` feature.
+    pub enabled: bool,
+
+    /// Address to serve on.
+    #[schemars(with = "String")]
+    pub address: SocketAddr,
+
+    /// Token clients must send as `authorization: Bearer <token>` metadata.
+    ///
+    /// Required when serving on a non-loopback address.
+    pub token: Option<String>,
+}
+
+impl Default for Grpc {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "127.0.0.1:50051".parse().unwrap(),
+            token: None,
+        }
+    }
+}
+
+/// Discord bot configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Discord {
+    /// Enable Discord bot, requires `discord` feature.
+    pub enabled: bool,
+
+    /// Bot token.
+    pub token: String,
+
+    /// Application ID.
+    pub application_id: String,
+
+    /// Application public key, to verify interactions with.
+    pub public_key: String,
+
+    /// User IDs allowed to wake and sleep the server.
+    pub allowed_users: Vec<String>,
+
+    /// Role IDs allowed to wake and sleep the server.
+    pub allowed_roles: Vec<String>,
+}
+
+impl Default for Discord {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            token: "".into(),
+            application_id: "".into(),
+            public_key: "".into(),
+            allowed_users: vec![],
+            allowed_roles: vec![],
+        }
+    }
+}
+
+/// Telegram bot configuration.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Telegram {
+    /// Enable Telegram bot.
+    pub enabled: bool,
+
+    /// Bot token.
+    pub token: String,
+
+    /// Chat to push server state changes to.
+    pub chat_id: Option<i64>,
+
+    /// User IDs allowed to wake the server.
+    pub allowed_users: Vec<i64>,
+}
+
+/// Plugin configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Plugins {
+    /// WASM plugin files to load, requires `wasm` feature.
+    pub wasm: Vec<PathBuf>,
+
+    /// Fuel available to each plugin hook call, limits execution time.
+    pub fuel: u64,
+}
+
+impl Default for Plugins {
+    fn default() -> Self {
+        Self {
+            wasm: vec![],
+            fuel: 10_000_000,
+        }
+    }
+}
+
+/// Script configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Scripts {
+    /// Rhai script files to load, requires `rhai` feature.
+    pub files: Vec<PathBuf>,
+
+    /// Maximum number of operations per script call, limits execution time.
+    pub max_operations: u64,
+}
+
+impl Default for Scripts {
+    fn default() -> Self {
+        Self {
+            files: vec![],
+            max_operations: 100_000,
+        }
+    }
+}
+
+/// Proxy configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Proxy {
+    /// Client to server rate limit per connection in KB/s, 0 for unlimited.
+    pub upload_limit: u64,
+
+    /// Server to client rate limit per connection in KB/s, 0 for unlimited.
+    pub download_limit: u64,
+
+    /// Buffer size in bytes used for relaying data in each direction.
+    pub buffer_size: usize,
+
+    /// Socket options for client connections.
+    pub client: Socket,
+
+    /// Socket options for connections to the server.
+    pub server: Socket,
+
+    /// Extra UDP ports to forward while the server is started.
+    pub udp: Vec<UdpForward>,
+
+    /// Extra TCP ports to forward while the server is started.
+    pub tcp: Vec<TcpForward>,
+
+    /// Number of times to retry connecting to the server before giving up, only shortly after it
+    /// started.
+    pub connect_retries: u32,
+
+    /// Delay in milliseconds before the first connect retry, doubled for each next retry up to 5
+    /// seconds.
+    pub connect_retry_delay: u64,
+
+    /// Answer status requests while the server is started from a status fetched at most once per
+    /// this interval in milliseconds, 0 to proxy each status request.
+    pub status_coalesce: u64,
+}
+
+impl Default for Proxy {
+    fn default() -> Self {
+        Self {
+            upload_limit: 0,
+            download_limit: 0,
+            buffer_size: 8 * 1024,
+            client: Socket::default(),
+            server: Socket::default(),
+            udp: vec![],
+            tcp: vec![],
+            connect_retries: 3,
+            connect_retry_delay: 250,
+            status_coalesce: 0,
+        }
+    }
+}
+
+/// UDP port forward.
+#[derive(Debug, Deserialize, JsonSchema, Copy, Clone)]
+pub struct UdpForward {
+    /// Public address to receive datagrams on.
+    #[serde(deserialize_with = "to_socket_addrs")]
+    #[schemars(with = "String")]
+    pub public: SocketAddr,
+
+    /// Server address to forward datagrams to.
+    #[serde(deserialize_with = "to_socket_addrs")]
+    #[schemars(with = "String")]
+    pub server: SocketAddr,
+}
+
+/// TCP port forward.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TcpForward {
+    /// Public address to accept connections on.
+    #[serde(deserialize_with = "to_socket_addrs")]
+    #[schemars(with = "String")]
+    pub public: SocketAddr,
+
+    /// Server address to forward connections to.
+    #[serde(deserialize_with = "to_socket_addrs")]
+    #[schemars(with = "String")]
+    pub server: SocketAddr,
+
+    /// HTML page to respond with while the server is not started, relative to config file.
+    ///
+    /// Uses a default page if not set.
+    pub placeholder: Option<PathBuf>,
+}
+
+/// TCP socket options.
+///
+/// Options that are not set use the operating system default.
+#[derive(Debug, Deserialize, JsonSchema, Default, Copy, Clone)]
+#[serde(default)]
+pub struct Socket {
+    /// Set TCP_NODELAY, disabling Nagle's algorithm.
+    pub nodelay: bool,
+
+    /// Idle time in seconds before sending TCP keepalive probes, enables keepalive.
+    pub keepalive: Option<u32>,
+
+    /// Interval in seconds between TCP keepalive probes.
+    pub keepalive_interval: Option<u32>,
+
+    /// Linger time in seconds when closing socket.
+    pub linger: Option<u32>,
+
+    /// Socket send buffer size in bytes.
+    pub send_buffer: Option<usize>,
+
+    /// Socket receive buffer size in bytes.
+    pub recv_buffer: Option<usize>,
+}
+
+/// Player statistics configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Stats {
+    /// Record player sessions and wakes.
+    pub enabled: bool,
+
+    /// File to record to, relative to config file.
+    pub file: PathBuf,
+
+    /// Server power draw in watts when awake, to estimate energy saved in reports.
+    pub server_watts: Option<f64>,
+}
+
+impl Stats {
+    /// Get stats file path, relative to config directory if known.
+    pub fn file_path(config: &Config) -> PathBuf {
+        match config.path.as_ref().and_then(|p| p.parent()) {
+            Some(config_dir) => config_dir.join(&config.stats.file),
+            None => config.stats.file.clone(),
+        }
+    }
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            file: "lazymc-stats.jsonl".into(),
+            server_watts: None,
+        }
+    }
+}
+
+/// State file configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct StateFile {
+    /// Write server state to a JSON file on every change.
+    pub enabled: bool,
+
+    /// File to write to, relative to config file.
+    pub file: PathBuf,
+}
+
+impl StateFile {
+    /// Get state file path, relative to config directory if known.
+    pub fn file_path(config: &Config) -> PathBuf {
+        match config.path.as_ref().and_then(|p| p.parent()) {
+            Some(config_dir) => config_dir.join(&config.state_file.file),
+            None => config.state_file.file.clone(),
+        }
+    }
+}
+
+impl Default for StateFile {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            file: "lazymc-state.json".into(),
+        }
+    }
+}
+
+/// Control socket configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Control {
+    /// Serve control socket, Unix only.
+    pub enabled: bool,
+
+    /// Socket path, relative to config file.
+    pub socket: PathBuf,
+}
+
+impl Control {
+    /// Get control socket path, relative to config directory if known.
+    pub fn socket_path(config: &Config) -> PathBuf {
+        match config.path.as_ref().and_then(|p| p.parent()) {
+            Some(config_dir) => config_dir.join(&config.control.socket),
+            None => config.control.socket.clone(),
+        }
+    }
+}
+
+impl Default for Control {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            socket: "lazymc.sock".into(),
+        }
+    }
+}
+
+/// Advanced configuration.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Advanced {
+    /// Rewrite server.properties.
+    pub rewrite_server_properties: bool,
+}
+
+impl Default for Advanced {
+    fn default() -> Self {
+        Self {
+            rewrite_server_properties: true,
+        }
+    }
+}
+
+/// Async runtime configuration.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Runtime {
+    /// Runtime flavor.
+    pub flavor: RuntimeFlavor,
+
+    /// Number of worker threads for the multi-threaded runtime, number of CPU cores if not set.
+    pub worker_threads: Option<usize>,
+
+    /// Maximum number of threads for blocking operations, tokio default if not set.
+    pub max_blocking_threads: Option<usize>,
+}
+
+/// Async runtime flavor.
+#[derive(Debug, Default, Deserialize, JsonSchema, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuntimeFlavor {
+    /// Run all work on a single thread.
+    CurrentThread,
+
+    /// Spread work over multiple worker threads.
+    #[default]
+    MultiThread,
+}
+
+/// Config configuration.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ConfigConfig {
+    /// Configuration for lazymc version.
+    pub version: Option<String>,
+}
+
+fn option_pathbuf_dot() -> Option<PathBuf> {
+    Some(".".into())
+}
+
+fn server_address_default() -> SocketAddr {
+    "127.0.0.1:25566".parse().unwrap()
 }
 
 fn u32_300() -> u32 {
@@ -506,6 +2653,10 @@ fn u32_150() -> u32 {
     300
 }
 
+fn string_docker() -> String {
+    "docker".into()
+}
+
 fn bool_true() -> bool {
     true
 }
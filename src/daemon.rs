@@ -0,0 +1,56 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::OnceCell;
+
+/// PID file written by this process, removed when quitting.
+static PID_FILE: OnceCell<PathBuf> = OnceCell::new();
+
+/// Detach from the terminal and run in the background.
+///
+/// Standard input is redirected to `/dev/null`, standard output and error are redirected to the
+/// given log file, or `/dev/null` if none. Must be called before any threads are spawned.
+#[cfg(unix)]
+pub fn daemonize(log_file: Option<&Path>) -> io::Result<()> {
+    use std::fs::OpenOptions;
+    use std::os::unix::io::AsRawFd;
+
+    use nix::unistd::{daemon, dup2};
+
+    // Open files before detaching, so relative paths and errors behave as expected
+    let null = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/null")?;
+    let log = match log_file {
+        Some(path) => OpenOptions::new().create(true).append(true).open(path)?,
+        None => null.try_clone()?,
+    };
+
+    // Keep working directory, redirect stdio ourselves
+    daemon(true, true)?;
+    dup2(null.as_raw_fd(), 0)?;
+    dup2(log.as_raw_fd(), 1)?;
+    dup2(log.as_raw_fd(), 2)?;
+
+    Ok(())
+}
+
+/// Write PID of this process to the given file.
+///
+/// The file is removed again when lazymc quits through `remove_pid_file()`.
+pub fn write_pid_file(path: &Path) -> io::Result<()> {
+    fs::write(path, format!("{}\n", std::process::id()))?;
+    let _ = PID_FILE.set(path.to_path_buf());
+    Ok(())
+}
+
+/// Remove PID file if one was written.
+pub fn remove_pid_file() {
+    if let Some(path) = PID_FILE.get() {
+        if let Err(err) = fs::remove_file(path) {
+            warn!(target: "lazymc", "Failed to remove PID file at {}: {}", path.display(), err);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use std::io;
+
+use thiserror::Error;
+
+/// Result with lazymc error.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// lazymc error for server, proxy and config operations.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// I/O error.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    /// Failed to parse config.
+    #[error("failed to parse config: {0}")]
+    Config(#[from] toml::de::Error),
+
+    /// Failed to include config file.
+    #[error("failed to include config: {0}")]
+    Include(String),
+
+    /// Invalid server state ID.
+    #[error("invalid server state: {0}")]
+    InvalidState(u8),
+
+    /// Server command is empty or can't be parsed.
+    #[error("invalid server command: {0:?}")]
+    InvalidCommand(String),
+
+    /// Server process has no known PID.
+    #[error("server process ID unknown")]
+    UnknownPid,
+
+    /// Server was not started because a check or command before starting failed.
+    #[error("not starting server: {0}")]
+    Preflight(String),
+
+    /// Panel API request failed.
+    #[error("panel request failed: {0}")]
+    Panel(String),
+
+    /// Failed to encode proxy header.
+    #[error("failed to encode proxy header: {0}")]
+    ProxyHeader(#[from] proxy_protocol::EncodeError),
+}
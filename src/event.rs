@@ -0,0 +1,46 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+/// Number of events buffered for slow subscribers.
+pub const EVENT_BUFFER: usize = 128;
+
+/// Server event.
+///
+/// Published by `Server`, see `Server::subscribe`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// Server state changed.
+    State {
+        from: &'static str,
+        to: &'static str,
+    },
+
+    /// Server is woken, optionally by a player.
+    Wake { username: Option<String> },
+
+    /// Number of online players changed.
+    Players { online: u32, max: u32 },
+
+    /// Server process crashed.
+    Crash { code: Option<i32> },
+
+    /// Server process failed to start, quit while starting.
+    StartFailed { code: Option<i32> },
+}
+
+impl Event {
+    /// Serialize as JSON object with timestamp.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Some(object) = value.as_object_mut() {
+            let time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            object.insert("time".into(), time.into());
+        }
+        value
+    }
+}
@@ -10,9 +10,9 @@ use minecraft_protocol::encoder::Encoder;
 use minecraft_protocol::version::forge_v1_13::login::{Acknowledgement, LoginWrapper, ModList};
 use minecraft_protocol::version::v1_14_4::login::{LoginPluginRequest, LoginPluginResponse};
 use minecraft_protocol::version::PacketId;
+use tokio::io::AsyncWrite;
 #[cfg(feature = "lobby")]
 use tokio::io::AsyncWriteExt;
-use tokio::net::tcp::WriteHalf;
 #[cfg(feature = "lobby")]
 use tokio::net::TcpStream;
 #[cfg(feature = "lobby")]
@@ -45,7 +45,7 @@ const CLIENT_DRAIN_FORGE_TIMEOUT: Duration = Duration::from_secs(5);
 /// Respond with Forge login wrapper packet.
 pub async fn respond_forge_login_packet(
     client: &Client,
-    writer: &mut WriteHalf<'_>,
+    writer: &mut (impl AsyncWrite + Unpin),
     message_id: i32,
     forge_channel: String,
     forge_packet: impl PacketId + Encoder,
@@ -83,7 +83,7 @@ pub async fn respond_forge_login_packet(
 pub async fn respond_login_plugin_request(
     client: &Client,
     packet: LoginPluginRequest,
-    writer: &mut WriteHalf<'_>,
+    writer: &mut (impl AsyncWrite + Unpin),
 ) -> Result<(), ()> {
     // Decode Forge login wrapper packet
     let (message_id, login_wrapper, packet) =
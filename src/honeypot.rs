@@ -0,0 +1,125 @@
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use tokio::sync::Mutex;
+
+use crate::config::Config;
+
+/// Number of tracked IPs after which stale entries are pruned.
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Maximum number of tracked IPs, least recently hit IPs that aren't denied are evicted first.
+const MAX_ENTRIES: usize = 16384;
+
+/// Kind of suspicious request.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Hit {
+    /// Status request while the server isn't online.
+    Status,
+
+    /// Login attempt that didn't result in a join.
+    Login,
+
+    /// Malformed or unexpected packet.
+    Malformed,
+}
+
+/// Tracks suspicious requests per IP, to detect scanners and spammers.
+///
+/// Offending IPs may be denied temporarily.
+#[derive(Debug, Default)]
+pub struct Honeypot {
+    entries: Mutex<HashMap<IpAddr, Entry>>,
+}
+
+/// Tracked requests for a single IP.
+#[derive(Debug, Default)]
+struct Entry {
+    /// Times of recent suspicious requests, oldest first.
+    hits: VecDeque<Instant>,
+
+    /// Deny connections until this time.
+    denied_until: Option<Instant>,
+}
+
+impl Honeypot {
+    /// Record suspicious request from IP.
+    ///
+    /// Logs to the audit log once the threshold is reached, and denies the IP if configured.
+    pub async fn record(&self, config: &Config, ip: IpAddr, hit: Hit) {
+        let honeypot = &config.honeypot;
+        if !honeypot.enabled {
+            return;
+        }
+
+        let now = Instant::now();
+        let window = Duration::from_secs(honeypot.window as u64);
+        let mut entries = self.entries.lock().await;
+        if entries.len() >= PRUNE_THRESHOLD {
+            entries.retain(|_, entry| !entry.is_stale(now, window));
+        }
+        if entries.len() >= MAX_ENTRIES && !entries.contains_key(&ip) {
+            let evict = entries
+                .iter()
+                .min_by_key(|(_, entry)| (entry.is_denied(now), entry.hits.back().copied()))
+                .map(|(ip, _)| *ip);
+            if let Some(evict) = evict {
+                entries.remove(&evict);
+            }
+        }
+
+        let entry = entries.entry(ip).or_default();
+        while entry.hits.front().map_or(false, |t| now - *t > window) {
+            entry.hits.pop_front();
+        }
+        entry.hits.push_back(now);
+
+        if entry.hits.len() != honeypot.threshold as usize {
+            return;
+        }
+        warn!(
+            target: "lazymc::audit",
+            "Suspicious requests from {}: {} requests within {}s, last: {:?}",
+            ip,
+            entry.hits.len(),
+            honeypot.window,
+            hit,
+        );
+
+        if honeypot.ban {
+            warn!(target: "lazymc::audit", "Denying connections from {} for {}s", ip, honeypot.ban_duration);
+            entry.denied_until = Some(now + Duration::from_secs(honeypot.ban_duration as u64));
+            entry.hits.clear();
+        }
+    }
+
+    /// Forget suspicious requests from IP, such as when a player joins from it.
+    pub async fn forgive(&self, ip: &IpAddr) {
+        let mut entries = self.entries.lock().await;
+        if let Some(entry) = entries.get_mut(ip) {
+            entry.hits.clear();
+        }
+    }
+
+    /// Check whether connections from IP are temporarily denied.
+    pub async fn is_denied(&self, ip: &IpAddr) -> bool {
+        self.entries
+            .lock()
+            .await
+            .get(ip)
+            .map_or(false, |entry| entry.is_denied(Instant::now()))
+    }
+}
+
+impl Entry {
+    /// Check whether this entry is denied at the given time.
+    fn is_denied(&self, now: Instant) -> bool {
+        self.denied_until.map_or(false, |until| until > now)
+    }
+
+    /// Check whether this entry has no recent requests and isn't denied.
+    fn is_stale(&self, now: Instant, window: Duration) -> bool {
+        !self.is_denied(now) && self.hits.back().map_or(true, |t| now - *t > window)
+    }
+}
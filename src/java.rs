@@ -0,0 +1,101 @@
+use std::path::Path;
+use std::time::Duration;
+
+use tokio::process::Command;
+use tokio::time;
+
+use crate::config::{Config, Server as ConfigServer};
+
+/// Maximum time to wait for `java -version`.
+const VERSION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Run Java runtime preflight checks before starting the server.
+///
+/// Verifies the Java binary can be invoked, and that its major version satisfies the configured
+/// minimum. Returns a descriptive error message if a check fails.
+pub async fn preflight(config: &Config) -> Result<(), String> {
+    // Find binary, skip if server is started through something else
+    let java = match binary(config) {
+        Some(java) => java,
+        None => {
+            debug!(target: "lazymc::java", "Skipping Java preflight checks, server command does not invoke Java directly");
+            return Ok(());
+        }
+    };
+
+    // Invoke java, version is reported on stderr
+    let mut cmd = Command::new(&java);
+    cmd.arg("-version");
+    cmd.kill_on_drop(true);
+    if let Some(dir) = ConfigServer::server_directory(config) {
+        cmd.current_dir(dir);
+    }
+    let output = match time::timeout(VERSION_TIMEOUT, cmd.output()).await {
+        Ok(Ok(output)) if output.status.success() => output,
+        Ok(Ok(output)) => {
+            return Err(format!(
+                "Java binary '{}' exited with {} when querying its version",
+                java, output.status
+            ))
+        }
+        Ok(Err(err)) => {
+            return Err(format!(
+                "Java binary '{}' could not be invoked, is Java installed? ({})",
+                java, err
+            ))
+        }
+        Err(_) => {
+            return Err(format!(
+                "Java binary '{}' did not report its version within {}s",
+                java,
+                VERSION_TIMEOUT.as_secs()
+            ))
+        }
+    };
+    let output = String::from_utf8_lossy(&output.stderr);
+
+    // Check version requirement
+    let version = match parse_major_version(&output) {
+        Some(version) => version,
+        None => {
+            warn!(target: "lazymc::java", "Could not determine Java version from '{} -version' output", java);
+            return Ok(());
+        }
+    };
+    debug!(target: "lazymc::java", "Server uses Java {}", version);
+
+    let required = config.java.min_version;
+    if required > 0 && version < required {
+        return Err(format!(
+            "Java {} is required, but '{}' is Java {}",
+            required, java, version
+        ));
+    }
+
+    Ok(())
+}
+
+/// Determine Java binary to check.
+///
+/// Uses the configured binary, or the server command if it invokes Java directly.
+fn binary(config: &Config) -> Option<String> {
+    if let Some(java) = &config.java.path {
+        return Some(java.clone());
+    }
+
+    let program = ConfigServer::command_args(config)?.into_iter().next()?;
+    let name = Path::new(&program).file_stem()?.to_str()?;
+    (name == "java").then_some(program)
+}
+
+/// Parse major version from `java -version` output.
+///
+/// Supports both legacy `1.8.0_301` and modern `17.0.2` version formats.
+fn parse_major_version(output: &str) -> Option<u32> {
+    let version = output.lines().next()?.split('"').nth(1)?;
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    match parts.next()?.parse().ok()? {
+        1 => parts.next()?.parse().ok(),
+        major => Some(major),
+    }
+}
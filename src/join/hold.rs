@@ -1,22 +1,48 @@
-use std::ops::Deref;
 use std::sync::Arc;
 use std::time::Duration;
 
 use bytes::BytesMut;
+use minecraft_protocol::decoder::Decoder;
+use minecraft_protocol::version::v1_14_4::login::{LoginPluginRequest, LoginPluginResponse};
 use tokio::net::TcpStream;
-use tokio::time;
+use tokio::time::{self, Instant};
 
 use crate::config::*;
+use crate::proto::client::{Client, ClientInfo};
+use crate::proto::{packet, packets};
 use crate::server::{Server, State};
 use crate::service;
 
 use super::MethodResult;
 
+/// Channel of login plugin requests sent to held clients as keep-alive.
+const KEEP_ALIVE_CHANNEL: &str = "lazymc:keep_alive";
+
+/// Lowest protocol version supporting login plugin requests, Minecraft 1.13.
+const KEEP_ALIVE_MIN_PROTOCOL: u32 = 393;
+
+/// Timeout for a held client to respond to a keep-alive.
+const KEEP_ALIVE_RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Holding outcome.
+enum Held {
+    /// Server is ready, client should be proxied.
+    Ready,
+
+    /// Server stopped or failed to start.
+    Stopped,
+
+    /// Holding timed out.
+    TimedOut,
+}
+
 /// Hold the client.
 pub async fn occupy(
+    client: &Client,
+    client_info: &ClientInfo,
     config: Arc<Config>,
     server: Arc<Server>,
-    inbound: TcpStream,
+    mut inbound: TcpStream,
     inbound_history: &mut BytesMut,
 ) -> Result<MethodResult, ()> {
     trace!(target: "lazymc", "Using hold method to occupy joining client");
@@ -27,75 +53,151 @@ pub async fn occupy(
     }
 
     // Start holding, consume client
-    if hold(&config, &server).await? {
-        service::server::route_proxy_queue(inbound, config, inbound_history.clone());
-        return Ok(MethodResult::Consumed);
+    let behavior = config.join.hold.behavior(client_info.protocol());
+    match hold(client, client_info, &server, &mut inbound, behavior).await? {
+        Held::Ready => {
+            service::server::route_proxy_queue(inbound, config, &server, inbound_history.clone());
+            Ok(MethodResult::Consumed)
+        }
+        Held::TimedOut => match behavior.on_timeout.method() {
+            Some(method) => Ok(MethodResult::Fallback(inbound, method)),
+            None => Ok(MethodResult::Continue(inbound)),
+        },
+        Held::Stopped => Ok(MethodResult::Continue(inbound)),
     }
-
-    Ok(MethodResult::Continue(inbound))
 }
 
 /// Hold a client while server starts.
 ///
-/// Returns holding status. `true` if client is held and it should be proxied, `false` it was held
-/// but it timed out.
-async fn hold<'a>(config: &Config, server: &Server) -> Result<bool, ()> {
+/// Sends keep-alives to the client while holding if enabled and supported.
+async fn hold(
+    client: &Client,
+    client_info: &ClientInfo,
+    server: &Server,
+    inbound: &mut TcpStream,
+    behavior: HoldBehavior,
+) -> Result<Held, ()> {
     trace!(target: "lazymc", "Started holding client");
 
     // A task to wait for suitable server state
     // Waits for started state, errors if stopping/stopped state is reached
     let task_wait = async {
-        let mut state = server.state_receiver();
-        loop {
-            // Wait for state change
-            state.changed().await.unwrap();
-
-            match state.borrow().deref() {
-                // Still waiting on server start
-                State::Starting => {
-                    trace!(target: "lazymc", "Server not ready, holding client for longer");
-                    continue;
-                }
-
-                // Server started, start relaying and proxy
-                State::Started => {
-                    break true;
-                }
-
-                // Server stopping, this shouldn't happen, kick
-                State::Stopping => {
-                    warn!(target: "lazymc", "Server stopping for held client, disconnecting");
-                    break false;
-                }
-
-                // Server stopped, this shouldn't happen, disconnect
-                State::Stopped => {
-                    error!(target: "lazymc", "Server stopped for held client, disconnecting");
-                    break false;
-                }
+        match server
+            .wait_for_state(|state| state != State::Starting)
+            .await
+        {
+            // Server started, start relaying and proxy
+            State::Started => true,
+
+            // Server stopping, this shouldn't happen, kick
+            State::Stopping => {
+                warn!(target: "lazymc", "Server stopping for held client, disconnecting");
+                false
+            }
+
+            // Server failed to start, continue with next method to inform client
+            State::Stopped if server.failed_to_start() => {
+                warn!(target: "lazymc", "Server failed to start for held client");
+                false
+            }
+
+            // Server stopped, this shouldn't happen, disconnect
+            State::Stopped => {
+                error!(target: "lazymc", "Server stopped for held client, disconnecting");
+                false
             }
+
+            State::Starting => unreachable!(),
         }
     };
+    tokio::pin!(task_wait);
+
+    // Wait for server state with timeout, send keep-alive at interval in between
+    let keep_alive = behavior.keep_alive > 0
+        && client_info
+            .protocol()
+            .map(|protocol| protocol >= KEEP_ALIVE_MIN_PROTOCOL)
+            .unwrap_or(false);
+    let deadline = Instant::now() + Duration::from_secs(behavior.timeout as u64);
+    let mut message_id = 0;
+    loop {
+        let until = match keep_alive {
+            true => deadline.min(Instant::now() + Duration::from_secs(behavior.keep_alive as u64)),
+            false => deadline,
+        };
+
+        match time::timeout_at(until, &mut task_wait).await {
+            // Relay client to proxy
+            Ok(true) => {
+                info!(target: "lazymc", "Server ready for held client, relaying to server");
+                return Ok(Held::Ready);
+            }
 
-    // Wait for server state with timeout
-    let timeout = Duration::from_secs(config.join.hold.timeout as u64);
-    match time::timeout(timeout, task_wait).await {
-        // Relay client to proxy
-        Ok(true) => {
-            info!(target: "lazymc", "Server ready for held client, relaying to server");
-            Ok(true)
-        }
+            // Server stopping/stopped, this shouldn't happen, kick
+            Ok(false) => {
+                warn!(target: "lazymc", "Server stopping for held client");
+                return Ok(Held::Stopped);
+            }
 
-        // Server stopping/stopped, this shouldn't happen, kick
-        Ok(false) => {
-            warn!(target: "lazymc", "Server stopping for held client");
-            Ok(false)
-        }
+            // Timeout reached, continue with timeout behavior
+            Err(_) if until >= deadline => {
+                warn!(target: "lazymc", "Held client reached timeout of {}s", behavior.timeout);
+                return Ok(Held::TimedOut);
+            }
 
-        // Timeout reached, kick with starting message
-        Err(_) => {
-            warn!(target: "lazymc", "Held client reached timeout of {}s", config.join.hold.timeout);
-            Ok(false)
+            // Keep client alive
+            Err(_) => {
+                message_id += 1;
+                send_keep_alive(client, inbound, message_id).await?;
+            }
         }
     }
 }
+
+/// Send keep-alive to held client, and consume its response.
+///
+/// Uses a login plugin request the client doesn't understand, which it must respond to. The
+/// response is consumed so it isn't relayed to the server later.
+async fn send_keep_alive(
+    client: &Client,
+    inbound: &mut TcpStream,
+    message_id: i32,
+) -> Result<(), ()> {
+    trace!(target: "lazymc", "Sending keep-alive to held client");
+
+    let (mut reader, mut writer) = inbound.split();
+    packet::write_packet(
+        LoginPluginRequest {
+            message_id,
+            channel: KEEP_ALIVE_CHANNEL.into(),
+            data: vec![],
+        },
+        client,
+        &mut writer,
+    )
+    .await?;
+
+    // Client sends nothing else while logging in, the buffer only holds the response
+    let mut buf = BytesMut::new();
+    let (packet, _raw) = time::timeout(
+        KEEP_ALIVE_RESPONSE_TIMEOUT,
+        packet::read_packet(client, &mut buf, &mut reader),
+    )
+    .await
+    .map_err(|_| {
+        debug!(target: "lazymc", "Held client did not respond to keep-alive, disconnecting");
+    })??
+    .ok_or(())?;
+
+    if packet.id != packets::login::SERVER_LOGIN_PLUGIN_RESPONSE {
+        debug!(target: "lazymc", "Held client sent unexpected packet in response to keep-alive, disconnecting");
+        return Err(());
+    }
+    let response = LoginPluginResponse::decode(&mut packet.data.as_slice()).map_err(|_| ())?;
+    if response.message_id != message_id || !buf.is_empty() {
+        debug!(target: "lazymc", "Held client sent unexpected keep-alive response, disconnecting");
+        return Err(());
+    }
+
+    Ok(())
+}
@@ -3,14 +3,16 @@ use tokio::net::TcpStream;
 use crate::config::*;
 use crate::net;
 use crate::proto::action;
-use crate::proto::client::Client;
+use crate::proto::client::{Client, ClientInfo};
 use crate::server::{self, Server};
+use crate::util::cli::format_duration;
 
 use super::MethodResult;
 
 /// Kick the client.
 pub async fn occupy(
     client: &Client,
+    client_info: &ClientInfo,
     config: &Config,
     server: &Server,
     mut inbound: TcpStream,
@@ -18,12 +20,22 @@ pub async fn occupy(
     trace!(target: "lazymc", "Using kick method to occupy joining client");
 
     // Select message and kick
+    let kick = &config.join.kick;
     let msg = match server.state() {
+        server::State::Stopped if server.failed_to_start() => kick.start_failed.pick(kick.rotation),
         server::State::Starting | server::State::Stopped | server::State::Started => {
-            &config.join.kick.starting
+            kick.starting.pick(kick.rotation)
         }
-        server::State::Stopping => &config.join.kick.stopping,
+        server::State::Stopping if server.wake_pending() => kick.stopping_wake.pick(kick.rotation),
+        server::State::Stopping => kick.stopping.pick(kick.rotation),
     };
+    let msg = &queue_placeholders(msg, client, client_info, server).await;
+
+    // Let scripts compute kick message
+    #[cfg(feature = "rhai")]
+    let msg = &crate::script::kick_message(server.state(), server.players_online().await, msg)
+        .unwrap_or_else(|| msg.to_string());
+
     action::kick(client, msg, &mut inbound.split().1).await?;
 
     // Gracefully close connection
@@ -31,3 +43,32 @@ pub async fn occupy(
 
     Ok(MethodResult::Consumed)
 }
+
+/// Replace starting kick message placeholders.
+///
+/// Supports `%queue_position%` with the position of the player among players waiting for the
+/// server to start, and `%eta%` with the estimated time until the server is started.
+async fn queue_placeholders(
+    msg: &str,
+    client: &Client,
+    client_info: &ClientInfo,
+    server: &Server,
+) -> String {
+    if !msg.contains('%') {
+        return msg.into();
+    }
+
+    let player = match &client_info.username {
+        Some(username) => username.clone(),
+        None => client.peer.ip().to_string(),
+    };
+    let position = server.queue_position(&player).await;
+    let eta = match server.start_eta().await {
+        Some(eta) if eta.as_secs() > 0 => format!("~{}", format_duration(eta.as_secs())),
+        Some(_) => "any moment".into(),
+        None => "unknown".into(),
+    };
+
+    msg.replace("%queue_position%", &position.to_string())
+        .replace("%eta%", &eta)
+}
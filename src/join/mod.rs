@@ -1,7 +1,9 @@
+use std::collections::VecDeque;
 use std::sync::Arc;
 
 use bytes::BytesMut;
 use tokio::net::TcpStream;
+use tracing::Instrument;
 
 use crate::config::*;
 use crate::net;
@@ -21,6 +23,9 @@ pub enum MethodResult {
 
     /// Method is done, continue with the next.
     Continue(TcpStream),
+
+    /// Method is done, continue with the given method before the next.
+    Fallback(TcpStream, Method),
 }
 
 /// Start occupying client.
@@ -28,7 +33,7 @@ pub enum MethodResult {
 /// This assumes the login start packet has just been received.
 pub async fn occupy(
     client: Client,
-    #[allow(unused_variables)] client_info: ClientInfo,
+    client_info: ClientInfo,
     config: Arc<Config>,
     server: Arc<Server>,
     mut inbound: TcpStream,
@@ -43,55 +48,77 @@ pub async fn occupy(
     );
 
     // Go through all configured join methods
-    for method in &config.join.methods {
+    let mut methods: VecDeque<Method> = config.join.methods.iter().copied().collect();
+    while let Some(method) = methods.pop_front() {
+        let span =
+            tracing::info_span!("join_method", method = ?method, result = tracing::field::Empty);
+
         // Invoke method, take result
-        let result = match method {
-            // Kick method, immediately kick client
-            Method::Kick => kick::occupy(&client, &config, &server, inbound).await?,
-
-            // Hold method, hold client connection while server starts
-            Method::Hold => {
-                hold::occupy(
-                    config.clone(),
-                    server.clone(),
-                    inbound,
-                    &mut inbound_history,
-                )
-                .await?
-            }
+        let result = async {
+            Ok::<_, ()>(match method {
+                // Kick method, immediately kick client
+                Method::Kick => {
+                    kick::occupy(&client, &client_info, &config, &server, inbound).await?
+                }
 
-            // Forward method, forward client connection while server starts
-            Method::Forward => {
-                forward::occupy(config.clone(), inbound, &mut inbound_history).await?
-            }
+                // Hold method, hold client connection while server starts
+                Method::Hold => {
+                    hold::occupy(
+                        &client,
+                        &client_info,
+                        config.clone(),
+                        server.clone(),
+                        inbound,
+                        &mut inbound_history,
+                    )
+                    .await?
+                }
 
-            // Lobby method, keep client in lobby while server starts
-            #[cfg(feature = "lobby")]
-            Method::Lobby => {
-                lobby::occupy(
-                    &client,
-                    client_info.clone(),
-                    config.clone(),
-                    server.clone(),
-                    inbound,
-                    login_queue.clone(),
-                )
-                .await?
-            }
+                // Forward method, forward client connection while server starts
+                Method::Forward => {
+                    forward::occupy(config.clone(), inbound, &mut inbound_history).await?
+                }
 
-            // Lobby method, keep client in lobby while server starts
-            #[cfg(not(feature = "lobby"))]
-            Method::Lobby => {
-                error!(target: "lazymc", "Lobby join method not supported in this lazymc build");
-                MethodResult::Continue(inbound)
-            }
-        };
+                // Lobby method, keep client in lobby while server starts
+                #[cfg(feature = "lobby")]
+                Method::Lobby => {
+                    lobby::occupy(
+                        &client,
+                        client_info.clone(),
+                        config.clone(),
+                        server.clone(),
+                        inbound,
+                        login_queue.clone(),
+                    )
+                    .await?
+                }
+
+                // Lobby method, keep client in lobby while server starts
+                #[cfg(not(feature = "lobby"))]
+                Method::Lobby => {
+                    error!(target: "lazymc", "Lobby join method not supported in this lazymc build");
+                    MethodResult::Continue(inbound)
+                }
+            })
+        }
+        .instrument(span.clone())
+        .await?;
 
         // Handle method result
         match result {
-            MethodResult::Consumed => return Ok(()),
+            MethodResult::Consumed => {
+                span.record("result", "consumed");
+                return Ok(());
+            }
             MethodResult::Continue(stream) => {
+                span.record("result", "continue");
+                inbound = stream;
+                continue;
+            }
+            MethodResult::Fallback(stream, fallback) => {
+                span.record("result", "fallback");
                 inbound = stream;
+                methods.push_front(fallback);
                 continue;
             }
         }
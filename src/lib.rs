@@ -0,0 +1,95 @@
+//! Put your Minecraft server to rest when idle.
+//!
+//! This crate provides the sleep/wake logic of lazymc as library, so it can be embedded in other
+//! projects such as server panels and launchers. The `lazymc` binary is a thin consumer of it.
+//!
+//! The main parts are:
+//!
+//! - [`config`]: configuration, as loaded from `lazymc.toml`.
+//! - [`server`]: shared server state and state machine, to start and stop the server.
+//! - [`event`]: events published by the server, see [`server::Server::subscribe`].
+//! - [`proxy`]: proxy client connections to the server.
+//! - [`join`]: join methods, to occupy clients while the server starts.
+//! - [`service`]: long running tasks, [`service::server::serve`] runs everything.
+//! - [`error`]: error type for server, proxy and config operations.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use lazymc::config::Config;
+//! use lazymc::server::Server;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let config = Arc::new(Config::load("lazymc.toml".into()).unwrap());
+//!     let server = Arc::new(Server::default());
+//!
+//!     // Subscribe to server events before serving
+//!     let mut events = server.subscribe();
+//!     tokio::spawn(async move {
+//!         while let Ok(event) = events.recv().await {
+//!             println!("{:?}", event);
+//!         }
+//!     });
+//!
+//!     lazymc::service::server::serve(config, server).await.unwrap();
+//! }
+//! ```
+
+// Errors are reported where they occur, callers only need to know whether it failed
+#![allow(clippy::result_unit_err)]
+
+#[macro_use]
+extern crate anyhow;
+#[macro_use]
+extern crate clap;
+#[macro_use]
+extern crate derive_builder;
+#[macro_use]
+extern crate log;
+
+pub(crate) mod action;
+pub(crate) mod backup;
+pub mod cli;
+pub mod config;
+pub(crate) mod daemon;
+pub mod error;
+pub mod event;
+pub(crate) mod forge;
+pub(crate) mod honeypot;
+pub(crate) mod java;
+pub mod join;
+#[cfg(feature = "lobby")]
+pub(crate) mod lobby;
+pub(crate) mod logger;
+pub(crate) mod login;
+pub(crate) mod mc;
+pub(crate) mod metrics;
+pub(crate) mod monitor;
+pub(crate) mod net;
+pub(crate) mod os;
+pub(crate) mod panel;
+#[cfg(feature = "wasm")]
+pub(crate) mod plugin;
+pub(crate) mod probe;
+pub(crate) mod proto;
+pub mod proxy;
+#[cfg(feature = "rhai")]
+pub(crate) mod script;
+pub mod server;
+pub mod service;
+pub(crate) mod stats;
+pub(crate) mod status;
+#[cfg(feature = "otel")]
+pub(crate) mod telemetry;
+pub(crate) mod thread_dump;
+pub(crate) mod types;
+pub(crate) mod updater;
+pub(crate) mod util;
+pub(crate) mod wake_link;
+
+// Compile time feature compatability check.
+#[cfg(all(windows, not(feature = "rcon")))]
+compile_error!("Must enable \"rcon\" feature on Windows.");
@@ -1,5 +1,4 @@
 use std::io::ErrorKind;
-use std::ops::Deref;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -20,10 +19,12 @@ use crate::forge;
 use crate::mc::uuid;
 use crate::net;
 use crate::proto;
+use crate::proto::action;
 use crate::proto::client::{Client, ClientInfo, ClientState};
+use crate::proto::packet::RawPacket;
 use crate::proto::packets::play::join_game::JoinGameData;
 use crate::proto::{packet, packets};
-use crate::proxy;
+use crate::proxy::{self, ProxyOptions, Target, TargetStream};
 use crate::server::{Server, State};
 
 /// Interval to send keep-alive packets at.
@@ -121,13 +122,23 @@ pub async fn serve(
             // Send packets to client required to get into workable play state for lobby world
             send_lobby_play_packets(client, &client_info, &mut writer, &server).await?;
 
-            // Wait for server to come online
-            stage_wait(client, &client_info, &server, &config, &mut writer).await?;
+            // Wait for server to come online, tell client if it failed to start
+            if stage_wait(client, &client_info, &server, &config, &mut writer)
+                .await
+                .is_err()
+            {
+                if server.failed_to_start() {
+                    let kick = &config.join.kick;
+                    action::kick(client, kick.start_failed.pick(kick.rotation), &mut writer)
+                        .await?;
+                }
+                return Err(());
+            }
 
             // Start new connection to server
             let server_client_info = client_info.clone();
             let (server_client, mut outbound, mut server_buf) =
-                connect_to_server(&server_client_info, &inbound, &config).await?;
+                connect_to_server(&server_client_info, &inbound, &config, &server).await?;
             let (returned_reader, returned_writer) = inbound.split();
             reader = returned_reader;
             writer = returned_writer;
@@ -164,7 +175,11 @@ pub async fn serve(
 
             // Client and server connection ready now, move client to proxy
             debug!(target: "lazymc::lobby", "Server connection ready, relaying lobby client to proxy");
-            route_proxy(inbound, outbound, server_buf);
+            let options = ProxyOptions {
+                username: client_info.username.clone(),
+                ..ProxyOptions::from_config(&config)
+            };
+            route_proxy(inbound, outbound, server_buf, options);
 
             return Ok(());
         }
@@ -305,29 +320,11 @@ async fn wait_for_server(server: &Server, config: &Config) -> Result<(), ()> {
     // A task to wait for suitable server state
     // Waits for started state, errors if stopping/stopped state is reached
     let task_wait = async {
-        let mut state = server.state_receiver();
-        loop {
-            // Wait for state change
-            state.changed().await.unwrap();
-
-            match state.borrow().deref() {
-                // Still waiting on server start
-                State::Starting => {
-                    trace!(target: "lazymc::lobby", "Server not ready, holding client for longer");
-                    continue;
-                }
-
-                // Server started, start relaying and proxy
-                State::Started => {
-                    break true;
-                }
-
-                // Server stopping, this shouldn't happen, kick
-                State::Stopping | State::Stopped => {
-                    break false;
-                }
-            }
-        }
+        // Server stopping/stopped, this shouldn't happen, kick
+        server
+            .wait_for_state(|state| state != State::Starting)
+            .await
+            == State::Started
     };
 
     // Wait for server state with timeout
@@ -358,10 +355,11 @@ async fn connect_to_server(
     client_info: &ClientInfo,
     inbound: &TcpStream,
     config: &Config,
-) -> Result<(Client, TcpStream, BytesMut), ()> {
+    server: &Server,
+) -> Result<(Client, TargetStream, BytesMut), ()> {
     time::timeout(
         SERVER_CONNECT_TIMEOUT,
-        connect_to_server_no_timeout(client_info, inbound, config),
+        connect_to_server_no_timeout(client_info, inbound, config, server),
     )
     .await
     .map_err(|_| {
@@ -377,12 +375,15 @@ async fn connect_to_server_no_timeout(
     client_info: &ClientInfo,
     inbound: &TcpStream,
     config: &Config,
-) -> Result<(Client, TcpStream, BytesMut), ()> {
+    server: &Server,
+) -> Result<(Client, TargetStream, BytesMut), ()> {
     // Open connection
     // TODO: on connect fail, ping server and redirect to serve_status if offline
-    let mut outbound = TcpStream::connect(config.server.address)
+    let mut outbound = Target::server(config, server)
+        .connect()
         .await
         .map_err(|_| ())?;
+    outbound.configure(&config.proxy.server).map_err(|_| ())?;
 
     // Add proxy header
     if config.server.send_proxy_v2 {
@@ -395,13 +396,11 @@ async fn connect_to_server_no_timeout(
 
     // Construct temporary server client
     let tmp_client = match outbound.local_addr() {
-        Ok(addr) => Client::new(addr),
-        Err(_) => Client::dummy(),
+        Some(addr) => Client::new(addr),
+        None => Client::dummy(),
     };
     tmp_client.set_state(ClientState::Login);
 
-    let (mut reader, mut writer) = outbound.split();
-
     // Replay client handshake packet
     assert_eq!(
         client_info.handshake.as_ref().unwrap().next_state,
@@ -411,26 +410,35 @@ async fn connect_to_server_no_timeout(
     packet::write_packet(
         client_info.handshake.clone().unwrap(),
         &tmp_client,
-        &mut writer,
+        &mut outbound,
     )
     .await?;
 
-    // Request login start
-    packet::write_packet(
-        LoginStart {
-            name: client_info.username.clone().ok_or(())?,
-        },
-        &tmp_client,
-        &mut writer,
-    )
-    .await?;
+    // Replay client login start, keeps chat signing data, or build it from username
+    match &client_info.login_start {
+        Some(data) => {
+            let raw = RawPacket::new(packets::login::SERVER_LOGIN_START, data.clone())
+                .encode_with_len(&tmp_client)?;
+            outbound.write_all(&raw).await.map_err(|_| ())?;
+        }
+        None => {
+            packet::write_packet(
+                LoginStart {
+                    name: client_info.username.clone().ok_or(())?,
+                },
+                &tmp_client,
+                &mut outbound,
+            )
+            .await?
+        }
+    }
 
     // Incoming buffer
     let mut buf = BytesMut::new();
 
     loop {
         // Read packet from stream
-        let (packet, _raw) = match packet::read_packet(&tmp_client, &mut buf, &mut reader).await {
+        let (packet, _raw) = match packet::read_packet(&tmp_client, &mut buf, &mut outbound).await {
             Ok(Some(packet)) => packet,
             Ok(None) => break,
             Err(_) => {
@@ -491,7 +499,7 @@ async fn connect_to_server_no_timeout(
                 trace!(target: "lazymc::lobby", "Got login plugin request from server, responding with Forge reply");
 
                 // Respond to Forge login plugin request
-                forge::respond_login_plugin_request(&tmp_client, plugin_request, &mut writer)
+                forge::respond_login_plugin_request(&tmp_client, plugin_request, &mut outbound)
                     .await?;
 
                 continue;
@@ -507,7 +515,7 @@ async fn connect_to_server_no_timeout(
                     data: vec![],
                 },
                 &tmp_client,
-                &mut writer,
+                &mut outbound,
             )
             .await?;
 
@@ -569,7 +577,7 @@ async fn connect_to_server_no_timeout(
 async fn wait_for_server_join_game(
     client: &Client,
     client_info: &ClientInfo,
-    outbound: &mut TcpStream,
+    outbound: &mut TargetStream,
     buf: &mut BytesMut,
 ) -> Result<JoinGameData, ()> {
     time::timeout(
@@ -590,14 +598,12 @@ async fn wait_for_server_join_game(
 async fn wait_for_server_join_game_no_timeout(
     client: &Client,
     client_info: &ClientInfo,
-    outbound: &mut TcpStream,
+    outbound: &mut TargetStream,
     buf: &mut BytesMut,
 ) -> Result<JoinGameData, ()> {
-    let (mut reader, mut _writer) = outbound.split();
-
     loop {
         // Read packet from stream
-        let (packet, _raw) = match packet::read_packet(client, buf, &mut reader).await {
+        let (packet, _raw) = match packet::read_packet(client, buf, outbound).await {
             Ok(Some(packet)) => packet,
             Ok(None) => break,
             Err(_) => {
@@ -632,10 +638,15 @@ async fn wait_for_server_join_game_no_timeout(
 /// `inbound_queue` is used for data already received from the server, that needs to be pushed to
 /// the client.
 #[inline]
-pub fn route_proxy(inbound: TcpStream, outbound: TcpStream, inbound_queue: BytesMut) {
+pub fn route_proxy(
+    inbound: TcpStream,
+    outbound: TargetStream,
+    inbound_queue: BytesMut,
+    options: ProxyOptions,
+) {
     // When server is online, proxy all
     let service = async move {
-        proxy::proxy_inbound_outbound_with_queue(inbound, outbound, &inbound_queue, &[])
+        proxy::proxy_inbound_outbound_with_queue(inbound, outbound, &inbound_queue, &[], options)
             .map(|r| {
                 if let Err(err) = r {
                     warn!(target: "lazymc", "Failed to proxy: {}", err);
@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, RwLock};
+
+use chrono::{DateTime, Local};
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::config::{Config, LogFormat, LogRotate};
+
+/// Global logger, configurable at runtime.
+static LOGGER: Logger = Logger {
+    inner: RwLock::new(None),
+};
+
+/// Logger that can be reconfigured once the configuration is loaded.
+struct Logger {
+    inner: RwLock<Option<Inner>>,
+}
+
+/// Configured logger state.
+struct Inner {
+    /// Pretty logger, also used for filtering records.
+    pretty: Box<dyn Log>,
+
+    /// Output format.
+    format: LogFormat,
+
+    /// Server name to include in structured output.
+    name: Option<String>,
+
+    /// Log file to write to in addition to standard error.
+    file: Option<Mutex<LogFile>>,
+}
+
+/// Log file with rotation.
+struct LogFile {
+    /// Path of current log file.
+    path: PathBuf,
+
+    /// Opened log file.
+    file: File,
+
+    /// Current log file size in bytes.
+    size: u64,
+
+    /// Time current log file was opened at.
+    opened: DateTime<Local>,
+
+    /// Rotate when file exceeds size in bytes, 0 to disable.
+    max_size: u64,
+
+    /// Rotate based on time.
+    rotate: LogRotate,
+
+    /// Number of rotated files to keep.
+    keep: usize,
+
+    /// Write JSON lines instead of plain text.
+    json: bool,
+}
+
+/// Initialize logger with default configuration.
+///
+/// Log levels are taken from `RUST_LOG`.
+pub fn init() {
+    let (pretty, max_level) = build_pretty(&HashMap::new());
+    *LOGGER.inner.write().unwrap() = Some(Inner {
+        pretty,
+        format: LogFormat::Pretty,
+        name: None,
+        file: None,
+    });
+
+    log::set_logger(&LOGGER).expect("failed to initialize logger");
+    log::set_max_level(max_level);
+}
+
+/// Reconfigure logger based on loaded configuration.
+pub fn configure(config: &Config) {
+    let (pretty, max_level) = build_pretty(&config.log.levels);
+
+    // Open log file, relative to config directory
+    let file = config.log.file.as_ref().and_then(|path| {
+        let path = match config.path.as_ref().and_then(|p| p.parent()) {
+            Some(dir) => dir.join(path),
+            None => path.clone(),
+        };
+        match LogFile::open(&config.log, path.clone()) {
+            Ok(file) => Some(Mutex::new(file)),
+            Err(err) => {
+                eprintln!("Failed to open log file at {}: {}", path.display(), err);
+                None
+            }
+        }
+    });
+
+    *LOGGER.inner.write().unwrap() = Some(Inner {
+        pretty,
+        format: config.log.format,
+        name: config.log.name.clone(),
+        file,
+    });
+    log::set_max_level(max_level);
+}
+
+/// Build pretty logger, returns it with its maximum log level.
+///
+/// Applies the given per-target log levels, `RUST_LOG` takes precedence.
+fn build_pretty(levels: &HashMap<String, String>) -> (Box<dyn Log>, LevelFilter) {
+    let mut builder = pretty_env_logger::formatted_builder();
+    for (target, level) in levels {
+        match LevelFilter::from_str(level) {
+            Ok(level) => {
+                builder.filter_module(target, level);
+            }
+            Err(_) => eprintln!("Ignoring invalid log level '{level}' for '{target}' in config"),
+        }
+    }
+    builder.parse_filters(&std::env::var("RUST_LOG").unwrap_or_default());
+
+    let logger = builder.build();
+    let max_level = logger.filter();
+    (Box::new(logger), max_level)
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        match &*self.inner.read().unwrap() {
+            Some(inner) => inner.pretty.enabled(metadata),
+            None => false,
+        }
+    }
+
+    fn log(&self, record: &Record) {
+        let inner = self.inner.read().unwrap();
+        let inner = match &*inner {
+            Some(inner) if inner.pretty.enabled(record.metadata()) => inner,
+            _ => return,
+        };
+
+        match inner.format {
+            LogFormat::Pretty => inner.pretty.log(record),
+            LogFormat::Json => {
+                let _ = writeln!(io::stderr().lock(), "{}", json_line(inner, record));
+            }
+        }
+
+        if let Some(file) = &inner.file {
+            let mut file = file.lock().unwrap();
+            let line = if file.json {
+                json_line(inner, record)
+            } else {
+                plain_line(record)
+            };
+            file.write_line(&line);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(inner) = &*self.inner.read().unwrap() {
+            inner.pretty.flush();
+            if let Some(file) = &inner.file {
+                let _ = file.lock().unwrap().file.flush();
+            }
+        }
+    }
+}
+
+/// Format record as single line JSON object.
+fn json_line(inner: &Inner, record: &Record) -> String {
+    let mut line = serde_json::json!({
+        "timestamp": Local::now().to_rfc3339(),
+        "level": record.level().as_str(),
+        "target": record.target(),
+        "message": record.args().to_string(),
+    });
+    if let Some(name) = &inner.name {
+        line["server"] = name.as_str().into();
+    }
+    line.to_string()
+}
+
+/// Format record as plain single line.
+fn plain_line(record: &Record) -> String {
+    format!(
+        "{} {:<5} {} > {}",
+        Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z"),
+        record.level(),
+        record.target(),
+        record.args(),
+    )
+}
+
+impl LogFile {
+    /// Open log file for appending.
+    fn open(config: &crate::config::Log, path: PathBuf) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            size,
+            opened: Local::now(),
+            max_size: config.max_size * 1024 * 1024,
+            rotate: config.rotate,
+            keep: config.keep,
+            json: config.format == LogFormat::Json,
+        })
+    }
+
+    /// Write line to log file, rotate first if required.
+    fn write_line(&mut self, line: &str) {
+        if self.should_rotate() {
+            if let Err(err) = self.rotate() {
+                eprintln!(
+                    "Failed to rotate log file at {}: {}",
+                    self.path.display(),
+                    err
+                );
+            }
+        }
+
+        if writeln!(self.file, "{line}").is_ok() {
+            self.size += line.len() as u64 + 1;
+        }
+    }
+
+    /// Check whether log file should be rotated.
+    fn should_rotate(&self) -> bool {
+        if self.max_size > 0 && self.size >= self.max_size {
+            return true;
+        }
+
+        let now = Local::now();
+        match self.rotate {
+            LogRotate::Never => false,
+            LogRotate::Daily => now.date_naive() != self.opened.date_naive(),
+            LogRotate::Hourly => {
+                now.format("%Y%m%d%H").to_string() != self.opened.format("%Y%m%d%H").to_string()
+            }
+        }
+    }
+
+    /// Rotate log file, remove old rotated files exceeding retention.
+    fn rotate(&mut self) -> io::Result<()> {
+        let rotated = rotated_path(
+            &self.path,
+            &Local::now().format("%Y%m%d-%H%M%S").to_string(),
+        );
+        fs::rename(&self.path, rotated)?;
+
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        self.opened = Local::now();
+
+        self.prune()
+    }
+
+    /// Remove old rotated log files, keeping configured number of newest files.
+    fn prune(&self) -> io::Result<()> {
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let prefix = rotated_path(&self.path, "");
+        let prefix = prefix
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+
+        // Rotated names contain timestamp, sort chronologically
+        let mut rotated: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .map(|n| n.starts_with(prefix) && n.len() > prefix.len())
+                    .unwrap_or(false)
+            })
+            .collect();
+        rotated.sort();
+
+        let remove = rotated.len().saturating_sub(self.keep);
+        for path in rotated.into_iter().take(remove) {
+            fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Get path for rotated log file with given suffix.
+fn rotated_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(suffix);
+    path.into()
+}
@@ -0,0 +1,172 @@
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::config::{Config, Server as ConfigServer};
+use crate::server::Server;
+use crate::util;
+
+/// Throttles repeated failed login attempts per IP.
+///
+/// Only logins denied by authorization hooks count as failed. Usernames are not tracked, so
+/// nobody can get another player throttled by failing logins with their username.
+#[derive(Debug, Default)]
+pub struct LoginThrottle {
+    /// Times of recent failed logins per IP, oldest first.
+    failures: Mutex<HashMap<IpAddr, VecDeque<Instant>>>,
+}
+
+impl LoginThrottle {
+    /// Check whether logins from the given IP are throttled.
+    pub async fn is_throttled(&self, config: &Config, ip: IpAddr) -> bool {
+        let max = config.login.max_failures as usize;
+        if max == 0 {
+            return false;
+        }
+
+        let window = Duration::from_secs(config.login.failure_window as u64);
+        let now = Instant::now();
+        let mut failures = self.failures.lock().await;
+        failures.retain(|_, times| {
+            while times.front().map_or(false, |t| now - *t > window) {
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+
+        failures.get(&ip).map_or(false, |times| times.len() >= max)
+    }
+
+    /// Record failed login attempt.
+    pub async fn fail(&self, config: &Config, ip: IpAddr) {
+        if config.login.max_failures == 0 {
+            return;
+        }
+
+        let now = Instant::now();
+        self.failures
+            .lock()
+            .await
+            .entry(ip)
+            .or_default()
+            .push_back(now);
+    }
+
+    /// Forget failed login attempts after a successful login.
+    pub async fn succeed(&self, ip: IpAddr) {
+        self.failures.lock().await.remove(&ip);
+    }
+}
+
+/// Ask configured authorization hooks whether a player may wake the server.
+///
+/// Returns the kick message if denied.
+pub async fn authorize(
+    config: &Config,
+    server: &Server,
+    username: Option<&str>,
+    ip: IpAddr,
+) -> Result<(), String> {
+    if let Some(command) = &config.login.auth_command {
+        authorize_command(config, command, username, ip).await?;
+    }
+
+    if let Some(url) = &config.login.webhook.url {
+        let uuid = match username {
+            Some(username) => Some(server.uuids().resolve(config, username).await),
+            None => None,
+        };
+        authorize_webhook(config, url, username, uuid, ip).await?;
+    }
+
+    Ok(())
+}
+
+/// Run authorization command to check whether a player may wake the server.
+///
+/// Waking is allowed if the command exits successfully.
+async fn authorize_command(
+    config: &Config,
+    command: &str,
+    username: Option<&str>,
+    ip: IpAddr,
+) -> Result<(), String> {
+    let mut args = match ConfigServer::resolve_command(config, command) {
+        Some(args) => args,
+        None => {
+            error!(target: "lazymc", "Invalid authorization command, denying login: {}", command);
+            return Err(config.login.auth_command_message.clone());
+        }
+    };
+    args.push(username.unwrap_or_default().into());
+    args.push(ip.to_string());
+
+    let dir = ConfigServer::server_directory(config);
+    let timeout = Duration::from_secs(config.server.command_timeout as u64);
+    match util::command::run(&args, dir.as_deref(), timeout).await {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            info!(target: "lazymc", "Authorization command denied login from {}, {}", ip, err);
+            Err(config.login.auth_command_message.clone())
+        }
+    }
+}
+
+/// Webhook authorization response.
+#[derive(Debug, Deserialize)]
+struct WebhookResponse {
+    /// Whether the player may wake the server.
+    allow: bool,
+
+    /// Kick message if denied.
+    message: Option<String>,
+}
+
+/// Ask authorization webhook whether a player may wake the server.
+async fn authorize_webhook(
+    config: &Config,
+    url: &str,
+    username: Option<&str>,
+    uuid: Option<Uuid>,
+    ip: IpAddr,
+) -> Result<(), String> {
+    let webhook = &config.login.webhook;
+    let body = json!({
+        "username": username,
+        "uuid": uuid.map(|uuid| uuid.to_string()),
+        "ip": ip.to_string(),
+    });
+    let result = async {
+        reqwest::Client::new()
+            .post(url)
+            .json(&body)
+            .timeout(Duration::from_secs(webhook.timeout as u64))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<WebhookResponse>()
+            .await
+    }
+    .await;
+
+    match result {
+        Ok(response) if response.allow => Ok(()),
+        Ok(response) => {
+            info!(target: "lazymc", "Authorization webhook denied login from {}", ip);
+            Err(response.message.unwrap_or_else(|| webhook.message.clone()))
+        }
+        Err(err) if webhook.allow_on_error => {
+            warn!(target: "lazymc", "Authorization webhook failed, allowing login: {}", err);
+            Ok(())
+        }
+        Err(err) => {
+            warn!(target: "lazymc", "Authorization webhook failed, denying login: {}", err);
+            Err(webhook.message.clone())
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use serde_json::Value;
+
+/// Minecraft server software brand.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Brand {
+    Vanilla,
+    Paper,
+    Purpur,
+    Spigot,
+    Forge,
+    Fabric,
+}
+
+impl Brand {
+    /// Detect brand from raw server status JSON.
+    pub fn from_status(status: &Value) -> Option<Self> {
+        // Forge announces itself with mod data in status
+        if status.get("forgeData").is_some() || status["modinfo"]["type"] == "FML" {
+            return Some(Self::Forge);
+        }
+
+        let version = status["version"]["name"].as_str()?;
+        Self::from_name(version).or_else(|| {
+            // Plain version number, such as 1.20.1
+            version
+                .chars()
+                .all(|c| c.is_ascii_digit() || c == '.')
+                .then_some(Self::Vanilla)
+        })
+    }
+
+    /// Detect brand from server start command, such as the jar name.
+    pub fn from_command(command: &str) -> Option<Self> {
+        Self::from_name(command)
+    }
+
+    /// Detect brand from a name containing it.
+    fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        [
+            ("purpur", Self::Purpur),
+            ("paper", Self::Paper),
+            ("spigot", Self::Spigot),
+            ("bukkit", Self::Spigot),
+            ("forge", Self::Forge),
+            ("fabric", Self::Fabric),
+            ("quilt", Self::Fabric),
+        ]
+        .into_iter()
+        .find(|(pattern, _)| name.contains(pattern))
+        .map(|(_, brand)| brand)
+    }
+
+    /// Brand name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Vanilla => "vanilla",
+            Self::Paper => "paper",
+            Self::Purpur => "purpur",
+            Self::Spigot => "spigot",
+            Self::Forge => "forge",
+            Self::Fabric => "fabric",
+        }
+    }
+
+    /// Whether this brand is based on Bukkit, which lets plugins override vanilla commands.
+    pub fn is_bukkit(self) -> bool {
+        matches!(self, Self::Paper | Self::Purpur | Self::Spigot)
+    }
+
+    /// Get vanilla RCON command for this brand.
+    ///
+    /// Bukkit-based servers get the namespaced command, so plugins overriding it don't interfere.
+    pub fn vanilla_command(self, command: &str) -> String {
+        if self.is_bukkit() {
+            format!("minecraft:{command}")
+        } else {
+            command.into()
+        }
+    }
+
+    /// Whether this brand runs mods.
+    pub fn is_modded(self) -> bool {
+        matches!(self, Self::Forge | Self::Fabric)
+    }
+
+    /// Minimum start timeout in seconds, modded servers load much more on start.
+    pub fn min_start_timeout(self) -> u32 {
+        if self.is_modded() {
+            600
+        } else {
+            0
+        }
+    }
+
+    /// Minimum stop timeout in seconds, modded servers have more state to save on stop.
+    pub fn min_stop_timeout(self) -> u32 {
+        if self.is_modded() {
+            300
+        } else {
+            0
+        }
+    }
+}
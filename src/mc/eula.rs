@@ -0,0 +1,47 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// File name.
+pub const FILE: &str = "eula.txt";
+
+/// Accept the Minecraft EULA by writing eula.txt in server directory.
+///
+/// Does nothing if it is already accepted.
+pub fn accept_dir(dir: &Path) {
+    let path = dir.join(FILE);
+    match is_accepted(&path) {
+        Ok(true) => {}
+        Ok(false) => {
+            info!(target: "lazymc", "Accepting Minecraft EULA in {}", FILE);
+            if let Err(err) = accept(&path) {
+                error!(target: "lazymc", "Failed to write {} file: {}", FILE, err);
+            }
+        }
+        Err(err) => error!(target: "lazymc", "Failed to read {} file: {}", FILE, err),
+    }
+}
+
+/// Check whether EULA in given file is accepted.
+fn is_accepted(path: &Path) -> Result<bool, io::Error> {
+    if !path.is_file() {
+        return Ok(false);
+    }
+
+    let contents = fs::read_to_string(path)?;
+    Ok(contents
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .any(|(key, value)| key.trim() == "eula" && value.trim().eq_ignore_ascii_case("true")))
+}
+
+/// Write accepted EULA to given file.
+fn accept(path: &Path) -> Result<(), io::Error> {
+    fs::write(
+        path,
+        "#By changing the setting below to TRUE you are indicating your agreement to our EULA (https://aka.ms/MinecraftEULA).\n\
+         #Accepted by lazymc through server.accept_eula\n\
+         eula=true\n",
+    )
+}
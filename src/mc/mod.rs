@@ -1,11 +1,14 @@
 pub mod ban;
+pub mod brand;
 #[cfg(feature = "lobby")]
 pub mod dimension;
+pub mod eula;
 pub mod favicon;
+pub mod profile;
 #[cfg(feature = "rcon")]
 pub mod rcon;
 pub mod server_properties;
-#[cfg(feature = "lobby")]
+pub mod username;
 pub mod uuid;
 pub mod whitelist;
 
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::config::{Config, Login};
+use crate::mc::uuid::offline_player_uuid;
+
+/// Mojang API endpoint to look up a profile by username.
+const PROFILE_URL: &str = "https://api.mojang.com/users/profiles/minecraft/";
+
+/// Mojang API request timeout.
+const API_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Maximum number of cached UUIDs, the least recently resolved are evicted first.
+const CACHE_MAX: usize = 4096;
+
+/// Minimum interval between writing the cache to disk, new entries are written in batches.
+///
+/// Entries resolved within this interval of the last write are lost if lazymc quits before the
+/// next write, they are resolved again.
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Resolves player usernames to UUIDs.
+///
+/// Uses the Mojang API, with an on-disk cache. Falls back to offline mode UUIDs for unknown
+/// players, or if the Mojang API is disabled.
+#[derive(Debug, Default)]
+pub struct UuidResolver {
+    /// UUID cache, loaded from disk on first use.
+    cache: Mutex<Option<Cache>>,
+}
+
+/// UUID cache.
+#[derive(Debug, Default)]
+struct Cache {
+    /// Cached UUIDs by lowercase username.
+    entries: HashMap<String, CacheEntry>,
+
+    /// When entries were last written to disk.
+    saved_at: Option<Instant>,
+}
+
+/// Cached UUID.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    /// Player UUID.
+    uuid: String,
+
+    /// Resolved at, UNIX timestamp in seconds.
+    resolved_at: i64,
+}
+
+/// Mojang API profile.
+#[derive(Debug, Deserialize)]
+struct Profile {
+    /// Player UUID, without dashes.
+    id: String,
+}
+
+impl UuidResolver {
+    /// Resolve UUID for the given username.
+    pub async fn resolve(&self, config: &Config, username: &str) -> Uuid {
+        if !config.login.mojang_api {
+            return offline_player_uuid(username);
+        }
+
+        // Use cached UUID if still fresh
+        let key = username.to_lowercase();
+        let now = chrono::Utc::now().timestamp();
+        let cached = {
+            let mut cache = self.cache.lock().await;
+            if cache.is_none() {
+                *cache = Some(Cache {
+                    entries: load(config).await,
+                    ..Default::default()
+                });
+            }
+            cache
+                .as_ref()
+                .and_then(|cache| cache.entries.get(&key))
+                .and_then(|entry| {
+                    Uuid::parse_str(&entry.uuid)
+                        .ok()
+                        .map(|u| (u, entry.resolved_at))
+                })
+        };
+        if let Some((uuid, resolved_at)) = cached {
+            if now - resolved_at < config.login.uuid_cache_ttl as i64 {
+                return uuid;
+            }
+        }
+
+        // Fetch without holding the cache lock
+        match fetch(username).await {
+            Ok(Some(uuid)) => {
+                debug!(target: "lazymc", "Resolved UUID of '{}' through Mojang API: {}", username, uuid);
+                self.insert(config, key, uuid, now).await;
+                uuid
+            }
+            Ok(None) => {
+                debug!(target: "lazymc", "No Mojang profile for '{}', using offline UUID", username);
+                offline_player_uuid(username)
+            }
+            Err(err) => match cached {
+                Some((uuid, _)) => {
+                    warn!(target: "lazymc", "Failed to resolve UUID of '{}', using cached UUID: {}", username, err);
+                    uuid
+                }
+                None => {
+                    warn!(target: "lazymc", "Failed to resolve UUID of '{}', using offline UUID: {}", username, err);
+                    offline_player_uuid(username)
+                }
+            },
+        }
+    }
+
+    /// Cache resolved UUID, write cache to disk if not written recently.
+    async fn insert(&self, config: &Config, key: String, uuid: Uuid, now: i64) {
+        let data = {
+            let mut cache = self.cache.lock().await;
+            let cache = cache.get_or_insert_with(Default::default);
+
+            // Evict least recently resolved entry if full
+            if cache.entries.len() >= CACHE_MAX && !cache.entries.contains_key(&key) {
+                let oldest = cache
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.resolved_at)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    cache.entries.remove(&oldest);
+                }
+            }
+            cache.entries.insert(
+                key,
+                CacheEntry {
+                    uuid: uuid.to_string(),
+                    resolved_at: now,
+                },
+            );
+
+            // Batch writes, entries are written with the next write
+            if cache
+                .saved_at
+                .map_or(false, |saved_at| saved_at.elapsed() < SAVE_INTERVAL)
+            {
+                return;
+            }
+            cache.saved_at = Some(Instant::now());
+            serde_json::to_vec_pretty(&cache.entries)
+        };
+
+        save(config, data).await;
+    }
+}
+
+/// Fetch UUID for username from the Mojang API.
+///
+/// Returns `None` if no profile exists for the username.
+async fn fetch(username: &str) -> Result<Option<Uuid>, anyhow::Error> {
+    let mut url = reqwest::Url::parse(PROFILE_URL)?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow!("invalid profile URL"))?
+        .pop_if_empty()
+        .push(username);
+    let response = reqwest::Client::new()
+        .get(url)
+        .timeout(API_TIMEOUT)
+        .send()
+        .await?;
+    if matches!(
+        response.status(),
+        reqwest::StatusCode::NO_CONTENT | reqwest::StatusCode::NOT_FOUND
+    ) {
+        return Ok(None);
+    }
+    let profile: Profile = response.error_for_status()?.json().await?;
+    Ok(Some(Uuid::parse_str(&profile.id)?))
+}
+
+/// Load UUID cache from disk.
+async fn load(config: &Config) -> HashMap<String, CacheEntry> {
+    let path = Login::uuid_cache_path(config);
+    let data = match fs::read(&path).await {
+        Ok(data) => data,
+        Err(_) => return HashMap::new(),
+    };
+    serde_json::from_slice(&data).unwrap_or_else(|err| {
+        warn!(target: "lazymc", "Failed to parse UUID cache {}, ignoring: {}", path.display(), err);
+        HashMap::new()
+    })
+}
+
+/// Save serialized UUID cache to disk.
+async fn save(config: &Config, data: Result<Vec<u8>, serde_json::Error>) {
+    let path = Login::uuid_cache_path(config);
+    let result = match data {
+        Ok(data) => fs::write(&path, data).await.map_err(anyhow::Error::from),
+        Err(err) => Err(err.into()),
+    };
+    if let Err(err) = result {
+        warn!(target: "lazymc", "Failed to write UUID cache {}: {}", path.display(), err);
+    }
+}
@@ -5,7 +5,7 @@ use async_std::prelude::*;
 use rust_rcon::{Connection, Error as RconError};
 use tokio::time;
 
-use crate::config::Config;
+use crate::config::{Config, Server as ConfigServer};
 use crate::proxy;
 
 /// Minecraft RCON quirk.
@@ -48,7 +48,7 @@ impl Rcon {
     /// Connect to a host from the given configuration.
     pub async fn connect_config(config: &Config) -> Result<Self, Box<dyn std::error::Error>> {
         // RCON address
-        let mut addr = config.server.address;
+        let mut addr = ConfigServer::probe_address(config);
         addr.set_port(config.rcon.port);
         let addr = addr.to_string();
 
@@ -2,12 +2,99 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
+use crate::config::{self, Config, Server as ConfigServer};
+use crate::proto;
+
 /// File name.
 pub const FILE: &str = "server.properties";
 
 /// EOL in server.properties file.
 const EOL: &str = "\r\n";
 
+/// Rewrite server server.properties file based on configuration.
+///
+/// Applies the configured `server_properties` table, and the correct internal IP, port and RCON
+/// settings if rewriting is enabled. Values required by lazymc take precedence.
+pub fn rewrite_config(config: &Config) {
+    // Must have something to rewrite
+    if !config.advanced.rewrite_server_properties && config.server_properties.is_empty() {
+        return;
+    }
+
+    // Ensure server directory is set, it must exist
+    let dir = match ConfigServer::server_directory(config) {
+        Some(dir) => dir,
+        None => {
+            warn!(target: "lazymc", "Not rewriting {} file, server directory not configured (server.directory)", FILE);
+            return;
+        }
+    };
+
+    // Start with configured properties
+    let mut changes: HashMap<&str, String> = config
+        .server_properties
+        .iter()
+        .map(|(key, value)| (key.as_str(), property_value(value)))
+        .collect();
+
+    // Add values required by lazymc
+    if config.advanced.rewrite_server_properties {
+        for (key, value) in required_changes(config) {
+            if changes.get(key).map(|v| v != &value).unwrap_or(false) {
+                warn!(target: "lazymc", "Overriding '{}' from server_properties in config, lazymc requires it to be '{}'", key, value);
+            }
+            changes.insert(key, value);
+        }
+    }
+
+    // Rewrite file
+    rewrite_dir(dir, changes)
+}
+
+/// Build list of changes lazymc requires in server.properties.
+fn required_changes(config: &Config) -> HashMap<&'static str, String> {
+    #[allow(unused_mut)]
+    let mut changes = HashMap::from([
+        ("server-ip", config.server.address.ip().to_string()),
+        ("server-port", config.server.address.port().to_string()),
+        ("enable-status", "true".into()),
+        ("query.port", config.server.address.port().to_string()),
+    ]);
+
+    // If connecting to server over non-loopback address, disable proxy blocking
+    if !config.server.address.ip().is_loopback() {
+        changes.extend([("prevent-proxy-connections", "false".into())]);
+    }
+
+    // Update network compression threshold for lobby mode
+    if config.join.methods.contains(&config::Method::Lobby) {
+        changes.extend([(
+            "network-compression-threshold",
+            proto::COMPRESSION_THRESHOLD.to_string(),
+        )]);
+    }
+
+    // Add RCON configuration
+    #[cfg(feature = "rcon")]
+    if config.rcon.enabled {
+        changes.extend([
+            ("rcon.port", config.rcon.port.to_string()),
+            ("rcon.password", config.rcon.password.clone()),
+            ("enable-rcon", "true".into()),
+        ]);
+    }
+
+    changes
+}
+
+/// Transform configured property value into server.properties value.
+fn property_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
 /// Try to rewrite changes in server.properties file in dir.
 ///
 /// Prints an error and stops on failure.
@@ -0,0 +1,20 @@
+/// Minimum username length.
+const MIN_LENGTH: usize = 3;
+
+/// Maximum username length.
+const MAX_LENGTH: usize = 16;
+
+/// Check whether the given username is valid by Mojang rules.
+///
+/// Usernames must be 3 to 16 characters, only using `a-z`, `A-Z`, `0-9` and `_`. A single
+/// configured prefix, such as the `.` used by Floodgate for Bedrock players, is ignored.
+pub fn is_valid(username: &str, prefixes: &[String]) -> bool {
+    let name = prefixes
+        .iter()
+        .filter(|prefix| !prefix.is_empty())
+        .find_map(|prefix| username.strip_prefix(prefix.as_str()))
+        .unwrap_or(username);
+
+    (MIN_LENGTH..=MAX_LENGTH).contains(&name.len())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
@@ -3,6 +3,7 @@ use std::fs;
 use std::path::Path;
 
 use serde::Deserialize;
+use uuid::Uuid;
 
 /// Whitelist file name.
 pub const WHITELIST_FILE: &str = "whitelist.json";
@@ -20,6 +21,9 @@ pub struct Whitelist {
 
     /// OPd users.
     ops: Vec<String>,
+
+    /// UUIDs of whitelisted and OPd users.
+    uuids: Vec<Uuid>,
 }
 
 impl Whitelist {
@@ -27,6 +31,13 @@ impl Whitelist {
     pub fn is_whitelisted(&self, username: &str) -> bool {
         self.whitelist.iter().any(|u| u == username) || self.ops.iter().any(|u| u == username)
     }
+
+    /// Check whether a user is whitelisted by UUID.
+    ///
+    /// Keeps matching users that changed their name since the whitelist was written.
+    pub fn is_whitelisted_uuid(&self, uuid: &Uuid) -> bool {
+        self.uuids.contains(uuid)
+    }
 }
 
 /// A whitelist user.
@@ -79,29 +90,35 @@ pub fn load_dir(path: &Path) -> Result<Whitelist, Box<dyn Error>> {
 
     debug!(target: "lazymc", "Loaded {} whitelist and {} OP users", whitelist.len(), ops.len());
 
-    Ok(Whitelist { whitelist, ops })
+    // Collect known UUIDs
+    let uuids = whitelist
+        .iter()
+        .filter_map(|user| user.uuid.as_deref())
+        .chain(ops.iter().filter_map(|user| user.uuid.as_deref()))
+        .filter_map(|uuid| Uuid::parse_str(uuid).ok())
+        .collect();
+
+    Ok(Whitelist {
+        whitelist: whitelist.into_iter().map(|user| user.username).collect(),
+        ops: ops.into_iter().map(|user| user.username).collect(),
+        uuids,
+    })
 }
 
 /// Load whitelist from file.
-fn load_whitelist(path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+fn load_whitelist(path: &Path) -> Result<Vec<WhitelistUser>, Box<dyn Error>> {
     // Load file contents
     let contents = fs::read_to_string(path)?;
 
     // Parse contents
-    let users: Vec<WhitelistUser> = serde_json::from_str(&contents)?;
-
-    // Pluck usernames
-    Ok(users.into_iter().map(|user| user.username).collect())
+    Ok(serde_json::from_str(&contents)?)
 }
 
 /// Load OPs from file.
-fn load_ops(path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+fn load_ops(path: &Path) -> Result<Vec<OpUser>, Box<dyn Error>> {
     // Load file contents
     let contents = fs::read_to_string(path)?;
 
     // Parse contents
-    let users: Vec<OpUser> = serde_json::from_str(&contents)?;
-
-    // Pluck usernames
-    Ok(users.into_iter().map(|user| user.username).collect())
+    Ok(serde_json::from_str(&contents)?)
 }
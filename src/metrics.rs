@@ -0,0 +1,106 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Number of times the server was woken.
+static WAKES: AtomicU64 = AtomicU64::new(0);
+
+/// Number of accepted connections.
+static CONNECTIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Bytes proxied from clients to the server.
+static BYTES_TO_SERVER: AtomicU64 = AtomicU64::new(0);
+
+/// Bytes proxied from the server to clients.
+static BYTES_TO_CLIENT: AtomicU64 = AtomicU64::new(0);
+
+/// Number of finished proxy sessions.
+static SESSIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Total duration of finished proxy sessions in seconds.
+static SESSION_SECONDS: AtomicU64 = AtomicU64::new(0);
+
+/// Number of currently active login/play proxy sessions.
+static ACTIVE_SESSIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Time the last active proxy session closed at.
+static LAST_SESSION_CLOSED: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Record a server wake.
+pub fn record_wake() {
+    WAKES.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Record an accepted connection.
+pub fn record_connection() {
+    CONNECTIONS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Record a login/play proxy session being opened.
+///
+/// Status requests are not counted. Must be followed by `record_session_close` once closed.
+pub fn record_session_open() {
+    ACTIVE_SESSIONS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Record a login/play proxy session being closed.
+pub fn record_session_close() {
+    if ACTIVE_SESSIONS.fetch_sub(1, Ordering::Relaxed) == 1 {
+        LAST_SESSION_CLOSED.lock().unwrap().replace(Instant::now());
+    }
+}
+
+/// Get number of currently active login/play proxy sessions.
+pub fn active_sessions() -> u64 {
+    ACTIVE_SESSIONS.load(Ordering::Relaxed)
+}
+
+/// Get the time the last active proxy session closed at.
+pub fn last_session_closed() -> Option<Instant> {
+    *LAST_SESSION_CLOSED.lock().unwrap()
+}
+
+/// Record a finished proxy session with its proxied bytes.
+pub fn record_session(to_server: u64, to_client: u64, duration: Duration) {
+    BYTES_TO_SERVER.fetch_add(to_server, Ordering::Relaxed);
+    BYTES_TO_CLIENT.fetch_add(to_client, Ordering::Relaxed);
+    SESSIONS.fetch_add(1, Ordering::Relaxed);
+    SESSION_SECONDS.fetch_add(duration.as_secs(), Ordering::Relaxed);
+}
+
+/// Snapshot of all counters since lazymc started.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Counters {
+    pub wakes: u64,
+    pub connections: u64,
+    pub bytes_to_server: u64,
+    pub bytes_to_client: u64,
+    pub sessions: u64,
+    pub session_seconds: u64,
+}
+
+impl Counters {
+    /// Take snapshot of current counters.
+    pub fn snapshot() -> Self {
+        Self {
+            wakes: WAKES.load(Ordering::Relaxed),
+            connections: CONNECTIONS.load(Ordering::Relaxed),
+            bytes_to_server: BYTES_TO_SERVER.load(Ordering::Relaxed),
+            bytes_to_client: BYTES_TO_CLIENT.load(Ordering::Relaxed),
+            sessions: SESSIONS.load(Ordering::Relaxed),
+            session_seconds: SESSION_SECONDS.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Get counter increase since an earlier snapshot.
+    pub fn since(&self, earlier: &Self) -> Self {
+        Self {
+            wakes: self.wakes.saturating_sub(earlier.wakes),
+            connections: self.connections.saturating_sub(earlier.connections),
+            bytes_to_server: self.bytes_to_server.saturating_sub(earlier.bytes_to_server),
+            bytes_to_client: self.bytes_to_client.saturating_sub(earlier.bytes_to_client),
+            sessions: self.sessions.saturating_sub(earlier.sessions),
+            session_seconds: self.session_seconds.saturating_sub(earlier.session_seconds),
+        }
+    }
+}
@@ -1,6 +1,8 @@
+#[cfg(feature = "rcon")]
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use bytes::BytesMut;
 use minecraft_protocol::data::server_status::ServerStatus;
@@ -10,67 +12,342 @@ use minecraft_protocol::version::v1_14_4::status::{
     PingRequest, PingResponse, StatusRequest, StatusResponse,
 };
 use rand::Rng;
-use tokio::io::AsyncWriteExt;
+use serde_json::Value;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use tokio::net::TcpStream;
+#[cfg(unix)]
+use tokio::net::UnixStream;
 use tokio::time;
 
+use crate::backup;
 use crate::config::Config;
+#[cfg(unix)]
+use crate::config::Server as ConfigServer;
 use crate::proto::client::{Client, ClientState};
 use crate::proto::{packet, packets};
 use crate::proxy;
 use crate::server::{Server, State};
+use crate::thread_dump;
+use crate::types;
 
-/// Monitor ping inverval in seconds.
-const MONITOR_POLL_INTERVAL: Duration = Duration::from_secs(2);
-
-/// Status request timeout in seconds.
-const STATUS_TIMEOUT: u64 = 20;
-
-/// Ping request timeout in seconds.
-const PING_TIMEOUT: u64 = 10;
+/// Interval to check whether online players are idle.
+#[cfg(feature = "rcon")]
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
 
 /// Monitor server.
 pub async fn monitor_server(config: Arc<Config>, server: Arc<Server>) {
-    // Server address
-    let addr = config.server.address;
-
-    let mut poll_interval = time::interval(MONITOR_POLL_INTERVAL);
+    let mut poll_interval =
+        time::interval(Duration::from_secs(config.monitor.interval.max(1) as u64));
+    let mut failures = 0;
+    #[allow(unused_mut, unused_variables)]
+    let mut last_save = Instant::now();
+
+    // Kick idle players in separate task, RCON may be slow with many players
+    #[cfg(feature = "rcon")]
+    if config.rcon.idle_kick_after > 0 {
+        tokio::spawn(kick_idle(config.clone(), server.clone()));
+    }
 
     loop {
         poll_interval.tick().await;
 
+        // Server address, may change when switching blue/green instance
+        let addr = server.probe_address(&config);
+
         // Poll server state and update internal status
         trace!(target: "lazymc::monitor", "Fetching status for {} ... ", addr);
         let status = poll_server(&config, &server, addr).await;
         match status {
             // Got status, update
-            Ok(Some(status)) => server.update_status(&config, Some(status)).await,
+            Ok(Some(status)) => {
+                failures = 0;
+                server.update_status(&config, Some(status)).await;
+            }
+
+            // Error, tolerate a number of consecutive failures such as for long GC pauses
+            Err(_) if failures + 1 < config.monitor.failures => {
+                failures += 1;
+                debug!(target: "lazymc::monitor", "Failed to poll server status ({}/{})", failures, config.monitor.failures);
+            }
 
-            // Error, reset status
-            Err(_) => server.update_status(&config, None).await,
+            // Error, keep started if watchdog is waiting on possibly hung server, or reset status
+            Err(_) => {
+                if config.watchdog.enabled
+                    && server.state() == State::Started
+                    && server.pid().await.is_some()
+                {
+                    server.mark_unresponsive().await;
+                } else {
+                    server.update_status(&config, None).await;
+                }
+            }
 
             // Didn't get status, but ping fallback worked, leave as-is, show warning
             Ok(None) => {
+                failures = 0;
                 warn!(target: "lazymc::monitor", "Failed to poll server status, ping fallback succeeded");
             }
         }
 
+        // Periodically save world
+        #[cfg(feature = "rcon")]
+        if config.rcon.save_interval > 0 {
+            periodic_save(&config, &server, &mut last_save).await;
+        }
+
+        // Detect and handle hung server
+        if config.watchdog.enabled {
+            check_hung(&config, &server).await;
+        }
+
         // Sleep server when it's bedtime
         if server.should_sleep(&config).await {
-            info!(target: "lazymc::monitor", "Server has been idle, sleeping...");
-            server.stop(&config).await;
+            sleep_server(&config, &server, addr).await;
+        }
+
+        // Fully stop frozen server when it has been idle for long
+        if server.should_stop_frozen(&config).await {
+            info!(target: "lazymc::monitor", "Server has been frozen for long, stopping it to release memory...");
+            server.stop_frozen(&config).await;
         }
 
         // Check whether we should force kill server
         if server.should_kill().await {
+            if config.dry_run {
+                info!(target: "lazymc::monitor", "Dry run, would force kill server, took too long to start or stop");
+                continue;
+            }
+
             error!(target: "lazymc::monitor", "Force killing server, took too long to start or stop");
-            if !server.force_kill().await {
+
+            // Capture thread dump before killing
+            if config.diagnostics.thread_dump {
+                if let Some(pid) = server.pid().await {
+                    thread_dump::capture(&config, pid).await;
+                }
+            }
+
+            if !server.force_kill(&config).await {
                 warn!(target: "lazymc", "Failed to force kill server");
             }
         }
     }
 }
 
+/// Periodically save world through RCON while the server is started.
+///
+/// The interval restarts each time the server comes online.
+#[cfg(feature = "rcon")]
+async fn periodic_save(config: &Config, server: &Server, last_save: &mut Instant) {
+    if server.state() != State::Started {
+        *last_save = Instant::now();
+        return;
+    }
+
+    if last_save.elapsed() < Duration::from_secs(config.rcon.save_interval as u64) {
+        return;
+    }
+    *last_save = Instant::now();
+
+    // Only log in dry run
+    if config.dry_run {
+        info!(target: "lazymc::monitor", "Dry run, would save world through RCON");
+        return;
+    }
+
+    debug!(target: "lazymc::monitor", "Saving world through RCON...");
+    if server
+        .rcon_vanilla_command(config, "save-all")
+        .await
+        .is_err()
+    {
+        warn!(target: "lazymc::monitor", "Failed to save world through RCON");
+    }
+}
+
+/// Kick online players through RCON once all of them are idle for long enough.
+#[cfg(feature = "rcon")]
+async fn kick_idle(config: Arc<Config>, server: Arc<Server>) {
+    let mut idle = IdleTracker::default();
+    let mut interval = time::interval(IDLE_CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        idle.check(&config, &server).await;
+    }
+}
+
+/// Tracks idle players through RCON.
+#[cfg(feature = "rcon")]
+#[derive(Default)]
+struct IdleTracker {
+    /// Last known position and rotation per player, with the time it last changed.
+    players: HashMap<String, (String, Instant)>,
+}
+
+#[cfg(feature = "rcon")]
+impl IdleTracker {
+    /// Check whether all online players are idle, kick them if idle for long enough.
+    async fn check(&mut self, config: &Config, server: &Server) {
+        if server.state() != State::Started {
+            self.players.clear();
+            return;
+        }
+
+        // Status sample must list all online players
+        let players = server.last_players().await;
+        if players.is_empty() || players.len() as u32 != server.players_online().await {
+            self.players.clear();
+            return;
+        }
+        self.players.retain(|name, _| players.contains(name));
+
+        // Sample position and rotation, remember when it last changed
+        let cmds: Vec<String> = players
+            .iter()
+            .flat_map(|name| {
+                [
+                    format!("data get entity {name} Pos"),
+                    format!("data get entity {name} Rotation"),
+                ]
+            })
+            .collect();
+        let samples = match server.rcon_vanilla_commands(config, &cmds).await {
+            Ok(samples) => samples,
+            Err(()) => return,
+        };
+        let now = Instant::now();
+        for (name, sample) in players.iter().zip(samples.chunks(2)) {
+            let sample = sample.concat();
+            match self.players.get_mut(name) {
+                Some((last, _)) if *last == sample => {}
+                Some(entry) => *entry = (sample, now),
+                None => {
+                    self.players.insert(name.clone(), (sample, now));
+                }
+            }
+        }
+
+        // All players must be idle for long enough
+        let idle_after = Duration::from_secs(config.rcon.idle_kick_after as u64);
+        if self
+            .players
+            .values()
+            .any(|(_, since)| since.elapsed() < idle_after)
+        {
+            return;
+        }
+
+        if config.dry_run {
+            info!(target: "lazymc::monitor", "Dry run, would kick idle players: {}", players.join(", "));
+            self.players.clear();
+            return;
+        }
+
+        info!(target: "lazymc::monitor", "All online players are idle, kicking them");
+        let cmds: Vec<String> = players
+            .iter()
+            .map(|name| format!("kick {} {}", name, config.rcon.idle_kick_message))
+            .collect();
+        if server.rcon_vanilla_commands(config, &cmds).await.is_err() {
+            warn!(target: "lazymc::monitor", "Failed to kick idle players through RCON");
+        }
+        self.players.clear();
+    }
+}
+
+/// Put idle server to sleep.
+///
+/// Runs backups before sleeping if configured.
+async fn sleep_server(config: &Config, server: &Server, addr: SocketAddr) {
+    // Run backups, players may join meanwhile so recheck afterwards
+    if config.backup.enabled() && !config.dry_run {
+        if !backup::before_sleep(config, server).await {
+            server.update_last_active().await;
+            return;
+        }
+
+        if let Ok(Some(status)) = poll_server(config, server, addr).await {
+            server.update_status(config, Some(status)).await;
+        }
+        if !server.should_sleep(config).await {
+            info!(target: "lazymc::monitor", "Server is no longer idle after backup, not sleeping");
+            return;
+        }
+    }
+
+    info!(target: "lazymc::monitor", "Server has been idle, sleeping...");
+    server.stop(config).await;
+}
+
+/// Check whether the server is hung, handle it if it is.
+///
+/// The server is considered hung if it has been started but unresponsive for the configured
+/// watchdog timeout, and if it doesn't respond over RCON either.
+async fn check_hung(config: &Config, server: &Server) {
+    // Must be unresponsive for long enough
+    let timeout = Duration::from_secs(config.watchdog.timeout as u64);
+    let unresponsive_for = match server.unresponsive_for().await {
+        Some(duration) if duration >= timeout => duration,
+        _ => return,
+    };
+
+    // Server is not hung if it still responds over RCON
+    #[cfg(feature = "rcon")]
+    if server.rcon_vanilla_command(config, "list").await.is_ok() {
+        debug!(target: "lazymc::watchdog", "Server does not respond to status requests, but does respond over RCON");
+        server.mark_responsive().await;
+        return;
+    }
+
+    error!(
+        target: "lazymc::watchdog",
+        "Server has been unresponsive for {}s, it seems to be hung",
+        unresponsive_for.as_secs(),
+    );
+    capture_diagnostics(config, server).await;
+
+    // Only report if we shouldn't restart, reset to prevent reporting on every poll
+    if !config.watchdog.restart {
+        server.mark_responsive().await;
+        return;
+    }
+
+    warn!(target: "lazymc::watchdog", "Force killing hung server to restart it...");
+    if !server.force_restart(config).await {
+        warn!(target: "lazymc::watchdog", "Failed to force kill hung server");
+    }
+}
+
+/// Capture diagnostics about the current server process.
+///
+/// Reports them in the log, and captures a thread dump if enabled.
+async fn capture_diagnostics(config: &Config, server: &Server) {
+    let pid = server.pid().await;
+    let resources = server.resources().await;
+
+    error!(target: "lazymc::watchdog", "Diagnostics:");
+    error!(target: "lazymc::watchdog", "- State: {:?}", server.state());
+    match pid {
+        Some(pid) => error!(target: "lazymc::watchdog", "- PID: {}", pid),
+        None => error!(target: "lazymc::watchdog", "- PID: unknown"),
+    }
+    error!(target: "lazymc::watchdog", "- Last known players online: {}", server.players_online().await);
+    if let Some(resources) = resources {
+        error!(
+            target: "lazymc::watchdog",
+            "- Resources: {}MB memory, {:.1}% CPU ({}s ago)",
+            resources.memory / 1024 / 1024,
+            resources.cpu,
+            resources.sampled_at.elapsed().as_secs(),
+        );
+    }
+
+    // Capture thread dump
+    if let (true, Some(pid)) = (config.diagnostics.thread_dump, pid) {
+        thread_dump::capture(config, pid).await;
+    }
+}
+
 /// Poll server state.
 ///
 /// Returns `Ok` if status/ping succeeded, includes server status most of the time.
@@ -81,23 +358,68 @@ pub async fn poll_server(
     addr: SocketAddr,
 ) -> Result<Option<ServerStatus>, ()> {
     // Fetch status
-    if let Ok(status) = fetch_status(config, addr).await {
+    let protocol = server.protocol(config).await;
+    if let Ok((status, json)) = fetch_status(config, addr, protocol).await {
+        server.update_status_json(&json).await;
         return Ok(Some(status));
     }
 
     // Try ping fallback if server is currently started
     if server.state() == State::Started {
         debug!(target: "lazymc::monitor", "Failed to get status from started server, trying ping...");
-        do_ping(config, addr).await?;
+        do_ping(config, addr, protocol).await?;
     }
 
     Err(())
 }
 
 /// Attemp to fetch status from server.
-async fn fetch_status(config: &Config, addr: SocketAddr) -> Result<ServerStatus, ()> {
-    let mut stream = TcpStream::connect(addr).await.map_err(|_| ())?;
+///
+/// Connects over the server Unix socket instead if configured.
+pub async fn fetch_status(
+    config: &Config,
+    addr: SocketAddr,
+    protocol: u32,
+) -> Result<(ServerStatus, Value), ()> {
+    #[cfg(unix)]
+    if let Some(path) = ConfigServer::socket_path(config) {
+        let stream = UnixStream::connect(path).await.map_err(|_| ())?;
+        return fetch_status_stream(config, stream, addr, protocol).await;
+    }
+
+    let stream = TcpStream::connect(addr).await.map_err(|_| ())?;
+    fetch_status_stream(config, stream, addr, protocol).await
+}
+
+/// Check whether a server responds to status requests on the given TCP address.
+pub async fn is_online(config: &Config, addr: SocketAddr, protocol: u32) -> bool {
+    match TcpStream::connect(addr).await {
+        Ok(stream) => fetch_status_stream(config, stream, addr, protocol)
+            .await
+            .is_ok(),
+        Err(_) => false,
+    }
+}
 
+/// Get number of players online on a server on the given TCP address, if it responds.
+pub async fn online_players(config: &Config, addr: SocketAddr, protocol: u32) -> Option<u32> {
+    let stream = TcpStream::connect(addr).await.ok()?;
+    fetch_status_stream(config, stream, addr, protocol)
+        .await
+        .ok()
+        .map(|(status, _)| status.players.online)
+}
+
+/// Attemp to fetch status from server over the given stream.
+async fn fetch_status_stream<S>(
+    config: &Config,
+    mut stream: S,
+    addr: SocketAddr,
+    protocol: u32,
+) -> Result<(ServerStatus, Value), ()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
     // Add proxy header
     if config.server.send_proxy_v2 {
         trace!(target: "lazymc::monitor", "Sending local proxy header for server connection");
@@ -110,15 +432,35 @@ async fn fetch_status(config: &Config, addr: SocketAddr) -> Result<ServerStatus,
     // Dummy client
     let client = Client::dummy();
 
-    send_handshake(&client, &mut stream, config, addr).await?;
+    send_handshake(&client, &mut stream, protocol, addr).await?;
     request_status(&client, &mut stream).await?;
-    wait_for_status_timeout(&client, &mut stream).await
+    wait_for_status_timeout(config, &client, &mut stream).await
 }
 
 /// Attemp to ping server.
-async fn do_ping(config: &Config, addr: SocketAddr) -> Result<(), ()> {
-    let mut stream = TcpStream::connect(addr).await.map_err(|_| ())?;
+///
+/// Connects over the server Unix socket instead if configured.
+async fn do_ping(config: &Config, addr: SocketAddr, protocol: u32) -> Result<(), ()> {
+    #[cfg(unix)]
+    if let Some(path) = ConfigServer::socket_path(config) {
+        let stream = UnixStream::connect(path).await.map_err(|_| ())?;
+        return do_ping_stream(config, stream, addr, protocol).await;
+    }
+
+    let stream = TcpStream::connect(addr).await.map_err(|_| ())?;
+    do_ping_stream(config, stream, addr, protocol).await
+}
 
+/// Attemp to ping server over the given stream.
+async fn do_ping_stream<S>(
+    config: &Config,
+    mut stream: S,
+    addr: SocketAddr,
+    protocol: u32,
+) -> Result<(), ()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
     // Add proxy header
     if config.server.send_proxy_v2 {
         trace!(target: "lazymc::monitor", "Sending local proxy header for server connection");
@@ -131,52 +473,56 @@ async fn do_ping(config: &Config, addr: SocketAddr) -> Result<(), ()> {
     // Dummy client
     let client = Client::dummy();
 
-    send_handshake(&client, &mut stream, config, addr).await?;
+    send_handshake(&client, &mut stream, protocol, addr).await?;
     let token = send_ping(&client, &mut stream).await?;
-    wait_for_ping_timeout(&client, &mut stream, token).await
+    wait_for_ping_timeout(config, &client, &mut stream, token).await
 }
 
 /// Send handshake.
 async fn send_handshake(
     client: &Client,
-    stream: &mut TcpStream,
-    config: &Config,
+    stream: &mut (impl AsyncWrite + Unpin),
+    protocol: u32,
     addr: SocketAddr,
 ) -> Result<(), ()> {
     packet::write_packet(
         Handshake {
-            protocol_version: config.public.protocol as i32,
+            protocol_version: protocol as i32,
             server_addr: addr.ip().to_string(),
             server_port: addr.port(),
             next_state: ClientState::Status.to_id(),
         },
         client,
-        &mut stream.split().1,
+        stream,
     )
     .await
 }
 
 /// Send status request.
-async fn request_status(client: &Client, stream: &mut TcpStream) -> Result<(), ()> {
-    packet::write_packet(StatusRequest {}, client, &mut stream.split().1).await
+async fn request_status(client: &Client, stream: &mut (impl AsyncWrite + Unpin)) -> Result<(), ()> {
+    packet::write_packet(StatusRequest {}, client, stream).await
 }
 
 /// Send status request.
-async fn send_ping(client: &Client, stream: &mut TcpStream) -> Result<u64, ()> {
+async fn send_ping(client: &Client, stream: &mut (impl AsyncWrite + Unpin)) -> Result<u64, ()> {
     let token = rand::thread_rng().gen();
-    packet::write_packet(PingRequest { time: token }, client, &mut stream.split().1).await?;
+    packet::write_packet(PingRequest { time: token }, client, stream).await?;
     Ok(token)
 }
 
 /// Wait for a status response.
-async fn wait_for_status(client: &Client, stream: &mut TcpStream) -> Result<ServerStatus, ()> {
-    // Get stream reader, set up buffer
-    let (mut reader, mut _writer) = stream.split();
+///
+/// Also returns the raw status JSON, for details `ServerStatus` doesn't include.
+async fn wait_for_status(
+    client: &Client,
+    stream: &mut (impl AsyncRead + Unpin),
+) -> Result<(ServerStatus, Value), ()> {
+    // Set up buffer
     let mut buf = BytesMut::new();
 
     loop {
         // Read packet from stream
-        let (packet, _raw) = match packet::read_packet(client, &mut buf, &mut reader).await {
+        let (packet, _raw) = match packet::read_packet(client, &mut buf, stream).await {
             Ok(Some(packet)) => packet,
             Ok(None) => break,
             Err(_) => continue,
@@ -185,7 +531,14 @@ async fn wait_for_status(client: &Client, stream: &mut TcpStream) -> Result<Serv
         // Catch status response
         if packet.id == packets::status::CLIENT_STATUS {
             let status = StatusResponse::decode(&mut packet.data.as_slice()).map_err(|_| ())?;
-            return Ok(status.server_status);
+            let (len, json_len) = types::read_var_int(&packet.data)?;
+            let json_len = usize::try_from(json_len).map_err(|_| ())?;
+            let json = len
+                .checked_add(json_len)
+                .and_then(|end| packet.data.get(len..end))
+                .and_then(|json| serde_json::from_slice(json).ok())
+                .unwrap_or_default();
+            return Ok((status.server_status, json));
         }
     }
 
@@ -195,24 +548,29 @@ async fn wait_for_status(client: &Client, stream: &mut TcpStream) -> Result<Serv
 
 /// Wait for a status response.
 async fn wait_for_status_timeout(
+    config: &Config,
     client: &Client,
-    stream: &mut TcpStream,
-) -> Result<ServerStatus, ()> {
+    stream: &mut (impl AsyncRead + Unpin),
+) -> Result<(ServerStatus, Value), ()> {
     let status = wait_for_status(client, stream);
-    tokio::time::timeout(Duration::from_secs(STATUS_TIMEOUT), status)
+    let timeout = Duration::from_secs(config.monitor.status_timeout as u64);
+    tokio::time::timeout(timeout, status)
         .await
         .map_err(|_| ())?
 }
 
 /// Wait for a status response.
-async fn wait_for_ping(client: &Client, stream: &mut TcpStream, token: u64) -> Result<(), ()> {
-    // Get stream reader, set up buffer
-    let (mut reader, mut _writer) = stream.split();
+async fn wait_for_ping(
+    client: &Client,
+    stream: &mut (impl AsyncRead + Unpin),
+    token: u64,
+) -> Result<(), ()> {
+    // Set up buffer
     let mut buf = BytesMut::new();
 
     loop {
         // Read packet from stream
-        let (packet, _raw) = match packet::read_packet(client, &mut buf, &mut reader).await {
+        let (packet, _raw) = match packet::read_packet(client, &mut buf, stream).await {
             Ok(Some(packet)) => packet,
             Ok(None) => break,
             Err(_) => continue,
@@ -237,12 +595,14 @@ async fn wait_for_ping(client: &Client, stream: &mut TcpStream, token: u64) -> R
 
 /// Wait for a status response.
 async fn wait_for_ping_timeout(
+    config: &Config,
     client: &Client,
-    stream: &mut TcpStream,
+    stream: &mut (impl AsyncRead + Unpin),
     token: u64,
 ) -> Result<(), ()> {
     let status = wait_for_ping(client, stream, token);
-    tokio::time::timeout(Duration::from_secs(PING_TIMEOUT), status)
+    let timeout = Duration::from_secs(config.monitor.ping_timeout as u64);
+    tokio::time::timeout(timeout, status)
         .await
         .map_err(|_| ())?
 }
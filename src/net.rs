@@ -1,22 +1,151 @@
-use std::error::Error;
 use std::io;
-use tokio::io::AsyncWriteExt;
+use std::net::IpAddr;
+#[cfg(unix)]
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::Deserialize;
+use socket2::{SockRef, TcpKeepalive};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+#[cfg(unix)]
+use tokio::net::TcpListener;
 use tokio::net::TcpStream;
 
+use crate::config::Socket;
+use crate::error::Error;
+
 /// Gracefully close given TCP stream.
 ///
 /// Intended as helper to make code less messy. This also succeeds if already closed.
-pub async fn close_tcp_stream(mut stream: TcpStream) -> Result<(), Box<dyn Error>> {
+pub async fn close_tcp_stream(mut stream: impl AsyncWrite + Unpin) -> Result<(), Error> {
     close_tcp_stream_ref(&mut stream).await
 }
 
 /// Gracefully close given TCP stream.
 ///
 /// Intended as helper to make code less messy. This also succeeds if already closed.
-pub async fn close_tcp_stream_ref(stream: &mut TcpStream) -> Result<(), Box<dyn Error>> {
+pub async fn close_tcp_stream_ref(stream: &mut (impl AsyncWrite + Unpin)) -> Result<(), Error> {
     match stream.shutdown().await {
         Ok(_) => Ok(()),
         Err(err) if err.kind() == io::ErrorKind::NotConnected => Ok(()),
         Err(err) => Err(err.into()),
     }
 }
+
+/// Apply configured socket options to given TCP stream.
+///
+/// Options that are not set are left untouched.
+pub fn configure_socket(stream: &TcpStream, options: &Socket) -> Result<(), Error> {
+    if options.nodelay {
+        stream.set_nodelay(true)?;
+    }
+
+    let socket = SockRef::from(stream);
+
+    if let Some(time) = options.keepalive {
+        #[allow(unused_mut)]
+        let mut keepalive = TcpKeepalive::new().with_time(Duration::from_secs(time as u64));
+        #[cfg(any(target_os = "linux", target_os = "macos", windows))]
+        if let Some(interval) = options.keepalive_interval {
+            keepalive = keepalive.with_interval(Duration::from_secs(interval as u64));
+        }
+        socket.set_tcp_keepalive(&keepalive)?;
+    }
+
+    if let Some(linger) = options.linger {
+        socket.set_linger(Some(Duration::from_secs(linger as u64)))?;
+    }
+    if let Some(size) = options.send_buffer {
+        socket.set_send_buffer_size(size)?;
+    }
+    if let Some(size) = options.recv_buffer {
+        socket.set_recv_buffer_size(size)?;
+    }
+
+    Ok(())
+}
+
+/// Bind TCP listener on given address with SO_REUSEPORT.
+///
+/// Allows binding multiple listeners on the same address, having the kernel balance incoming
+/// connections between them. Must be called from within a tokio runtime.
+#[cfg(unix)]
+pub fn bind_reuseport(addr: SocketAddr) -> io::Result<TcpListener> {
+    use socket2::{Domain, Socket as RawSocket, Type};
+
+    let socket = RawSocket::new(Domain::for_address(addr), Type::STREAM, None)?;
+    socket.set_reuse_address(true)?;
+    socket.set_reuse_port(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+
+    TcpListener::from_std(socket.into())
+}
+
+/// IP subnet, such as `192.168.1.0/24`, or a single IP address.
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Subnet {
+    /// Network address.
+    addr: IpAddr,
+
+    /// Prefix length in bits.
+    prefix: u8,
+}
+
+impl Subnet {
+    /// Check whether the given IP address is in this subnet.
+    ///
+    /// IPv4-mapped IPv6 addresses are matched as IPv4.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = match ip {
+            IpAddr::V6(ip) => ip
+                .to_ipv4_mapped()
+                .map(IpAddr::V4)
+                .unwrap_or(IpAddr::V6(ip)),
+            ip => ip,
+        };
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Subnet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = s.trim().split_once('/').unwrap_or((s.trim(), ""));
+        let addr: IpAddr = addr
+            .parse()
+            .map_err(|_| format!("invalid IP address '{addr}'"))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            "" => max,
+            prefix => prefix
+                .parse()
+                .ok()
+                .filter(|prefix| *prefix <= max)
+                .ok_or_else(|| format!("invalid subnet prefix '{prefix}'"))?,
+        };
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl TryFrom<String> for Subnet {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
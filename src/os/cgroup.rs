@@ -0,0 +1,111 @@
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+use crate::config::Cgroup as CgroupConfig;
+
+/// CPU bandwidth period in microseconds.
+const CPU_PERIOD: u64 = 100_000;
+
+/// Set up cgroup v2 with configured limits for the given blue/green instance.
+///
+/// The server process moves itself into it with [`join`] before executing. The secondary instance
+/// gets its own sibling cgroup, so both instances don't share limits while switching.
+///
+/// Returns the path of the cgroup.
+pub fn setup(config: &CgroupConfig, secondary: bool) -> io::Result<PathBuf> {
+    let mut path = config.path.clone();
+    if secondary {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push("-secondary");
+        path.set_file_name(name);
+    }
+    fs::create_dir_all(&path)?;
+
+    // Enable controllers for cgroup in parent, may already be enabled
+    if let Some(parent) = path.parent() {
+        if let Err(err) = fs::write(parent.join("cgroup.subtree_control"), "+memory +cpu") {
+            debug!(target: "lazymc::cgroup", "Failed to enable cgroup controllers in {}: {}", parent.display(), err);
+        }
+    }
+
+    // Apply limits
+    let memory = match config.memory_max {
+        0 => "max".into(),
+        mb => (mb * 1024 * 1024).to_string(),
+    };
+    fs::write(path.join("memory.max"), memory)?;
+    let cpu = if config.cpu_max > 0.0 {
+        format!(
+            "{} {}",
+            (config.cpu_max as f64 * CPU_PERIOD as f64) as u64,
+            CPU_PERIOD
+        )
+    } else {
+        format!("max {CPU_PERIOD}")
+    };
+    fs::write(path.join("cpu.max"), cpu)?;
+
+    Ok(path)
+}
+
+/// Path of the file to write to for moving a process into the cgroup.
+pub fn procs_file(path: &Path) -> io::Result<CString> {
+    CString::new(path.join("cgroup.procs").as_os_str().as_bytes())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+}
+
+/// Move the current process into cgroup through its procs file, children inherit it.
+///
+/// Only invokes async-signal-safe syscalls, so it may be used in a forked child.
+pub fn join(procs_file: &CStr) -> io::Result<()> {
+    // Safe because the path is a valid C string and the buffer is static
+    unsafe {
+        let fd = libc::open(procs_file.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let written = libc::write(fd, b"0".as_ptr() as *const libc::c_void, 1);
+        let err = io::Error::last_os_error();
+        libc::close(fd);
+        if written != 1 {
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+/// Kill all processes in cgroup.
+///
+/// Requires Linux 5.14 or newer.
+pub fn kill(path: &Path) -> bool {
+    match fs::write(path.join("cgroup.kill"), "1") {
+        Ok(()) => true,
+        Err(err) => {
+            warn!(target: "lazymc::cgroup", "Failed to kill processes in cgroup: {}", err);
+            false
+        }
+    }
+}
+
+/// Freeze or unfreeze all processes in cgroup.
+pub fn freeze(path: &Path, freeze: bool) -> bool {
+    match fs::write(path.join("cgroup.freeze"), if freeze { "1" } else { "0" }) {
+        Ok(()) => true,
+        Err(err) => {
+            warn!(target: "lazymc::cgroup", "Failed to change freeze state of cgroup: {}", err);
+            false
+        }
+    }
+}
+
+/// Remove cgroup.
+///
+/// Only succeeds once all processes in it have quit.
+pub fn remove(path: &Path) {
+    if let Err(err) = fs::remove_dir(path) {
+        debug!(target: "lazymc::cgroup", "Failed to remove cgroup at {}: {}", path.display(), err);
+    }
+}
@@ -0,0 +1,83 @@
+use std::fs;
+use std::io;
+use std::time::Duration;
+
+use nix::sched::{sched_setaffinity, CpuSet};
+use nix::unistd::Pid;
+
+use super::ProcessStats;
+use crate::config::IoniceClass;
+
+/// Target a process in `ioprio_set`.
+const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+
+/// Bit shift of class in I/O priority value.
+const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+
+/// Set I/O scheduling class and priority of the current process.
+pub fn set_ionice(class: IoniceClass, level: u8) -> io::Result<()> {
+    let class = match class {
+        IoniceClass::Realtime => 1,
+        IoniceClass::BestEffort => 2,
+        IoniceClass::Idle => 3,
+    };
+    let prio = (class << IOPRIO_CLASS_SHIFT) | level.min(7) as libc::c_int;
+
+    // Safe because ioprio_set only changes scheduling of current process
+    match unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, prio) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Pin the current process to the given CPU cores.
+pub fn set_affinity(cpus: &[usize]) -> io::Result<()> {
+    let mut set = CpuSet::new();
+    for cpu in cpus {
+        set.set(*cpu).map_err(io::Error::from)?;
+    }
+    sched_setaffinity(Pid::from_raw(0), &set).map_err(io::Error::from)
+}
+
+/// Get resource usage statistics of process on Linux.
+///
+/// Reads from `/proc/<pid>/stat`. Returns `None` if the process could not be read.
+pub fn process_stats(pid: u32) -> Option<ProcessStats> {
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+
+    // Skip PID and command name, command name may contain spaces
+    let fields: Vec<&str> = stat
+        .get(stat.rfind(')')? + 1..)?
+        .split_whitespace()
+        .collect();
+
+    // Fields after command name, see proc(5): utime (14), stime (15), rss (24)
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    let rss: u64 = fields.get(21)?.parse().ok()?;
+
+    // Transform clock ticks and pages into proper units
+    let ticks = clock_ticks()?;
+    let page_size = page_size()?;
+
+    Some(ProcessStats {
+        memory: rss * page_size,
+        cpu_time: Duration::from_secs_f64((utime + stime) as f64 / ticks as f64),
+    })
+}
+
+/// Get number of clock ticks per second.
+fn clock_ticks() -> Option<u64> {
+    match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => Some(ticks as u64),
+        _ => None,
+    }
+}
+
+/// Get memory page size in bytes.
+fn page_size() -> Option<u64> {
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => Some(size as u64),
+        _ => None,
+    }
+}
@@ -1,16 +1,23 @@
+#[cfg(target_os = "linux")]
+pub mod cgroup;
+#[cfg(target_os = "linux")]
+pub mod linux;
 #[cfg(windows)]
 pub mod windows;
 
+use std::time::Duration;
+
 #[cfg(unix)]
 use nix::{sys::signal, unistd::Pid};
 
 /// Force kill process.
 ///
+/// On Unix the whole process group is killed, see `new_process_group`.
 /// Results in undefined behavior if PID is invalid.
 #[allow(unreachable_code)]
 pub fn force_kill(pid: u32) -> bool {
     #[cfg(unix)]
-    return unix_signal(pid, signal::SIGKILL);
+    return unix_signal_group(pid, signal::SIGKILL);
 
     #[cfg(windows)]
     unsafe {
@@ -20,58 +27,222 @@ pub fn force_kill(pid: u32) -> bool {
     unimplemented!("force killing Minecraft server process not implemented on this platform");
 }
 
-/// Gracefully kill process.
+/// Gracefully kill process, and its process group.
 /// Results in undefined behavior if PID is invalid.
 ///
+/// On Windows a Ctrl+Break event is sent to the process group of the process.
+///
 /// # Panics
-/// Panics on platforms other than Unix.
+/// Panics on platforms other than Unix and Windows.
 #[allow(unreachable_code, dead_code, unused_variables)]
 pub fn kill_gracefully(pid: u32) -> bool {
     #[cfg(unix)]
-    return unix_signal(pid, signal::SIGTERM);
+    return unix_signal_group(pid, signal::SIGTERM);
 
-    unimplemented!(
-        "gracefully killing Minecraft server process not implemented on non-Unix platforms"
-    );
+    #[cfg(windows)]
+    unsafe {
+        return windows::kill_gracefully(pid);
+    }
+
+    unimplemented!("gracefully killing Minecraft server process not implemented on this platform");
 }
 
-/// Freeze process.
+/// Freeze process, and its process group.
 /// Results in undefined behavior if PID is invaild.
 ///
 /// # Panics
-/// Panics on platforms other than Unix.
+/// Panics on platforms other than Unix and Windows.
 #[allow(unreachable_code)]
 pub fn freeze(pid: u32) -> bool {
     #[cfg(unix)]
-    return unix_signal(pid, signal::SIGSTOP);
+    return unix_signal_group(pid, signal::SIGSTOP);
 
-    unimplemented!(
-        "freezing the Minecraft server process is not implemented on non-Unix platforms"
-    );
+    #[cfg(windows)]
+    unsafe {
+        return windows::suspend(pid);
+    }
+
+    unimplemented!("freezing the Minecraft server process is not implemented on this platform");
 }
 
-/// Unfreeze process.
+/// Unfreeze process, and its process group.
 /// Results in undefined behavior if PID is invaild.
 ///
 /// # Panics
-/// Panics on platforms other than Unix.
+/// Panics on platforms other than Unix and Windows.
 #[allow(unreachable_code)]
 pub fn unfreeze(pid: u32) -> bool {
     #[cfg(unix)]
-    return unix_signal(pid, signal::SIGCONT);
+    return unix_signal_group(pid, signal::SIGCONT);
+
+    #[cfg(windows)]
+    unsafe {
+        return windows::resume(pid);
+    }
+
+    unimplemented!("unfreezing the Minecraft server process is not implemented on this platform");
+}
+
+/// Request JVM thread dump by sending SIGQUIT.
+/// Results in undefined behavior if PID is invaild.
+///
+/// # Panics
+/// Panics on platforms other than Unix.
+#[allow(unreachable_code, unused_variables)]
+pub fn dump_threads(pid: u32) -> bool {
+    #[cfg(unix)]
+    return unix_signal(pid, signal::SIGQUIT);
 
     unimplemented!(
-        "unfreezing the Minecraft server process is not implemented on non-Unix platforms"
+        "requesting a thread dump through a signal is not implemented on non-Unix platforms"
     );
 }
 
+/// Process resource usage statistics.
+#[derive(Debug, Copy, Clone)]
+pub struct ProcessStats {
+    /// Resident memory in bytes.
+    pub memory: u64,
+
+    /// Total CPU time consumed by the process.
+    pub cpu_time: Duration,
+}
+
+/// Get resource usage statistics of process.
+///
+/// Returns `None` if the process could not be read, or if not supported on this platform.
+#[allow(unreachable_code, unused_variables)]
+pub fn process_stats(pid: u32) -> Option<ProcessStats> {
+    #[cfg(target_os = "linux")]
+    return linux::process_stats(pid);
+
+    None
+}
+
+/// Move the current process into a new process group.
+///
+/// Used in a spawned server process before executing, so the server and anything it spawns (for
+/// example, Java started by a wrapper script) can be signalled together.
+#[cfg(unix)]
+pub fn new_process_group() -> std::io::Result<()> {
+    nix::unistd::setpgid(Pid::from_raw(0), Pid::from_raw(0)).map_err(std::io::Error::from)
+}
+
+/// Run `f` with the given file mode creation mask, restore the previous mask afterwards.
+///
+/// The mask applies to the whole process, keep `f` short.
+#[cfg(unix)]
+pub fn with_umask<T>(mask: u32, f: impl FnOnce() -> T) -> T {
+    // Safe because umask only changes the file mode creation mask of current process
+    let previous = unsafe { libc::umask(mask as libc::mode_t) };
+    let result = f();
+    unsafe { libc::umask(previous) };
+    result
+}
+
+/// Set nice level of the current process.
+#[cfg(unix)]
+pub fn set_nice(nice: i32) -> std::io::Result<()> {
+    // Safe because setpriority only changes scheduling of current process
+    match unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } {
+        0 => Ok(()),
+        _ => Err(std::io::Error::last_os_error()),
+    }
+}
+
+/// Drop privileges of the current process to the given user and group.
+///
+/// Clears supplementary groups when dropping from root. Only invokes async-signal-safe syscalls,
+/// so it may be used in a forked child.
+#[cfg(unix)]
+pub fn drop_privileges(uid: Option<u32>, gid: Option<u32>) -> std::io::Result<()> {
+    // Safe because these only change credentials of the current process
+    unsafe {
+        if uid.is_some() && libc::getuid() == 0 && libc::setgroups(0, std::ptr::null()) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        if let Some(gid) = gid {
+            if libc::setgid(gid) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        if let Some(uid) = uid {
+            if libc::setuid(uid) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Resolve user and group to run a process as into IDs.
+///
+/// Both accept a name or numeric ID. If only a user is given, its primary group is used.
+#[cfg(unix)]
+pub fn resolve_user(
+    user: Option<&str>,
+    group: Option<&str>,
+) -> Result<(Option<u32>, Option<u32>), String> {
+    use nix::unistd::{Gid, Group, Uid, User};
+
+    let user = match user {
+        Some(user) => {
+            let found = match user.parse::<u32>() {
+                Ok(uid) => User::from_uid(Uid::from_raw(uid)),
+                Err(_) => User::from_name(user),
+            };
+            match found {
+                Ok(Some(user)) => Some(user),
+                Ok(None) => return Err(format!("user '{user}' does not exist")),
+                Err(err) => return Err(format!("failed to look up user '{user}': {err}")),
+            }
+        }
+        None => None,
+    };
+
+    let gid = match group {
+        Some(group) => {
+            let found = match group.parse::<u32>() {
+                Ok(gid) => Group::from_gid(Gid::from_raw(gid)),
+                Err(_) => Group::from_name(group),
+            };
+            match found {
+                Ok(Some(group)) => Some(group.gid.as_raw()),
+                Ok(None) => return Err(format!("group '{group}' does not exist")),
+                Err(err) => return Err(format!("failed to look up group '{group}': {err}")),
+            }
+        }
+        None => user.as_ref().map(|user| user.gid.as_raw()),
+    };
+
+    Ok((user.map(|user| user.uid.as_raw()), gid))
+}
+
 #[cfg(unix)]
 pub fn unix_signal(pid: u32, signal: signal::Signal) -> bool {
     return match signal::kill(Pid::from_raw(pid as i32), signal) {
         Ok(()) => true,
+        Err(nix::errno::Errno::EPERM) => {
+            warn!(target: "lazymc", "Sending {signal} signal to server failed: not permitted, the server may run as a different user than lazymc");
+            false
+        }
         Err(err) => {
             warn!(target: "lazymc", "Sending {signal} signal to server failed: {err}");
             false
         }
     };
 }
+
+/// Send signal to process group led by given PID.
+///
+/// Falls back to signalling just the process if it doesn't lead a process group.
+#[cfg(unix)]
+pub fn unix_signal_group(pid: u32, signal: signal::Signal) -> bool {
+    match signal::killpg(Pid::from_raw(pid as i32), signal) {
+        Ok(()) => true,
+        Err(err) => {
+            debug!(target: "lazymc", "Sending {signal} signal to server process group failed, signalling process instead: {err}");
+            unix_signal(pid, signal)
+        }
+    }
+}
@@ -1,8 +1,25 @@
+use std::mem;
+use std::ptr;
+
 use winapi::shared::minwindef::{FALSE, TRUE};
-use winapi::shared::ntdef::NULL;
+use winapi::shared::ntdef::{NTSTATUS, NULL};
 use winapi::um::handleapi::CloseHandle;
+use winapi::um::jobapi2::{
+    AssignProcessToJobObject, CreateJobObjectW, SetInformationJobObject, TerminateJobObject,
+};
 use winapi::um::processthreadsapi::{OpenProcess, TerminateProcess};
-use winapi::um::winnt::PROCESS_TERMINATE;
+use winapi::um::wincon::{GenerateConsoleCtrlEvent, CTRL_BREAK_EVENT};
+use winapi::um::winnt::{
+    JobObjectExtendedLimitInformation, HANDLE, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+    JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE, PROCESS_SET_QUOTA, PROCESS_SUSPEND_RESUME,
+    PROCESS_TERMINATE,
+};
+
+#[link(name = "ntdll")]
+extern "system" {
+    fn NtSuspendProcess(process: HANDLE) -> NTSTATUS;
+    fn NtResumeProcess(process: HANDLE) -> NTSTATUS;
+}
 
 /// Force kill process on Windows.
 ///
@@ -20,3 +37,121 @@ pub unsafe fn force_kill(pid: u32) -> bool {
 
     terminated && closed
 }
+
+/// Suspend all threads of process on Windows.
+///
+/// This is unsafe because the PID isn't checked.
+pub unsafe fn suspend(pid: u32) -> bool {
+    debug!(target: "lazymc", "Suspending process {} to freeze server", pid);
+    with_suspend_handle(pid, |handle| NtSuspendProcess(handle))
+}
+
+/// Resume all threads of process on Windows.
+///
+/// This is unsafe because the PID isn't checked.
+pub unsafe fn resume(pid: u32) -> bool {
+    debug!(target: "lazymc", "Resuming process {} to unfreeze server", pid);
+    with_suspend_handle(pid, |handle| NtResumeProcess(handle))
+}
+
+/// Open process handle with suspend/resume access, invoke function with it.
+///
+/// Returns `true` if the function returned a success status.
+unsafe fn with_suspend_handle(pid: u32, f: impl FnOnce(HANDLE) -> NTSTATUS) -> bool {
+    let handle = OpenProcess(PROCESS_SUSPEND_RESUME, FALSE, pid);
+    if handle == NULL {
+        warn!(target: "lazymc", "Failed to open process handle in order to suspend or resume it");
+        return false;
+    }
+
+    let status = f(handle);
+    CloseHandle(handle);
+    status >= 0
+}
+
+/// Gracefully kill process on Windows by sending a Ctrl+Break console event.
+///
+/// The server process is started in its own process group (`CREATE_NEW_PROCESS_GROUP`), the event
+/// is sent to just that group. Ctrl+C cannot be targeted at a group, so Ctrl+Break is used. Java
+/// servers handle it as a thread dump request, stop those through RCON instead. Fails if lazymc
+/// has no console, such as when running as service.
+///
+/// This is unsafe because the PID isn't checked.
+pub unsafe fn kill_gracefully(pid: u32) -> bool {
+    debug!(target: "lazymc", "Sending Ctrl+Break console event to {} to stop server", pid);
+
+    let sent = GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, pid) == TRUE;
+    if !sent {
+        warn!(target: "lazymc", "Failed to send Ctrl+Break console event, lazymc may not have a console");
+    }
+    sent
+}
+
+/// Job Object managing the server process tree.
+///
+/// All processes in the job are killed when the job is closed, which also happens when lazymc
+/// quits.
+#[derive(Debug)]
+pub struct Job(HANDLE);
+
+// Job Object handles may be used from any thread
+unsafe impl Send for Job {}
+unsafe impl Sync for Job {}
+
+impl Job {
+    /// Create new Job Object with kill-on-close.
+    pub fn new() -> Option<Self> {
+        unsafe {
+            let handle = CreateJobObjectW(ptr::null_mut(), ptr::null());
+            if handle == NULL {
+                warn!(target: "lazymc", "Failed to create Job Object for server process");
+                return None;
+            }
+            let job = Self(handle);
+
+            let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = mem::zeroed();
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            let set = SetInformationJobObject(
+                job.0,
+                JobObjectExtendedLimitInformation,
+                &mut info as *mut _ as *mut _,
+                mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            );
+            if set == FALSE {
+                warn!(target: "lazymc", "Failed to configure Job Object for server process");
+                return None;
+            }
+
+            Some(job)
+        }
+    }
+
+    /// Assign process to job. Processes it spawns are assigned automatically.
+    ///
+    /// This is unsafe because the PID isn't checked.
+    pub unsafe fn assign(&self, pid: u32) -> bool {
+        let handle = OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, FALSE, pid);
+        if handle == NULL {
+            warn!(target: "lazymc", "Failed to open process handle in order to assign it to Job Object");
+            return false;
+        }
+
+        let assigned = AssignProcessToJobObject(self.0, handle) == TRUE;
+        CloseHandle(handle);
+        assigned
+    }
+
+    /// Terminate all processes in job.
+    pub fn terminate(&self) -> bool {
+        debug!(target: "lazymc", "Terminating server Job Object to kill server");
+        unsafe { TerminateJobObject(self.0, 1) == TRUE }
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
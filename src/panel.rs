@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use reqwest::RequestBuilder;
+use serde_json::Value;
+
+use crate::config::{Panel, PanelKind};
+
+/// Server power state as reported by the panel.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PowerState {
+    /// Server is starting, or running.
+    Running,
+
+    /// Server is stopping.
+    Stopping,
+
+    /// Server is offline.
+    Offline,
+}
+
+/// Power signal to send through the panel.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Signal {
+    /// Start the server.
+    Start,
+
+    /// Gracefully stop the server.
+    Stop,
+
+    /// Kill the server.
+    Kill,
+}
+
+impl Signal {
+    /// Signal name, as used by Pterodactyl.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Stop => "stop",
+            Self::Kill => "kill",
+        }
+    }
+
+    /// Server action, as used by Crafty Controller.
+    fn crafty_action(self) -> &'static str {
+        match self {
+            Self::Start => "start_server",
+            Self::Stop => "stop_server",
+            Self::Kill => "kill_server",
+        }
+    }
+}
+
+/// Send a power signal to the server through the panel.
+pub async fn power(panel: &Panel, signal: Signal) -> Result<(), reqwest::Error> {
+    let url = panel.url.trim_end_matches('/');
+    let client = reqwest::Client::new();
+    let request = match panel.kind {
+        PanelKind::Pterodactyl => client
+            .post(format!("{url}/api/client/servers/{}/power", panel.server))
+            .json(&serde_json::json!({ "signal": signal.name() })),
+        PanelKind::Crafty => client.post(format!(
+            "{url}/api/v2/servers/{}/action/{}",
+            panel.server,
+            signal.crafty_action()
+        )),
+    };
+
+    send(panel, request).await?;
+    Ok(())
+}
+
+/// Fetch the server power state from the panel.
+pub async fn state(panel: &Panel) -> Result<PowerState, reqwest::Error> {
+    let url = panel.url.trim_end_matches('/');
+    let client = reqwest::Client::new();
+    let state = match panel.kind {
+        PanelKind::Pterodactyl => {
+            let request = client.get(format!(
+                "{url}/api/client/servers/{}/resources",
+                panel.server
+            ));
+            let body = send(panel, request).await?;
+            match body["attributes"]["current_state"].as_str() {
+                Some("starting" | "running") => PowerState::Running,
+                Some("stopping") => PowerState::Stopping,
+                _ => PowerState::Offline,
+            }
+        }
+        PanelKind::Crafty => {
+            let request = client.get(format!("{url}/api/v2/servers/{}/stats", panel.server));
+            let body = send(panel, request).await?;
+            match body["data"]["running"].as_bool() {
+                Some(true) => PowerState::Running,
+                _ => PowerState::Offline,
+            }
+        }
+    };
+    Ok(state)
+}
+
+/// Send an authenticated panel API request, return the JSON response body.
+///
+/// The body is `null` if the response is empty.
+async fn send(panel: &Panel, request: RequestBuilder) -> Result<Value, reqwest::Error> {
+    let response = request
+        .bearer_auth(&panel.api_key)
+        .header("Accept", "application/json")
+        .timeout(Duration::from_secs(panel.timeout as u64))
+        .send()
+        .await?
+        .error_for_status()?;
+    let body = response.bytes().await?;
+    Ok(serde_json::from_slice(&body).unwrap_or_default())
+}
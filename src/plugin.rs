@@ -0,0 +1,220 @@
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::OnceCell;
+use serde_json::{json, Value};
+use tokio::sync::broadcast::error::RecvError;
+use wasmtime::{Engine, Instance, Memory, Module, Store};
+
+use crate::config::Config;
+use crate::event::Event;
+use crate::server::{Server, State};
+
+/// Loaded plugins.
+static PLUGINS: OnceCell<Vec<Plugin>> = OnceCell::new();
+
+/// A loaded WASM plugin.
+struct Plugin {
+    /// Plugin name, its file name.
+    name: String,
+
+    /// Fuel available for each hook call.
+    fuel: u64,
+
+    /// Plugin store and instance.
+    inner: Mutex<(Store<()>, Instance, Memory)>,
+}
+
+impl Plugin {
+    /// Load plugin from file.
+    fn load(engine: &Engine, path: &Path, fuel: u64) -> Result<Self, anyhow::Error> {
+        let module = Module::from_file(engine, path)?;
+        let mut store = Store::new(engine, ());
+        store.set_fuel(fuel)?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow!("plugin does not export memory"))?;
+
+        // Plugin must implement allocation contract
+        instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        instance.get_typed_func::<(i32, i32), ()>(&mut store, "dealloc")?;
+
+        Ok(Self {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into())
+                .unwrap_or_default(),
+            fuel,
+            inner: Mutex::new((store, instance, memory)),
+        })
+    }
+
+    /// Call hook with JSON input.
+    ///
+    /// Input is written to memory allocated through the plugin `alloc` export. After the hook
+    /// returns, both the input and the output are freed through the plugin `dealloc` export.
+    ///
+    /// This blocks until the hook completes or runs out of fuel.
+    ///
+    /// Returns `None` if the plugin doesn't implement the hook, or returned nothing.
+    fn call(&self, hook: &str, input: &Value) -> Result<Option<Value>, anyhow::Error> {
+        let mut inner = self.inner.lock().unwrap();
+        let (store, instance, memory) = &mut *inner;
+
+        // Plugin may not implement this hook
+        let func = match instance.get_typed_func::<(i32, i32), i64>(&mut *store, hook) {
+            Ok(func) => func,
+            Err(_) => return Ok(None),
+        };
+        store.set_fuel(self.fuel)?;
+
+        // Write input into plugin memory
+        let input = serde_json::to_vec(input)?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut *store, "alloc")?;
+        let dealloc = instance.get_typed_func::<(i32, i32), ()>(&mut *store, "dealloc")?;
+        let ptr = alloc.call(&mut *store, input.len() as i32)?;
+        memory.write(&mut *store, ptr as usize, &input)?;
+
+        // Call hook, free input even if hook failed
+        let result = func.call(&mut *store, (ptr, input.len() as i32));
+        store.set_fuel(self.fuel)?;
+        dealloc.call(&mut *store, (ptr, input.len() as i32))?;
+        let result = result?;
+        if result == 0 {
+            return Ok(None);
+        }
+
+        // Read output from packed pointer and length, then free it
+        let (ptr, len) = ((result >> 32) as u32, result as u32);
+        let mut output = vec![0; len as usize];
+        let read = memory.read(&*store, ptr as usize, &mut output);
+        dealloc.call(&mut *store, (ptr as i32, len as i32))?;
+        read?;
+
+        Ok(Some(serde_json::from_slice(&output)?))
+    }
+}
+
+/// Load configured plugins.
+///
+/// Must be called once before invoking any hooks.
+pub fn load(config: &Config) {
+    let plugins = PLUGINS.get_or_init(|| {
+        if config.plugins.wasm.is_empty() {
+            return vec![];
+        }
+
+        let engine = match Engine::new(wasmtime::Config::new().consume_fuel(true)) {
+            Ok(engine) => engine,
+            Err(err) => {
+                error!(target: "lazymc::plugin", "Failed to initialize WASM runtime: {}", err);
+                return vec![];
+            }
+        };
+
+        config
+            .plugins
+            .wasm
+            .iter()
+            .filter_map(
+                |path| match Plugin::load(&engine, path, config.plugins.fuel) {
+                    Ok(plugin) => Some(plugin),
+                    Err(err) => {
+                        error!(target: "lazymc::plugin", "Failed to load plugin {}: {}", path.display(), err);
+                        None
+                    }
+                },
+            )
+            .collect()
+    });
+
+    for plugin in plugins {
+        info!(target: "lazymc::plugin", "Loaded plugin {}", plugin.name);
+    }
+}
+
+/// Invoke hook on all plugins on a blocking thread, collect their output.
+async fn invoke(hook: &'static str, input: Value) -> Vec<Value> {
+    // Skip blocking thread if there are no plugins
+    if PLUGINS.get().map(Vec::is_empty).unwrap_or(true) {
+        return vec![];
+    }
+
+    tokio::task::spawn_blocking(move || invoke_blocking(hook, input))
+        .await
+        .unwrap_or_default()
+}
+
+/// Invoke hook on all plugins, collect their output.
+///
+/// This blocks until all plugins complete.
+fn invoke_blocking(hook: &str, input: Value) -> Vec<Value> {
+    PLUGINS
+        .get()
+        .into_iter()
+        .flatten()
+        .filter_map(|plugin| match plugin.call(hook, &input) {
+            Ok(output) => output,
+            Err(err) => {
+                warn!(target: "lazymc::plugin", "Plugin {} failed in {}: {}", plugin.name, hook, err);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Invoke `on_login_attempt` hook.
+///
+/// Returns kick message if any plugin vetoes the login, which prevents waking the server.
+pub async fn on_login_attempt(username: Option<&str>, ip: IpAddr, state: State) -> Option<String> {
+    let input = json!({ "username": username, "ip": ip, "state": state.name() });
+    invoke("on_login_attempt", input)
+        .await
+        .into_iter()
+        .find(|output| output["allow"] == false)
+        .map(|output| output["message"].as_str().unwrap_or_default().to_string())
+}
+
+/// Invoke `on_status_request` hook.
+///
+/// Returns MOTD to show instead, if changed by any plugin.
+pub async fn on_status_request(ip: IpAddr, state: State, motd: &str) -> Option<String> {
+    let mut changed = None;
+    for output in invoke(
+        "on_status_request",
+        json!({ "ip": ip, "state": state.name(), "motd": motd }),
+    )
+    .await
+    {
+        if let Some(motd) = output["motd"].as_str() {
+            changed = Some(motd.to_string());
+        }
+    }
+    changed
+}
+
+/// Plugin task.
+///
+/// Invokes `on_state_change` hook for server state changes.
+pub async fn service(server: Arc<Server>) {
+    if PLUGINS
+        .get()
+        .map(|plugins| plugins.is_empty())
+        .unwrap_or(true)
+    {
+        return;
+    }
+
+    let mut events = server.subscribe();
+    loop {
+        match events.recv().await {
+            Ok(Event::State { from, to }) => {
+                tokio::spawn(invoke("on_state_change", json!({ "from": from, "to": to })));
+            }
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
@@ -1,4 +1,3 @@
-use std::ops::Deref;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -8,7 +7,6 @@ use minecraft_protocol::version::v1_14_4::handshake::Handshake;
 use minecraft_protocol::version::v1_14_4::login::{
     LoginPluginRequest, LoginPluginResponse, LoginStart, SetCompression,
 };
-use tokio::net::TcpStream;
 use tokio::time;
 
 use crate::config::Config;
@@ -17,6 +15,7 @@ use crate::net;
 use crate::proto::client::{Client, ClientInfo, ClientState};
 use crate::proto::packets::play::join_game::JoinGameData;
 use crate::proto::{self, packet, packets};
+use crate::proxy::{Target, TargetStream};
 use crate::server::{Server, State};
 
 /// Minecraft username to use for probing the server.
@@ -38,6 +37,12 @@ const PROBE_JOIN_GAME_TIMEOUT: Duration = Duration::from_secs(20);
 pub async fn probe(config: Arc<Config>, server: Arc<Server>) -> Result<(), ()> {
     debug!(target: "lazymc::probe", "Starting server probe...");
 
+    // Don't wake server in maintenance mode
+    if server.maintenance() {
+        warn!(target: "lazymc::probe", "Not probing server, maintenance mode is enabled");
+        return Err(());
+    }
+
     // Start server if not starting already
     if Server::start(config.clone(), server.clone(), None).await {
         info!(target: "lazymc::probe", "Starting server to probe...");
@@ -67,34 +72,26 @@ async fn wait_until_online<'a>(server: &Server) -> Result<bool, ()> {
     // A task to wait for suitable server state
     // Waits for started state, errors if stopping/stopped state is reached
     let task_wait = async {
-        let mut state = server.state_receiver();
-        loop {
-            // Wait for state change
-            state.changed().await.unwrap();
-
-            match state.borrow().deref() {
-                // Still waiting on server start
-                State::Starting => {
-                    continue;
-                }
-
-                // Server started, start relaying and proxy
-                State::Started => {
-                    break true;
-                }
-
-                // Server stopping, this shouldn't happen, skip
-                State::Stopping => {
-                    warn!(target: "lazymc::probe", "Server stopping while trying to probe, skipping");
-                    break false;
-                }
-
-                // Server stopped, this shouldn't happen, skip
-                State::Stopped => {
-                    error!(target: "lazymc::probe", "Server stopped while trying to probe, skipping");
-                    break false;
-                }
+        match server
+            .wait_for_state(|state| state != State::Starting)
+            .await
+        {
+            // Server started, start relaying and proxy
+            State::Started => true,
+
+            // Server stopping, this shouldn't happen, skip
+            State::Stopping => {
+                warn!(target: "lazymc::probe", "Server stopping while trying to probe, skipping");
+                false
             }
+
+            // Server stopped, this shouldn't happen, skip
+            State::Stopped => {
+                error!(target: "lazymc::probe", "Server stopped while trying to probe, skipping");
+                false
+            }
+
+            State::Starting => unreachable!(),
         }
     };
 
@@ -138,22 +135,22 @@ async fn connect_to_server_no_timeout(
 ) -> Result<Vec<Vec<u8>>, ()> {
     // Open connection
     // TODO: on connect fail, ping server and redirect to serve_status if offline
-    let mut outbound = TcpStream::connect(config.server.address)
+    let mut outbound = Target::server(config, server)
+        .connect()
         .await
         .map_err(|_| ())?;
 
     // Construct temporary server client
     let tmp_client = match outbound.local_addr() {
-        Ok(addr) => Client::new(addr),
-        Err(_) => Client::dummy(),
+        Some(addr) => Client::new(addr),
+        None => Client::dummy(),
     };
     tmp_client.set_state(ClientState::Login);
 
     // Construct client info
     let mut tmp_client_info = ClientInfo::empty();
-    tmp_client_info.protocol.replace(config.public.protocol);
-
-    let (mut reader, mut writer) = outbound.split();
+    let protocol = server.protocol(config).await;
+    tmp_client_info.protocol.replace(protocol);
 
     // Select server address to use, add magic if Forge
     let server_addr = if config.server.forge {
@@ -165,13 +162,13 @@ async fn connect_to_server_no_timeout(
     // Send handshake packet
     packet::write_packet(
         Handshake {
-            protocol_version: config.public.protocol as i32,
+            protocol_version: protocol as i32,
             server_addr,
             server_port: config.server.address.port(),
             next_state: ClientState::Login.to_id(),
         },
         &tmp_client,
-        &mut writer,
+        &mut outbound,
     )
     .await?;
 
@@ -181,7 +178,7 @@ async fn connect_to_server_no_timeout(
             name: PROBE_USER.into(),
         },
         &tmp_client,
-        &mut writer,
+        &mut outbound,
     )
     .await?;
 
@@ -191,7 +188,7 @@ async fn connect_to_server_no_timeout(
 
     loop {
         // Read packet from stream
-        let (packet, raw) = match packet::read_packet(&tmp_client, &mut buf, &mut reader).await {
+        let (packet, raw) = match packet::read_packet(&tmp_client, &mut buf, &mut outbound).await {
             Ok(Some(packet)) => packet,
             Ok(None) => break,
             Err(_) => {
@@ -240,7 +237,7 @@ async fn connect_to_server_no_timeout(
                 forge_payload.push(raw);
 
                 // Respond to Forge login plugin request
-                forge::respond_login_plugin_request(&tmp_client, plugin_request, &mut writer)
+                forge::respond_login_plugin_request(&tmp_client, plugin_request, &mut outbound)
                     .await?;
                 continue;
             }
@@ -255,7 +252,7 @@ async fn connect_to_server_no_timeout(
                     data: vec![],
                 },
                 &tmp_client,
-                &mut writer,
+                &mut outbound,
             )
             .await?;
 
@@ -303,7 +300,7 @@ async fn connect_to_server_no_timeout(
 async fn wait_for_server_join_game(
     client: &Client,
     client_info: &ClientInfo,
-    outbound: &mut TcpStream,
+    outbound: &mut TargetStream,
     buf: &mut BytesMut,
 ) -> Result<JoinGameData, ()> {
     time::timeout(
@@ -324,14 +321,12 @@ async fn wait_for_server_join_game(
 async fn wait_for_server_join_game_no_timeout(
     client: &Client,
     client_info: &ClientInfo,
-    outbound: &mut TcpStream,
+    outbound: &mut TargetStream,
     buf: &mut BytesMut,
 ) -> Result<JoinGameData, ()> {
-    let (mut reader, mut _writer) = outbound.split();
-
     loop {
         // Read packet from stream
-        let (packet, _raw) = match packet::read_packet(client, buf, &mut reader).await {
+        let (packet, _raw) = match packet::read_packet(client, buf, outbound).await {
             Ok(Some(packet)) => packet,
             Ok(None) => break,
             Err(_) => {
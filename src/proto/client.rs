@@ -1,6 +1,5 @@
 use std::net::SocketAddr;
 use std::sync::atomic::{AtomicI32, Ordering};
-use std::sync::Mutex;
 
 use minecraft_protocol::version::v1_14_4::handshake::Handshake;
 
@@ -13,7 +12,9 @@ pub struct Client {
     pub peer: SocketAddr,
 
     /// Current client state.
-    pub state: Mutex<ClientState>,
+    ///
+    /// Matches `ClientState` ID, lock-free as it is accessed for every packet.
+    state: AtomicI32,
 
     /// Compression state.
     ///
@@ -26,7 +27,7 @@ impl Client {
     pub fn new(peer: SocketAddr) -> Self {
         Self {
             peer,
-            state: Default::default(),
+            state: AtomicI32::new(ClientState::default().to_id()),
             compression: AtomicI32::new(-1),
         }
     }
@@ -38,12 +39,13 @@ impl Client {
 
     /// Get client state.
     pub fn state(&self) -> ClientState {
-        *self.state.lock().unwrap()
+        let id = self.state.load(Ordering::Relaxed);
+        ClientState::from_id(id).unwrap_or(ClientState::Play)
     }
 
     /// Set client state.
     pub fn set_state(&self, state: ClientState) {
-        *self.state.lock().unwrap() = state;
+        self.state.store(state.to_id(), Ordering::Relaxed);
     }
 
     /// Get compression threshold.
@@ -123,6 +125,11 @@ pub struct ClientInfo {
 
     /// Client username.
     pub username: Option<String>,
+
+    /// Login start packet data as received from client.
+    ///
+    /// Includes chat signing data on some versions, which must be preserved.
+    pub login_start: Option<Vec<u8>>,
 }
 
 impl ClientInfo {
@@ -0,0 +1,157 @@
+//! Packet debugging, enabled with `--debug-packets`.
+//!
+//! Logs decoded handshake, status and login packets, and optionally writes them to a JSON lines
+//! file. Fields that may hold secrets are redacted.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use minecraft_protocol::decoder::Decoder;
+use minecraft_protocol::version::v1_14_4::handshake::Handshake;
+use minecraft_protocol::version::v1_14_4::login::LoginStart;
+use once_cell::sync::OnceCell;
+use serde_json::{json, Value};
+
+use crate::proto::client::{Client, ClientState};
+use crate::proto::packet::RawPacket;
+use crate::proto::packets;
+use crate::stats;
+use crate::types;
+
+/// Packet debug file, if packet debugging is enabled.
+static DEBUG: OnceCell<Option<Mutex<File>>> = OnceCell::new();
+
+/// Packet direction.
+#[derive(Debug, Copy, Clone)]
+pub enum Direction {
+    /// Client to lazymc.
+    Serverbound,
+
+    /// lazymc to client.
+    Clientbound,
+}
+
+/// Enable packet debugging, optionally writing packets to the given file.
+pub fn init(file: Option<&Path>) -> io::Result<()> {
+    let file = match file {
+        Some(path) => Some(Mutex::new(
+            OpenOptions::new().create(true).append(true).open(path)?,
+        )),
+        None => None,
+    };
+    let _ = DEBUG.set(file);
+    Ok(())
+}
+
+/// Log packet of client in its current state, if packet debugging is enabled.
+pub fn log(client: &Client, direction: Direction, packet: &RawPacket) {
+    let debug = match DEBUG.get() {
+        Some(debug) => debug,
+        None => return,
+    };
+
+    let state = client.state();
+    let decoded = decode(state, direction, packet);
+    info!(
+        target: "lazymc::packets",
+        "{} {:?} {:?} packet 0x{:02x} ({} bytes): {}",
+        client.peer,
+        direction,
+        state,
+        packet.id,
+        packet.data.len(),
+        decoded,
+    );
+
+    if let Some(file) = debug {
+        let entry = json!({
+            "time": stats::now(),
+            "peer": client.peer.to_string(),
+            "direction": format!("{direction:?}").to_lowercase(),
+            "state": format!("{state:?}").to_lowercase(),
+            "id": packet.id,
+            "len": packet.data.len(),
+            "packet": decoded,
+        });
+        let mut file = file.lock().unwrap();
+        if let Err(err) = writeln!(file, "{entry}") {
+            warn!(target: "lazymc::packets", "Failed to write packet to debug file: {}", err);
+        }
+    }
+}
+
+/// Decode known packet into JSON, with secrets redacted.
+fn decode(state: ClientState, direction: Direction, packet: &RawPacket) -> Value {
+    let data = packet.data.as_slice();
+    match (state, direction, packet.id) {
+        (ClientState::Handshake, Direction::Serverbound, packets::handshake::SERVER_HANDSHAKE) => {
+            match Handshake::decode(&mut &*data) {
+                Ok(handshake) => json!({
+                    "type": "handshake",
+                    "protocol": handshake.protocol_version,
+                    "server_addr": redact_addr(&handshake.server_addr),
+                    "server_port": handshake.server_port,
+                    "next_state": handshake.next_state,
+                }),
+                Err(_) => json!({ "type": "handshake", "malformed": true }),
+            }
+        }
+        (ClientState::Status, Direction::Serverbound, packets::status::SERVER_STATUS) => {
+            json!({ "type": "status_request" })
+        }
+        (ClientState::Status, Direction::Serverbound, packets::status::SERVER_PING) => {
+            json!({ "type": "ping" })
+        }
+        (ClientState::Status, Direction::Clientbound, packets::status::CLIENT_STATUS) => {
+            json!({ "type": "status_response", "status": status_json(data) })
+        }
+        (ClientState::Login, Direction::Serverbound, packets::login::SERVER_LOGIN_START) => {
+            match LoginStart::decode(&mut &*data) {
+                // Chat signing data follows the username, never include it
+                Ok(login_start) => json!({
+                    "type": "login_start",
+                    "username": login_start.name,
+                    "redacted_bytes": data.len().saturating_sub(login_start.name.len() + 1),
+                }),
+                Err(_) => json!({ "type": "login_start", "malformed": true }),
+            }
+        }
+        _ => json!({ "type": "unknown", "redacted": true }),
+    }
+}
+
+/// Redact extra data appended to the handshake server address.
+///
+/// BungeeCord IP forwarding appends the client IP, UUID and profile properties separated by null
+/// bytes. Forge appends a marker, which is kept.
+fn redact_addr(addr: &str) -> String {
+    match addr.split_once('\0') {
+        Some((host, rest)) if rest.starts_with("FML") => format!("{host}\\0{rest}"),
+        Some((host, _)) => format!("{host}\\0<redacted>"),
+        None => addr.into(),
+    }
+}
+
+/// Read status JSON from status response packet data, without favicon.
+fn status_json(data: &[u8]) -> Value {
+    let json = types::read_var_int(data)
+        .ok()
+        .and_then(|(len, json_len)| {
+            let end = len.checked_add(usize::try_from(json_len).ok()?)?;
+            data.get(len..end)
+        })
+        .and_then(|json| serde_json::from_slice::<Value>(json).ok());
+    match json {
+        Some(mut json) => {
+            if let Some(json) = json.as_object_mut() {
+                if json.remove("favicon").is_some() {
+                    json.insert("favicon".into(), "<omitted>".into());
+                }
+            }
+            json
+        }
+        None => Value::Null,
+    }
+}
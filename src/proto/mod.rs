@@ -1,5 +1,6 @@
 pub mod action;
 pub mod client;
+pub mod debug;
 pub mod packet;
 pub mod packets;
 
@@ -19,6 +20,62 @@ pub const PROTO_DEFAULT_VERSION: &str = "1.19.3";
 /// Should be kept up-to-date with latest supported Minecraft version by lazymc.
 pub const PROTO_DEFAULT_PROTOCOL: u32 = 761;
 
+/// Known Minecraft release versions and their protocol version.
+///
+/// See: https://wiki.vg/Protocol_version_numbers
+pub const PROTO_VERSIONS: &[(&str, u32)] = &[
+    ("1.7.2", 4),
+    ("1.7.10", 5),
+    ("1.8", 47),
+    ("1.8.9", 47),
+    ("1.9", 107),
+    ("1.9.2", 109),
+    ("1.9.4", 110),
+    ("1.10", 210),
+    ("1.10.2", 210),
+    ("1.11", 315),
+    ("1.11.2", 316),
+    ("1.12", 335),
+    ("1.12.1", 338),
+    ("1.12.2", 340),
+    ("1.13", 393),
+    ("1.13.1", 401),
+    ("1.13.2", 404),
+    ("1.14", 477),
+    ("1.14.1", 480),
+    ("1.14.2", 485),
+    ("1.14.3", 490),
+    ("1.14.4", 498),
+    ("1.15", 573),
+    ("1.15.1", 575),
+    ("1.15.2", 578),
+    ("1.16", 735),
+    ("1.16.1", 736),
+    ("1.16.2", 751),
+    ("1.16.3", 753),
+    ("1.16.4", 754),
+    ("1.16.5", 754),
+    ("1.17", 755),
+    ("1.17.1", 756),
+    ("1.18", 757),
+    ("1.18.1", 757),
+    ("1.18.2", 758),
+    ("1.19", 759),
+    ("1.19.1", 760),
+    ("1.19.2", 760),
+    ("1.19.3", 761),
+    ("1.19.4", 762),
+    ("1.20", 763),
+    ("1.20.1", 763),
+    ("1.20.2", 764),
+    ("1.20.3", 765),
+    ("1.20.4", 765),
+    ("1.20.5", 766),
+    ("1.20.6", 766),
+    ("1.21", 767),
+    ("1.21.1", 767),
+];
+
 /// Compression threshold to use.
 // TODO: read this from server.properties instead
 pub const COMPRESSION_THRESHOLD: i32 = 256;
@@ -5,16 +5,22 @@ use bytes::BytesMut;
 use flate2::read::ZlibDecoder;
 use flate2::write::ZlibEncoder;
 use flate2::Compression;
+use minecraft_protocol::decoder::Decoder;
 use minecraft_protocol::encoder::Encoder;
+use minecraft_protocol::version::v1_14_4::handshake::Handshake;
+use minecraft_protocol::version::v1_14_4::login::LoginStart;
 use minecraft_protocol::version::PacketId;
 use tokio::io;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::tcp::{ReadHalf, WriteHalf};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
-use crate::proto::client::Client;
+use crate::proto::client::{Client, ClientState};
+use crate::proto::packets;
 use crate::proto::BUF_SIZE;
 use crate::types;
 
+/// Maximum packet length in bytes, as defined by the protocol.
+pub const MAX_PACKET_SIZE: usize = (1 << 21) - 1;
+
 /// Raw Minecraft packet.
 ///
 /// Having a packet ID and a raw data byte array.
@@ -161,7 +167,19 @@ impl RawPacket {
 pub async fn read_packet(
     client: &Client,
     buf: &mut BytesMut,
-    stream: &mut ReadHalf<'_>,
+    stream: &mut (impl AsyncRead + Unpin),
+) -> Result<Option<(RawPacket, Vec<u8>)>, ()> {
+    read_packet_max(client, buf, stream, MAX_PACKET_SIZE).await
+}
+
+/// Read raw packet from stream, with a maximum packet length in bytes.
+///
+/// Fails early if the packet length exceeds the maximum, without buffering the packet.
+pub async fn read_packet_max(
+    client: &Client,
+    buf: &mut BytesMut,
+    stream: &mut (impl AsyncRead + Unpin),
+    max_len: usize,
 ) -> Result<Option<(RawPacket, Vec<u8>)>, ()> {
     // Keep reading until we have at least 2 bytes
     while buf.len() < 2 {
@@ -190,6 +208,10 @@ pub async fn read_packet(
             return Err(err);
         }
     };
+    if len < 0 || len as usize > max_len {
+        debug!(target: "lazymc", "Packet length {} exceeds maximum of {} bytes", len, max_len);
+        return Err(());
+    }
 
     // Keep reading until we have all packet bytes
     while buf.len() < consumed + len as usize {
@@ -223,7 +245,7 @@ pub async fn read_packet(
 pub async fn write_packet(
     packet: impl PacketId + Encoder + Debug,
     client: &Client,
-    writer: &mut WriteHalf<'_>,
+    writer: &mut (impl AsyncWrite + Unpin),
 ) -> Result<(), ()> {
     let mut data = Vec::new();
     packet.encode(&mut data).map_err(|_| ())?;
@@ -233,3 +255,46 @@ pub async fn write_packet(
 
     Ok(())
 }
+
+/// Sniff login username from the start of a client stream.
+///
+/// Expects the handshake and login start packets at the start of the buffer. Returns `Ok(None)`
+/// if more data is needed, and an error if the stream isn't logging in or is malformed.
+pub fn sniff_login_username(mut buf: &[u8]) -> Result<Option<String>, ()> {
+    let client = Client::dummy();
+    let mut handshaked = false;
+    loop {
+        // Packet must be complete, a partial length is assumed incomplete
+        let (consumed, len) = match types::read_var_int(buf) {
+            Ok(result) => result,
+            Err(_) if buf.len() < 5 => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        if len < 0 {
+            return Err(());
+        }
+        if buf.len() < consumed + len as usize {
+            return Ok(None);
+        }
+        let packet = RawPacket::decode_without_len(&client, &buf[consumed..][..len as usize])?;
+        buf = &buf[consumed + len as usize..];
+
+        if !handshaked {
+            if packet.id != packets::handshake::SERVER_HANDSHAKE {
+                return Err(());
+            }
+            let handshake = Handshake::decode(&mut packet.data.as_slice()).map_err(|_| ())?;
+            if ClientState::from_id(handshake.next_state) != Some(ClientState::Login) {
+                return Err(());
+            }
+            handshaked = true;
+            continue;
+        }
+
+        if packet.id != packets::login::SERVER_LOGIN_START {
+            return Err(());
+        }
+        let login = LoginStart::decode(&mut packet.data.as_slice()).map_err(|_| ())?;
+        return Ok(Some(login.name));
+    }
+}
@@ -28,6 +28,5 @@ pub mod login {
     pub const CLIENT_ENCRYPTION_REQUEST: u8 = EncryptionRequest::PACKET_ID;
     pub const CLIENT_LOGIN_PLUGIN_REQUEST: u8 = LoginPluginRequest::PACKET_ID;
     pub const SERVER_LOGIN_START: u8 = LoginStart::PACKET_ID;
-    #[cfg(feature = "lobby")]
     pub const SERVER_LOGIN_PLUGIN_RESPONSE: u8 = LoginPluginResponse::PACKET_ID;
 }
@@ -105,7 +105,7 @@ pub async fn lobby_send(
     writer: &mut WriteHalf<'_>,
     server: &Server,
 ) -> Result<(), ()> {
-    let status = server.status().await;
+    let status = server.status();
     let join_game = server.probed_join_game.read().await;
 
     // Get dimension codec and build lobby dimension
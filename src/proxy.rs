@@ -1,37 +1,77 @@
-use std::error::Error;
+use std::fmt;
+use std::future::Future;
 use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
 use bytes::BytesMut;
 use proxy_protocol::version2::{ProxyAddresses, ProxyCommand, ProxyTransportProtocol};
-use proxy_protocol::EncodeError;
-use tokio::io;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::net::tcp::ReadHalf;
 use tokio::net::TcpStream;
+#[cfg(unix)]
+use tokio::net::UnixStream;
+use tokio::time;
 
+use crate::config::{self, Config};
+use crate::error::Error;
+use crate::metrics;
 use crate::net;
+use crate::proto::packet;
+use crate::server::Server;
+use crate::stats::{self, Entry};
+use crate::util::throttle;
 
-/// Proxy the inbound stream to a target address.
+/// Maximum delay between connect retries.
+const CONNECT_RETRY_MAX_DELAY: Duration = Duration::from_secs(5);
+
+/// Only retry connecting within this time after the server started.
+const CONNECT_RETRY_WINDOW: Duration = Duration::from_secs(60);
+
+/// Maximum number of bytes to read when sniffing the login username.
+const SNIFF_MAX: usize = 1024;
+
+/// Proxy the inbound stream to a target.
 pub async fn proxy(
     inbound: TcpStream,
     proxy_header: ProxyHeader,
-    addr_target: SocketAddr,
-) -> Result<(), Box<dyn Error>> {
-    proxy_with_queue(inbound, proxy_header, addr_target, &[]).await
+    target: Target,
+    options: ProxyOptions,
+) -> Result<(), Error> {
+    proxy_with_queue(inbound, proxy_header, target, &[], options).await
 }
 
-/// Proxy the inbound stream to a target address.
+/// Proxy the inbound stream to a target.
 ///
 /// Send the queue to the target server before proxying.
 pub async fn proxy_with_queue(
     inbound: TcpStream,
     proxy_header: ProxyHeader,
-    addr_target: SocketAddr,
+    target: Target,
     queue: &[u8],
-) -> Result<(), Box<dyn Error>> {
+    options: ProxyOptions,
+) -> Result<(), Error> {
     // Set up connection to server
     // TODO: on connect fail, ping server and redirect to serve_status if offline
-    let mut outbound = TcpStream::connect(addr_target).await?;
+    let outbound = connect(&options, || target.connect()).await?;
+    outbound.configure(&options.server_socket)?;
+    proxy_outbound_with_header(inbound, outbound, proxy_header, queue, options).await
+}
 
+/// Proxy the inbound stream to a connected outbound stream, sending the proxy header first.
+async fn proxy_outbound_with_header<O>(
+    inbound: TcpStream,
+    mut outbound: O,
+    proxy_header: ProxyHeader,
+    queue: &[u8],
+    options: ProxyOptions,
+) -> Result<(), Error>
+where
+    O: AsyncRead + AsyncWrite + Unpin,
+{
     // Add proxy header
     match proxy_header {
         ProxyHeader::None => {}
@@ -46,21 +86,51 @@ pub async fn proxy_with_queue(
     }
 
     // Start proxy on both streams
-    proxy_inbound_outbound_with_queue(inbound, outbound, &[], queue).await
+    proxy_inbound_outbound_with_queue(inbound, outbound, &[], queue, options).await
+}
+
+/// Connect to the target server.
+///
+/// Retries with exponential backoff as configured, because the server may accept status
+/// requests shortly before its game port is ready.
+async fn connect<S, F, Fut>(options: &ProxyOptions, mut connect: F) -> Result<S, io::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<S, io::Error>>,
+{
+    let mut delay = options.connect_retry_delay;
+    let mut attempt = 0;
+    loop {
+        match connect().await {
+            Ok(stream) => return Ok(stream),
+            Err(err) if attempt < options.connect_retries => {
+                attempt += 1;
+                debug!(target: "lazymc", "Failed to connect to server, retrying in {}ms ({}/{}): {}", delay.as_millis(), attempt, options.connect_retries, err);
+                time::sleep(delay).await;
+                delay = delay.saturating_mul(2).min(CONNECT_RETRY_MAX_DELAY);
+            }
+            Err(err) => return Err(err),
+        }
+    }
 }
 
 /// Proxy the inbound stream to a target address.
 ///
 /// Send the queue to the target server before proxying.
 // TODO: find better name for this
-pub async fn proxy_inbound_outbound_with_queue(
+pub async fn proxy_inbound_outbound_with_queue<O>(
     mut inbound: TcpStream,
-    mut outbound: TcpStream,
+    outbound: O,
     inbound_queue: &[u8],
     outbound_queue: &[u8],
-) -> Result<(), Box<dyn Error>> {
+    options: ProxyOptions,
+) -> Result<(), Error>
+where
+    O: AsyncRead + AsyncWrite + Unpin,
+{
+    let peer = inbound.peer_addr()?;
     let (mut ri, mut wi) = inbound.split();
-    let (mut ro, mut wo) = outbound.split();
+    let (mut ro, mut wo) = io::split(outbound);
 
     // Forward queued bytes to client once writable
     if !inbound_queue.is_empty() {
@@ -69,23 +139,91 @@ pub async fn proxy_inbound_outbound_with_queue(
         wi.write_all(inbound_queue).await?;
     }
 
-    // Forward queued bytes to server once writable
+    // Forward queued bytes to server
     if !outbound_queue.is_empty() {
-        wo.writable().await?;
         trace!(target: "lazymc", "Relaying {} queued bytes to server", outbound_queue.len());
         wo.write_all(outbound_queue).await?;
     }
 
+    let start = Instant::now();
+    let time = stats::now();
+    let (mut to_server, mut to_client) = (0, 0);
+    let mut username = options.username.clone();
+    let mut login = false;
+
     let client_to_server = async {
-        io::copy(&mut ri, &mut wo).await?;
+        // Sniff username from login if not known
+        if username.is_none() {
+            username = sniff_username(&mut ri, &mut wo, outbound_queue, &mut to_server).await?;
+        }
+
+        // Only count logins as active sessions, not status requests
+        if username.is_some() {
+            metrics::record_session_open();
+            login = true;
+        }
+
+        throttle::copy(
+            &mut ri,
+            &mut wo,
+            options.upload_limit,
+            options.buffer_size,
+            &mut to_server,
+        )
+        .await?;
         wo.shutdown().await
     };
     let server_to_client = async {
-        io::copy(&mut ro, &mut wi).await?;
+        throttle::copy(
+            &mut ro,
+            &mut wi,
+            options.download_limit,
+            options.buffer_size,
+            &mut to_client,
+        )
+        .await?;
         wi.shutdown().await
     };
 
-    tokio::try_join!(client_to_server, server_to_client)?;
+    let result = tokio::try_join!(client_to_server, server_to_client);
+
+    // Account session traffic, also if proxying failed
+    let duration = start.elapsed();
+    if login {
+        metrics::record_session_close();
+    }
+    metrics::record_session(to_server, to_client, duration);
+    match username {
+        Some(username) => {
+            info!(
+                target: "lazymc::proxy",
+                "Session of '{}' from {} closed after {}s, {} bytes to server, {} bytes to client",
+                username,
+                peer.ip(),
+                duration.as_secs(),
+                to_server,
+                to_client,
+            );
+            stats::record(Entry::Session {
+                username,
+                ip: peer.ip(),
+                time,
+                duration: duration.as_secs(),
+                to_server,
+                to_client,
+            })
+            .await;
+        }
+        None => debug!(
+            target: "lazymc::proxy",
+            "Session from {} closed after {}s, {} bytes to server, {} bytes to client",
+            peer.ip(),
+            duration.as_secs(),
+            to_server,
+            to_client,
+        ),
+    }
+    result?;
 
     // Gracefully close connection if not done already
     net::close_tcp_stream(inbound).await?;
@@ -93,6 +231,214 @@ pub async fn proxy_inbound_outbound_with_queue(
     Ok(())
 }
 
+/// Sniff login username from client stream, relaying all data read.
+///
+/// The `queue` is data already relayed to the server. Returns `None` if the client isn't logging
+/// in or the username could not be found.
+async fn sniff_username(
+    reader: &mut ReadHalf<'_>,
+    writer: &mut (impl AsyncWrite + Unpin),
+    queue: &[u8],
+    total: &mut u64,
+) -> Result<Option<String>, io::Error> {
+    let mut buf = queue.to_vec();
+    let mut chunk = [0; SNIFF_MAX];
+    while buf.len() < SNIFF_MAX {
+        match packet::sniff_login_username(&buf) {
+            Ok(Some(username)) => return Ok(Some(username)),
+            Ok(None) => {}
+            Err(()) => return Ok(None),
+        }
+
+        let read = reader.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&chunk[..read]).await?;
+        *total += read as u64;
+        buf.extend_from_slice(&chunk[..read]);
+    }
+    Ok(None)
+}
+
+/// Target to proxy to.
+#[derive(Debug, Clone)]
+pub enum Target {
+    /// TCP address.
+    Tcp(SocketAddr),
+
+    /// Unix socket path.
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl Target {
+    /// The active server target, its Unix socket if set or its address otherwise.
+    ///
+    /// The Unix socket is only used for the primary blue/green instance.
+    pub fn server(config: &Config, server: &Server) -> Self {
+        #[cfg(unix)]
+        if !server.secondary_active() {
+            if let Some(path) = config::Server::socket_path(config) {
+                return Self::Unix(path);
+            }
+        }
+        Self::Tcp(server.address(config))
+    }
+
+    /// Connect to the target.
+    pub async fn connect(&self) -> Result<TargetStream, io::Error> {
+        match self {
+            Self::Tcp(addr) => TcpStream::connect(addr).await.map(TargetStream::Tcp),
+            #[cfg(unix)]
+            Self::Unix(path) => UnixStream::connect(path).await.map(TargetStream::Unix),
+        }
+    }
+}
+
+impl From<SocketAddr> for Target {
+    fn from(addr: SocketAddr) -> Self {
+        Self::Tcp(addr)
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "{addr}"),
+            #[cfg(unix)]
+            Self::Unix(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Stream connected to a target.
+#[derive(Debug)]
+pub enum TargetStream {
+    /// TCP stream.
+    Tcp(TcpStream),
+
+    /// Unix socket stream.
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl TargetStream {
+    /// Apply configured socket options, only applies to TCP streams.
+    pub fn configure(&self, options: &config::Socket) -> Result<(), Error> {
+        match self {
+            Self::Tcp(stream) => net::configure_socket(stream, options),
+            #[cfg(unix)]
+            Self::Unix(_) => Ok(()),
+        }
+    }
+
+    /// Get the local address, only known for TCP streams.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        match self {
+            Self::Tcp(stream) => stream.local_addr().ok(),
+            #[cfg(unix)]
+            Self::Unix(_) => None,
+        }
+    }
+}
+
+impl AsyncRead for TargetStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for TargetStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+/// Options for a proxied connection.
+#[derive(Debug, Clone, Default)]
+pub struct ProxyOptions {
+    /// Client to server rate limit in bytes per second, 0 for unlimited.
+    pub upload_limit: u64,
+
+    /// Server to client rate limit in bytes per second, 0 for unlimited.
+    pub download_limit: u64,
+
+    /// Buffer size in bytes for relaying data.
+    pub buffer_size: usize,
+
+    /// Socket options for the connection to the server.
+    pub server_socket: config::Socket,
+
+    /// Username of the proxied player, sniffed from login if not set.
+    pub username: Option<String>,
+
+    /// Number of times to retry connecting to the server.
+    pub connect_retries: u32,
+
+    /// Delay before the first connect retry, doubled for each next retry up to a maximum.
+    pub connect_retry_delay: Duration,
+}
+
+impl ProxyOptions {
+    /// Proxy options from configuration.
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            upload_limit: config.proxy.upload_limit * 1024,
+            download_limit: config.proxy.download_limit * 1024,
+            buffer_size: config.proxy.buffer_size,
+            server_socket: config.proxy.server,
+            username: None,
+            connect_retries: config.proxy.connect_retries,
+            connect_retry_delay: Duration::from_millis(config.proxy.connect_retry_delay),
+        }
+    }
+
+    /// Proxy options for connecting to the given server.
+    ///
+    /// Connecting is only retried shortly after the server started, when it may not accept
+    /// players yet.
+    pub fn for_server(config: &Config, server: &Server) -> Self {
+        let mut options = Self::from_config(config);
+        if !server.started_within(CONNECT_RETRY_WINDOW) {
+            options.connect_retries = 0;
+        }
+        options
+    }
+}
+
 /// Proxy header.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ProxyHeader {
@@ -123,7 +469,7 @@ impl ProxyHeader {
 /// Get the proxy header for a locally initiated connection.
 ///
 /// This header may be sent over the outbound stream to signal client information.
-pub fn local_proxy_header() -> Result<BytesMut, EncodeError> {
+pub fn local_proxy_header() -> Result<BytesMut, Error> {
     // Build proxy header
     let header = proxy_protocol::ProxyHeader::Version2 {
         command: ProxyCommand::Local,
@@ -131,20 +477,16 @@ pub fn local_proxy_header() -> Result<BytesMut, EncodeError> {
         addresses: ProxyAddresses::Unspec,
     };
 
-    proxy_protocol::encode(header)
+    Ok(proxy_protocol::encode(header)?)
 }
 
 /// Get the proxy header for the given inbound stream.
 ///
 /// This header may be sent over the outbound stream to signal client information.
-pub fn stream_proxy_header(inbound: &TcpStream) -> Result<BytesMut, EncodeError> {
+pub fn stream_proxy_header(inbound: &TcpStream) -> Result<BytesMut, Error> {
     // Get peer and local address
-    let peer = inbound
-        .peer_addr()
-        .expect("Peer address not known for TCP stream");
-    let local = inbound
-        .local_addr()
-        .expect("Local address not known for TCP stream");
+    let peer = inbound.peer_addr()?;
+    let local = inbound.local_addr()?;
 
     // Build proxy header
     let header = proxy_protocol::ProxyHeader::Version2 {
@@ -163,5 +505,5 @@ pub fn stream_proxy_header(inbound: &TcpStream) -> Result<BytesMut, EncodeError>
         },
     };
 
-    proxy_protocol::encode(header)
+    Ok(proxy_protocol::encode(header)?)
 }
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use chrono::{Datelike, Local, Timelike};
+use once_cell::sync::OnceCell;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::config::Config;
+use crate::server::{Server, State};
+
+/// Loaded scripts.
+static SCRIPTS: OnceCell<Scripts> = OnceCell::new();
+
+/// Script engine with loaded scripts.
+struct Scripts {
+    engine: Engine,
+    scripts: Vec<(String, AST)>,
+}
+
+impl Scripts {
+    /// Call function in all scripts defining it.
+    ///
+    /// The output of each script is passed as input to the next. Returns `None` if no script
+    /// changed the input.
+    fn chain(&self, func: &str, ctx: &Map, input: &str) -> Option<String> {
+        let mut output = None;
+        for (name, ast) in self.scripts.iter().filter(|(_, ast)| defines(ast, func)) {
+            let input = output.clone().unwrap_or_else(|| input.to_string());
+            match self
+                .engine
+                .call_fn::<String>(&mut Scope::new(), ast, func, (ctx.clone(), input))
+            {
+                Ok(result) => output = Some(result),
+                Err(err) => {
+                    warn!(target: "lazymc::script", "Script {} failed in {}: {}", name, func, err)
+                }
+            }
+        }
+        output
+    }
+}
+
+/// Check whether script defines function with two parameters.
+fn defines(ast: &AST, func: &str) -> bool {
+    ast.iter_functions()
+        .any(|f| f.name == func && f.params.len() == 2)
+}
+
+/// Load configured scripts.
+///
+/// Must be called once before invoking any script functions.
+pub fn load(config: &Config) {
+    SCRIPTS.get_or_init(|| {
+        let mut engine = Engine::new();
+        engine.set_max_operations(config.scripts.max_operations);
+
+        let scripts = config
+            .scripts
+            .files
+            .iter()
+            .filter_map(|path| match engine.compile_file(path.clone()) {
+                Ok(ast) => {
+                    info!(target: "lazymc::script", "Loaded script {}", path.display());
+                    Some((path.display().to_string(), ast))
+                }
+                Err(err) => {
+                    error!(target: "lazymc::script", "Failed to load script {}: {}", path.display(), err);
+                    None
+                }
+            })
+            .collect();
+
+        Scripts { engine, scripts }
+    });
+}
+
+/// Build context passed to scripts, with server state and time.
+fn context(state: State, players: u32) -> Map {
+    let now = Local::now();
+    let mut ctx = Map::new();
+    ctx.insert("state".into(), state.name().into());
+    ctx.insert("players".into(), (players as i64).into());
+    ctx.insert("timestamp".into(), now.timestamp().into());
+    ctx.insert("hour".into(), (now.hour() as i64).into());
+    ctx.insert("minute".into(), (now.minute() as i64).into());
+    ctx.insert(
+        "weekday".into(),
+        (now.weekday().number_from_monday() as i64).into(),
+    );
+    ctx
+}
+
+/// Compute MOTD through `motd(ctx, motd)` script functions.
+///
+/// Returns `None` if no script changed it.
+pub fn motd(state: State, players: u32, motd: &str) -> Option<String> {
+    SCRIPTS.get()?.chain("motd", &context(state, players), motd)
+}
+
+/// Compute kick message through `kick_message(ctx, message)` script functions.
+///
+/// Returns `None` if no script changed it.
+pub fn kick_message(state: State, players: u32, message: &str) -> Option<String> {
+    SCRIPTS
+        .get()?
+        .chain("kick_message", &context(state, players), message)
+}
+
+/// Script task.
+///
+/// Invokes `on_event(ctx, event)` script functions for server events.
+pub async fn service(server: Arc<Server>) {
+    let scripts = match SCRIPTS.get() {
+        Some(scripts) if !scripts.scripts.is_empty() => scripts,
+        _ => return,
+    };
+
+    let mut events = server.subscribe();
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        };
+
+        let ctx = context(server.state(), server.players_online().await);
+        let event: Dynamic = match rhai::serde::to_dynamic(event.to_json()) {
+            Ok(event) => event,
+            Err(_) => continue,
+        };
+
+        for (name, ast) in scripts
+            .scripts
+            .iter()
+            .filter(|(_, ast)| defines(ast, "on_event"))
+        {
+            if let Err(err) = scripts.engine.call_fn::<Dynamic>(
+                &mut Scope::new(),
+                ast,
+                "on_event",
+                (ctx.clone(), event.clone()),
+            ) {
+                warn!(target: "lazymc::script", "Script {} failed in on_event: {}", name, err);
+            }
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::process::Child;
+use tokio::time;
+
+use crate::config::{BlueGreen, Config, DriverKind, Server as ConfigServer};
+use crate::monitor;
+use crate::os;
+
+use super::driver::process::{self, InstanceCommand};
+use super::{Server, State};
+
+/// Interval to check whether the switched to instance is online at.
+const READY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Interval to check whether players left the old instance at.
+const DRAIN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Newly started blue/green instance, adopted by the process driver once the old instance quits.
+#[derive(Debug)]
+pub(super) struct Handover {
+    /// Process of the new instance.
+    pub child: Child,
+
+    /// Job Object managing the process tree of the new instance, if any.
+    #[cfg(windows)]
+    pub job: Option<os::windows::Job>,
+
+    /// cgroup the new instance runs in, if any.
+    #[cfg(target_os = "linux")]
+    pub cgroup: Option<PathBuf>,
+}
+
+impl Server {
+    /// Whether the secondary blue/green instance is the active instance.
+    pub fn secondary_active(&self) -> bool {
+        self.secondary.load(Ordering::Relaxed)
+    }
+
+    /// Address of the active server instance, to proxy players to.
+    pub fn address(&self, config: &Config) -> SocketAddr {
+        if self.secondary_active() {
+            config.blue_green.address
+        } else {
+            config.server.address
+        }
+    }
+
+    /// Address of the active server instance, to probe status on.
+    pub fn probe_address(&self, config: &Config) -> SocketAddr {
+        if self.secondary_active() {
+            config.blue_green.address
+        } else {
+            ConfigServer::probe_address(config)
+        }
+    }
+
+    /// RCON address of the active server instance.
+    #[cfg(feature = "rcon")]
+    pub fn rcon_address(&self, config: &Config) -> SocketAddr {
+        let mut addr = self.probe_address(config);
+        if self.secondary_active() {
+            addr.set_port(config.blue_green.rcon_port);
+        } else {
+            addr.set_port(config.rcon.port);
+        }
+        addr
+    }
+
+    /// Start command arguments of the given instance, with placeholders resolved.
+    pub(super) fn instance_command_args(config: &Config, secondary: bool) -> Option<Vec<String>> {
+        match &config.blue_green.command {
+            Some(command) if secondary => ConfigServer::resolve_command(config, command),
+            _ => ConfigServer::command_args(config),
+        }
+    }
+
+    /// Server directory of the given instance.
+    pub(super) fn instance_directory(config: &Config, secondary: bool) -> Option<PathBuf> {
+        if secondary {
+            BlueGreen::directory(config)
+        } else {
+            ConfigServer::server_directory(config)
+        }
+    }
+
+    /// Switch to the other blue/green server instance.
+    ///
+    /// Starts the other instance, routes new players to it once it is online, waits for players to
+    /// leave the old instance and stops it. The process driver adopts the new instance once the
+    /// old one quits.
+    pub async fn switch(config: Arc<Config>, server: Arc<Server>) -> Result<(), String> {
+        if !config.blue_green.enabled {
+            return Err("blue/green switching is not enabled (blue_green.enabled)".into());
+        }
+        if config.driver.kind != DriverKind::Process {
+            return Err("blue/green switching requires the process driver".into());
+        }
+        if config.server.socket.is_some() {
+            return Err("blue/green switching is not supported with 'server.socket'".into());
+        }
+        if BlueGreen::directory(&config).is_none() {
+            return Err("blue/green switching requires 'blue_green.directory'".into());
+        }
+        if server.state() != State::Started {
+            return Err("server must be started to switch".into());
+        }
+        let old_pid = match *server.pid.lock().await {
+            Some(pid) => pid,
+            None => return Err("server process ID unknown".into()),
+        };
+
+        // Only log in dry run
+        if config.dry_run {
+            info!(target: "lazymc", "Dry run, would switch to other blue/green instance");
+            return Ok(());
+        }
+
+        // Claim handover slot, prevents concurrent switches
+        let secondary = !server.secondary_active();
+        let name = if secondary { "secondary" } else { "primary" };
+        let mut handover = server.handover.lock().await;
+        if handover.is_some() {
+            return Err("already switching".into());
+        }
+
+        // Start other instance, the same way the process driver does, in its own cgroup
+        let InstanceCommand {
+            mut cmd,
+            #[cfg(target_os = "linux")]
+            cgroup,
+        } = process::command(&config, secondary)
+            .map_err(|err| format!("failed to start {name} instance: {err}"))?;
+        info!(target: "lazymc", "Switching server, starting {} instance...", name);
+        let mut child = cmd
+            .spawn()
+            .map_err(|err| format!("failed to start {name} instance: {err}"))?;
+
+        // Manage process tree through Job Object, then resume the suspended process
+        #[cfg(windows)]
+        let job = {
+            let pid = child
+                .id()
+                .ok_or_else(|| format!("{name} instance quit immediately, PID unknown"))?;
+            let job = process::job(pid);
+            if !unsafe { os::windows::resume(pid) } {
+                unsafe { os::windows::force_kill(pid) };
+                return Err(format!("failed to resume suspended {name} instance"));
+            }
+            job
+        };
+
+        // Wait for other instance to come online
+        let addr = if secondary {
+            config.blue_green.address
+        } else {
+            ConfigServer::probe_address(&config)
+        };
+        let protocol = server.protocol(&config).await;
+        let ready = wait_ready(&config, &mut child, addr, protocol, name).await;
+        #[cfg(target_os = "linux")]
+        if let (Err(_), Some(path)) = (&ready, &cgroup) {
+            os::cgroup::remove(path);
+        }
+        ready?;
+
+        // Route new players to other instance, adopt it once the old instance quits
+        handover.replace(Handover {
+            child,
+            #[cfg(windows)]
+            job,
+            #[cfg(target_os = "linux")]
+            cgroup,
+        });
+        drop(handover);
+        server.secondary.store(secondary, Ordering::Relaxed);
+        info!(target: "lazymc", "Switched server to {} instance, waiting for players to leave old instance...", name);
+
+        // Wait for players to leave old instance, up to drain timeout
+        let old_addr = if secondary {
+            ConfigServer::probe_address(&config)
+        } else {
+            config.blue_green.address
+        };
+        let deadline = Instant::now() + Duration::from_secs(config.blue_green.drain_timeout as u64);
+        while Instant::now() < deadline {
+            match monitor::online_players(&config, old_addr, protocol).await {
+                Some(0) | None => break,
+                Some(players) => {
+                    trace!(target: "lazymc", "{} players left on old server instance", players)
+                }
+            }
+            time::sleep(DRAIN_INTERVAL).await;
+        }
+
+        info!(target: "lazymc", "Stopping old server instance...");
+
+        if !os::kill_gracefully(old_pid) {
+            warn!(target: "lazymc", "Failed to send stop signal to old server instance");
+        }
+        Ok(())
+    }
+}
+
+/// Wait for a newly started instance to come online, up to the ready timeout.
+///
+/// Kills the instance if it does not come online in time.
+async fn wait_ready(
+    config: &Config,
+    child: &mut Child,
+    addr: SocketAddr,
+    protocol: u32,
+    name: &str,
+) -> Result<(), String> {
+    let deadline = Instant::now() + Duration::from_secs(config.blue_green.ready_timeout as u64);
+    loop {
+        if monitor::is_online(config, addr, protocol).await {
+            return Ok(());
+        }
+        if let Ok(Some(status)) = child.try_wait() {
+            return Err(format!(
+                "{name} instance quit before coming online ({status})"
+            ));
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill().await;
+            return Err(format!(
+                "{name} instance did not come online within {}s, stopped it",
+                config.blue_green.ready_timeout
+            ));
+        }
+        time::sleep(READY_INTERVAL).await;
+    }
+}
@@ -0,0 +1,99 @@
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::process::Command;
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::server::Server;
+use crate::util;
+
+use super::ServerDriver;
+
+/// Driver starting an existing container through a container runtime.
+///
+/// Attaches to the container while it runs, so it stops when the container stops.
+pub struct Container;
+
+impl Container {
+    /// Invoke container runtime with the given subcommand on the configured container.
+    async fn invoke(config: &Config, subcommand: &str) -> bool {
+        let container = &config.driver.container;
+        if container.name.trim().is_empty() {
+            warn!(target: "lazymc", "Cannot {} container, no container configured", subcommand);
+            return false;
+        }
+
+        let args = [
+            container.runtime.clone(),
+            subcommand.into(),
+            container.name.clone(),
+        ];
+        let timeout = Duration::from_secs(config.server.command_timeout as u64);
+        match util::command::run(&args, None, timeout).await {
+            Ok(()) => true,
+            Err(err) => {
+                error!(target: "lazymc", "Failed to {} container, {}", subcommand, err);
+                false
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ServerDriver for Container {
+    /// Start container attached, and wait for it to stop.
+    async fn run(&self, config: &Arc<Config>, server: &Arc<Server>) -> Result<bool, Error> {
+        let container = &config.driver.container;
+        if container.name.trim().is_empty() {
+            error!(target: "lazymc", "Failed to start server, no container configured");
+            return Err(Error::Preflight(
+                "no container configured (driver.container.name)".into(),
+            ));
+        }
+
+        let mut child = Command::new(&container.runtime)
+            .args(["start", "--attach", &container.name])
+            .stdin(Stdio::null())
+            .spawn()
+            .map_err(|err| {
+                error!(target: "lazymc", "Failed to start server container through '{}'", container.runtime);
+                err
+            })?;
+
+        let crashed = match child.wait().await {
+            Ok(status) if status.success() => {
+                debug!(target: "lazymc", "Server container stopped successfully ({})", status);
+                false
+            }
+            Ok(status) => {
+                warn!(target: "lazymc", "Server container stopped with error code ({})", status);
+                server.crashed(status.code(), &status.to_string()).await
+            }
+            Err(err) => {
+                error!(target: "lazymc", "Failed to wait for server container to stop: {}", err);
+                error!(target: "lazymc", "Assuming server stopped, cleaning up...");
+                false
+            }
+        };
+        Ok(crashed)
+    }
+
+    async fn stop(&self, config: &Config, _server: &Server) -> bool {
+        Self::invoke(config, "stop").await
+    }
+
+    async fn kill(&self, config: &Config, _server: &Server) -> bool {
+        Self::invoke(config, "kill").await
+    }
+
+    async fn freeze(&self, config: &Config, _server: &Server) -> bool {
+        Self::invoke(config, "pause").await
+    }
+
+    async fn thaw(&self, config: &Config, _server: &Server) -> bool {
+        Self::invoke(config, "unpause").await
+    }
+}
@@ -0,0 +1,106 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tokio::time;
+
+use crate::config::{self, Config, DriverKind};
+use crate::error::Error;
+
+use super::Server;
+
+mod container;
+mod panel;
+pub(super) mod process;
+mod systemd;
+
+/// Server lifecycle driver.
+///
+/// Starts, stops, freezes and kills the server in some execution environment. Drivers only act on
+/// the server, state changes are handled by `Server`.
+#[async_trait]
+pub trait ServerDriver: Send + Sync {
+    /// Start the server and wait for it to quit.
+    ///
+    /// Returns whether the server crashed. Returns an error if it could not be started.
+    async fn run(&self, config: &Arc<Config>, server: &Arc<Server>) -> Result<bool, Error>;
+
+    /// Gracefully stop the server.
+    ///
+    /// Returns `true` if the server is now stopping.
+    async fn stop(&self, config: &Config, server: &Server) -> bool;
+
+    /// Force kill the server.
+    async fn kill(&self, config: &Config, server: &Server) -> bool;
+
+    /// Freeze the server.
+    ///
+    /// Returns `false` if not frozen, or if not supported.
+    async fn freeze(&self, _config: &Config, _server: &Server) -> bool {
+        false
+    }
+
+    /// Thaw a frozen server.
+    ///
+    /// Returns `false` if not thawed, or if not supported.
+    async fn thaw(&self, _config: &Config, _server: &Server) -> bool {
+        false
+    }
+}
+
+/// Get the configured server driver.
+pub fn get(config: &Config) -> &'static dyn ServerDriver {
+    match config.driver.kind {
+        DriverKind::Process => &process::Process,
+        DriverKind::Container => &container::Container,
+        DriverKind::Systemd => &systemd::Systemd,
+        DriverKind::Panel => &panel::Panel,
+    }
+}
+
+/// Poll whether the server is running at an interval in seconds, until it stopped.
+///
+/// Allows the server some time to report it is running after starting, up to the start timeout,
+/// or indefinitely if the start timeout is 0.
+/// Returns whether the server crashed, which is when it stopped while started. Stopping while
+/// still starting is reported as failed start.
+async fn wait_stopped<F, Fut>(
+    config: &Config,
+    server: &Server,
+    interval: u32,
+    mut running: F,
+) -> bool
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<bool, String>>,
+{
+    let started_at = Instant::now();
+    let start_timeout = match config::Server::start_timeout(config) {
+        0 => None,
+        timeout => Some(Duration::from_secs(timeout as u64)),
+    };
+    let mut interval = time::interval(Duration::from_secs(interval.max(1) as u64));
+    let mut seen_running = false;
+    loop {
+        interval.tick().await;
+
+        let timed_out = start_timeout.map_or(false, |timeout| started_at.elapsed() >= timeout);
+        match running().await {
+            Ok(true) => seen_running = true,
+            Ok(false) if seen_running || timed_out => break,
+            Ok(false) => {}
+            Err(err) => {
+                warn!(target: "lazymc", "Failed to poll whether server is running: {}", err);
+            }
+        }
+    }
+
+    let crashed = server.crashed(None, "stopped unexpectedly").await;
+    if crashed {
+        warn!(target: "lazymc", "Server stopped unexpectedly");
+    } else {
+        debug!(target: "lazymc", "Server stopped");
+    }
+    crashed
+}
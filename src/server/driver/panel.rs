@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::panel::{self, PowerState, Signal};
+use crate::server::Server;
+
+use super::ServerDriver;
+
+/// Driver starting the server through a panel API.
+///
+/// The server state is derived from the panel power state, and status polls by the monitor.
+pub struct Panel;
+
+#[async_trait]
+impl ServerDriver for Panel {
+    /// Start server through the panel, and wait for the panel to report it offline again.
+    async fn run(&self, config: &Arc<Config>, server: &Arc<Server>) -> Result<bool, Error> {
+        let panel = &config.driver.panel;
+        if let Err(err) = panel::power(panel, Signal::Start).await {
+            error!(target: "lazymc", "Failed to start server through panel");
+            return Err(Error::Panel(err.to_string()));
+        }
+
+        let crashed = super::wait_stopped(config, server, panel.poll_interval, || async move {
+            panel::state(panel)
+                .await
+                .map(|state| state != PowerState::Offline)
+                .map_err(|err| err.to_string())
+        })
+        .await;
+        Ok(crashed)
+    }
+
+    async fn stop(&self, config: &Config, _server: &Server) -> bool {
+        match panel::power(&config.driver.panel, Signal::Stop).await {
+            Ok(()) => true,
+            Err(err) => {
+                error!(target: "lazymc", "Failed to stop server through panel: {}", err);
+                false
+            }
+        }
+    }
+
+    async fn kill(&self, config: &Config, _server: &Server) -> bool {
+        match panel::power(&config.driver.panel, Signal::Kill).await {
+            Ok(()) => true,
+            Err(err) => {
+                error!(target: "lazymc", "Failed to kill server through panel: {}", err);
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,509 @@
+#[cfg(target_os = "linux")]
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::process::Command;
+use tokio::time;
+
+#[cfg(target_os = "linux")]
+use crate::config::FreezeMethod;
+use crate::config::{Config, Server as ConfigServer};
+use crate::error::Error;
+use crate::java;
+use crate::mc::eula;
+use crate::mc::server_properties;
+use crate::os;
+use crate::server::{Server, State};
+use crate::updater;
+use crate::util;
+
+use super::ServerDriver;
+
+/// Server cooldown after the process quit.
+/// Used to give it some more time to quit forgotten threads, such as for RCON.
+const SERVER_QUIT_COOLDOWN: Duration = Duration::from_millis(2500);
+
+/// Exit codes that are allowed.
+///
+/// - 143: https://github.com/timvisee/lazymc/issues/26#issuecomment-1435670029
+/// - 130: https://unix.stackexchange.com/q/386836/61092
+const ALLOWED_EXIT_CODES: [i32; 2] = [130, 143];
+
+/// Driver running the server command as child process.
+pub struct Process;
+
+#[async_trait]
+impl ServerDriver for Process {
+    /// Invoke server command, store PID and wait for it to quit.
+    async fn run(&self, config: &Arc<Config>, server: &Arc<Server>) -> Result<bool, Error> {
+        // Sync configured properties into server.properties file of primary instance
+        let secondary = server.secondary_active();
+        if !secondary {
+            server_properties::rewrite_config(config);
+        }
+
+        // Swap in downloaded server jar update
+        if config.updater.enabled {
+            updater::apply(config);
+        }
+
+        // Check Java runtime, don't start into a crash loop
+        if config.java.preflight {
+            if let Err(err) = java::preflight(config).await {
+                error!(target: "lazymc", "Not starting server, Java preflight check failed: {}", err);
+                return Err(Error::Preflight(format!(
+                    "Java preflight check failed: {err}"
+                )));
+            }
+        }
+
+        // Run pre-start command, don't start if it fails
+        if let Some(command) = &config.server.pre_start_command {
+            if !run_phase_command(config, "pre-start", command).await {
+                error!(target: "lazymc", "Not starting server, pre-start command failed");
+                return Err(Error::Preflight("pre-start command failed".into()));
+            }
+        }
+
+        // Configure command of active instance
+        let InstanceCommand {
+            mut cmd,
+            #[cfg(target_os = "linux")]
+            cgroup,
+        } = command(config, secondary)?;
+
+        // Spawn process
+        let mut child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                error!(target: "lazymc", "Failed to start server process through command");
+                return Err(err.into());
+            }
+        };
+
+        // Remember PID
+        let pid = match child.id() {
+            Some(pid) => pid,
+            None => {
+                error!(target: "lazymc", "Server process quit immediately, PID unknown");
+                return Err(Error::UnknownPid);
+            }
+        };
+        track(server, pid).await;
+        #[cfg(windows)]
+        if !unsafe { os::windows::resume(pid) } {
+            error!(target: "lazymc", "Failed to resume suspended server process");
+            unsafe { os::windows::force_kill(pid) };
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(path) = cgroup {
+            server.cgroup.lock().await.replace(path);
+        }
+
+        // Wait for process to exit, adopt new blue/green instance if switched
+        let status = loop {
+            let status = child.wait().await;
+            let next = match server.handover.lock().await.take() {
+                Some(next) => next,
+                None => break status,
+            };
+
+            debug!(target: "lazymc", "Old server instance quit, adopting new instance");
+            child = next.child;
+            *server.pid.lock().await = child.id();
+            #[cfg(windows)]
+            {
+                *server.job.lock().await = next.job;
+            }
+            #[cfg(target_os = "linux")]
+            {
+                let old = std::mem::replace(&mut *server.cgroup.lock().await, next.cgroup);
+                if let Some(old) = old {
+                    os::cgroup::remove(&old);
+                }
+            }
+
+            // Server is going to sleep while switching, stop new instance as well
+            if server.state() != State::Started {
+                debug!(target: "lazymc", "Server stopping while switching, stopping new instance");
+                if !child.id().map(os::kill_gracefully).unwrap_or(false) {
+                    warn!(target: "lazymc", "Failed to send stop signal to new server instance");
+                }
+            }
+        };
+
+        // Handle exit status
+        let crashed = match status {
+            Ok(status) if status.success() => {
+                debug!(target: "lazymc", "Server process stopped successfully ({})", status);
+                false
+            }
+            Ok(status)
+                if status
+                    .code()
+                    .map(|ref code| ALLOWED_EXIT_CODES.contains(code))
+                    .unwrap_or(false) =>
+            {
+                debug!(target: "lazymc", "Server process stopped successfully by SIGTERM ({})", status);
+                false
+            }
+            Ok(status) => {
+                warn!(target: "lazymc", "Server process stopped with error code ({})", status);
+                server.crashed(status.code(), &status.to_string()).await
+            }
+            Err(err) => {
+                error!(target: "lazymc", "Failed to wait for server process to quit: {}", err);
+                error!(target: "lazymc", "Assuming server quit, cleaning up...");
+                false
+            }
+        };
+
+        // Forget server PID, frozen state and resource usage
+        untrack(server).await;
+        server.frozen_since.write().await.take();
+        server.set_resources(None).await;
+
+        // Give server a little more time to quit forgotten threads
+        time::sleep(SERVER_QUIT_COOLDOWN).await;
+
+        // Run post-stop command
+        if let Some(command) = &config.server.post_stop_command {
+            run_phase_command(config, "post-stop", command).await;
+        }
+
+        Ok(crashed)
+    }
+
+    /// Stop server by sending SIGTERM signal, or Ctrl+Break console event on Windows.
+    ///
+    /// Only available on Unix and Windows.
+    #[allow(unused_variables)]
+    async fn stop(&self, config: &Config, server: &Server) -> bool {
+        #[cfg(any(unix, windows))]
+        {
+            // Grab PID
+            let pid = match *server.pid.lock().await {
+                Some(pid) => pid,
+                None => {
+                    debug!(target: "lazymc", "Could not send stop signal to server process, PID unknown");
+                    return false;
+                }
+            };
+
+            if !os::kill_gracefully(pid) {
+                error!(target: "lazymc", "Failed to send stop signal to server process");
+                return false;
+            }
+            return true;
+        }
+
+        #[allow(unreachable_code)]
+        false
+    }
+
+    /// Force kill server process.
+    ///
+    /// This requires the server PID to be known.
+    #[allow(unused_variables)]
+    async fn kill(&self, config: &Config, server: &Server) -> bool {
+        // Kill whole process tree through Job Object
+        #[cfg(windows)]
+        if let Some(job) = &*server.job.lock().await {
+            if job.terminate() {
+                return true;
+            }
+        }
+
+        // Kill whole process tree through cgroup
+        #[cfg(target_os = "linux")]
+        if let Some(cgroup) = &*server.cgroup.lock().await {
+            if os::cgroup::kill(cgroup) {
+                return true;
+            }
+        }
+
+        if let Some(pid) = *server.pid.lock().await {
+            return os::force_kill(pid);
+        }
+        false
+    }
+
+    /// Freeze server by sending SIGSTOP signal, or suspending it on Windows.
+    ///
+    /// Only available on Unix and Windows.
+    #[allow(unused_variables)]
+    async fn freeze(&self, config: &Config, server: &Server) -> bool {
+        #[cfg(any(unix, windows))]
+        {
+            // Grab PID
+            let pid = match *server.pid.lock().await {
+                Some(pid) => pid,
+                None => {
+                    debug!(target: "lazymc", "Could not send freeze signal to server process, PID unknown");
+                    return false;
+                }
+            };
+
+            // Freeze whole process tree through cgroup freezer, fall back to signal
+            #[allow(unused_mut)]
+            let mut frozen = false;
+            #[cfg(target_os = "linux")]
+            if config.server.freeze_method != FreezeMethod::Signal {
+                match &*server.cgroup.lock().await {
+                    Some(cgroup) => frozen = os::cgroup::freeze(cgroup, true),
+                    None if config.server.freeze_method == FreezeMethod::Cgroup => {
+                        warn!(target: "lazymc", "Cannot freeze server through cgroup, server is not in cgroup, using signal instead");
+                    }
+                    None => {}
+                }
+            }
+
+            if !frozen && !os::freeze(pid) {
+                error!(target: "lazymc", "Failed to send freeze signal to server process.");
+            }
+            return true;
+        }
+
+        #[allow(unreachable_code)]
+        false
+    }
+
+    /// Thaw server by sending SIGCONT signal, or resuming it on Windows.
+    ///
+    /// Only available on Unix and Windows.
+    #[allow(unused_variables)]
+    async fn thaw(&self, config: &Config, server: &Server) -> bool {
+        #[cfg(any(unix, windows))]
+        {
+            // Grab PID
+            let pid = match *server.pid.lock().await {
+                Some(pid) => pid,
+                None => {
+                    debug!(target: "lazymc", "Could not send unfreeze signal to server process, PID unknown");
+                    return false;
+                }
+            };
+
+            // Thaw cgroup, always send signal as well in case we fell back to it when freezing
+            #[cfg(target_os = "linux")]
+            if config.server.freeze_method != FreezeMethod::Signal {
+                if let Some(cgroup) = &*server.cgroup.lock().await {
+                    os::cgroup::freeze(cgroup, false);
+                }
+            }
+
+            if !os::unfreeze(pid) {
+                error!(target: "lazymc", "Failed to send unfreeze signal to server process.");
+            }
+            return true;
+        }
+
+        #[allow(unreachable_code)]
+        false
+    }
+}
+
+/// Server command of an instance, ready to spawn.
+pub(in crate::server) struct InstanceCommand {
+    /// Command to spawn.
+    pub cmd: Command,
+
+    /// cgroup the process joins, if any.
+    #[cfg(target_os = "linux")]
+    pub cgroup: Option<PathBuf>,
+}
+
+/// Build command to start the given server instance with.
+///
+/// Drops privileges, joins the cgroup, applies process scheduling and accepts the EULA as
+/// configured. On Windows the process starts suspended, resume it once it is assigned to a Job
+/// Object.
+pub(in crate::server) fn command(
+    config: &Config,
+    secondary: bool,
+) -> Result<InstanceCommand, Error> {
+    let args = match Server::instance_command_args(config, secondary) {
+        Some(args) => args,
+        None => {
+            error!(target: "lazymc", "Failed to start server, invalid server command");
+            return Err(Error::InvalidCommand(config.server.command.clone()));
+        }
+    };
+    let mut cmd = Command::new(&args[0]);
+    cmd.args(args.iter().skip(1));
+    cmd.kill_on_drop(true);
+
+    // Resolve configured user and group to drop privileges to
+    #[cfg(unix)]
+    let credentials = if config.server.user.is_some() || config.server.group.is_some() {
+        match os::resolve_user(
+            config.server.user.as_deref(),
+            config.server.group.as_deref(),
+        ) {
+            Ok(ids) => Some(ids),
+            Err(err) => {
+                error!(target: "lazymc", "Not starting server, cannot run it as configured user: {}", err);
+                return Err(Error::Preflight(err));
+            }
+        }
+    } else {
+        None
+    };
+    #[cfg(windows)]
+    if config.server.user.is_some() || config.server.group.is_some() {
+        warn!(target: "lazymc", "Running server as different user is not supported on Windows, ignoring");
+    }
+
+    // Set up cgroup for the server process to move into
+    #[cfg(target_os = "linux")]
+    let cgroup = if config.cgroup.enabled || config.server.freeze_method == FreezeMethod::Cgroup {
+        match os::cgroup::setup(&config.cgroup, secondary)
+            .and_then(|path| os::cgroup::procs_file(&path).map(|procs| (path, procs)))
+        {
+            Ok(cgroup) => Some(cgroup),
+            Err(err) => {
+                warn!(target: "lazymc", "Failed to set up cgroup for server process: {}", err);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // Start new process group, join cgroup, apply process scheduling and drop privileges in
+    // child before executing, so all server processes and threads inherit it
+    #[cfg(unix)]
+    {
+        let process = &config.process;
+        let nice = process.nice;
+        #[cfg(target_os = "linux")]
+        let (procs_file, ionice, affinity) = (
+            cgroup.as_ref().map(|(_, procs)| procs.clone()),
+            process
+                .ionice_class
+                .map(|class| (class, process.ionice_level.unwrap_or(4))),
+            process.cpu_affinity.clone(),
+        );
+
+        // Safe because only async-signal-safe syscalls are invoked in the child
+        unsafe {
+            cmd.pre_exec(move || {
+                os::new_process_group()?;
+                if let Some(nice) = nice {
+                    os::set_nice(nice)?;
+                }
+                #[cfg(target_os = "linux")]
+                {
+                    if let Some(procs_file) = &procs_file {
+                        os::cgroup::join(procs_file)?;
+                    }
+                    if let Some((class, level)) = ionice {
+                        os::linux::set_ionice(class, level)?;
+                    }
+                    if !affinity.is_empty() {
+                        os::linux::set_affinity(&affinity)?;
+                    }
+                }
+                if let Some((uid, gid)) = credentials {
+                    os::drop_privileges(uid, gid)?;
+                }
+                Ok(())
+            });
+        }
+    }
+    // Start in own process group, to send console events to just the server
+    // Start suspended on Windows, resumed once it is assigned to the Job Object
+    #[cfg(windows)]
+    cmd.creation_flags(
+        winapi::um::winbase::CREATE_NEW_PROCESS_GROUP | winapi::um::winbase::CREATE_SUSPENDED,
+    );
+    #[cfg(windows)]
+    if config.process.nice.is_some() {
+        warn!(target: "lazymc", "Setting server process nice level is not supported on Windows, ignoring");
+    }
+
+    // Set working directory, accept EULA
+    if let Some(ref dir) = Server::instance_directory(config, secondary) {
+        cmd.current_dir(dir);
+
+        if config.server.accept_eula {
+            eula::accept_dir(dir);
+        }
+    }
+
+    Ok(InstanceCommand {
+        cmd,
+        #[cfg(target_os = "linux")]
+        cgroup: cgroup.map(|(path, _)| path),
+    })
+}
+
+/// Remember server process PID, manage its process tree.
+async fn track(server: &Server, pid: u32) {
+    server.pid.lock().await.replace(pid);
+
+    // Manage server process tree through Job Object
+    #[cfg(windows)]
+    if let Some(job) = job(pid) {
+        server.job.lock().await.replace(job);
+    }
+}
+
+/// Create Job Object to manage the process tree of the given server process.
+#[cfg(windows)]
+pub(in crate::server) fn job(pid: u32) -> Option<os::windows::Job> {
+    let job = os::windows::Job::new()?;
+
+    // Safe because PID is of the process we just spawned
+    if unsafe { job.assign(pid) } {
+        Some(job)
+    } else {
+        warn!(target: "lazymc", "Failed to assign server process to Job Object");
+        None
+    }
+}
+
+/// Forget server process PID, clean up its process tree.
+async fn untrack(server: &Server) {
+    server.pid.lock().await.take();
+
+    // Close Job Object, kills processes left behind
+    #[cfg(windows)]
+    server.job.lock().await.take();
+
+    // Clean up cgroup
+    #[cfg(target_os = "linux")]
+    if let Some(cgroup) = server.cgroup.lock().await.take() {
+        os::cgroup::remove(&cgroup);
+    }
+}
+
+/// Run pre-start or post-stop command in server directory, wait for it to complete.
+///
+/// Returns `true` if it completed successfully, or if the command is empty.
+async fn run_phase_command(config: &Config, phase: &str, command: &str) -> bool {
+    if command.trim().is_empty() {
+        return true;
+    }
+
+    let args = match ConfigServer::resolve_command(config, command) {
+        Some(args) => args,
+        None => {
+            error!(target: "lazymc", "Invalid {} command: {}", phase, command);
+            return false;
+        }
+    };
+
+    info!(target: "lazymc", "Running {} command...", phase);
+    let dir = ConfigServer::server_directory(config);
+    let timeout = Duration::from_secs(config.server.command_timeout as u64);
+    match util::command::run(&args, dir.as_deref(), timeout).await {
+        Ok(()) => true,
+        Err(err) => {
+            error!(target: "lazymc", "Failed to run {} command, {}", phase, err);
+            false
+        }
+    }
+}
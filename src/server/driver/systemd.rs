@@ -0,0 +1,100 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::process::Command;
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::server::Server;
+use crate::util;
+
+use super::ServerDriver;
+
+/// Driver starting a systemd unit.
+///
+/// Polls the unit state while it runs.
+pub struct Systemd;
+
+impl Systemd {
+    /// Build systemctl command with the given arguments on the configured unit.
+    fn args(config: &Config, args: &[&str]) -> Vec<String> {
+        let systemd = &config.driver.systemd;
+        let mut cmd = vec!["systemctl".to_string()];
+        if systemd.user {
+            cmd.push("--user".into());
+        }
+        cmd.extend(args.iter().map(|arg| arg.to_string()));
+        cmd.push(systemd.unit.clone());
+        cmd
+    }
+
+    /// Invoke systemctl with the given arguments on the configured unit.
+    async fn invoke(config: &Config, args: &[&str]) -> Result<(), String> {
+        if config.driver.systemd.unit.trim().is_empty() {
+            return Err("no unit configured (driver.systemd.unit)".into());
+        }
+
+        let args = Self::args(config, args);
+        let timeout = Duration::from_secs(config.server.command_timeout as u64);
+        util::command::run(&args, None, timeout).await
+    }
+
+    /// Invoke systemctl with the given arguments, log errors.
+    async fn invoke_logged(config: &Config, action: &str, args: &[&str]) -> bool {
+        match Self::invoke(config, args).await {
+            Ok(()) => true,
+            Err(err) => {
+                error!(target: "lazymc", "Failed to {} systemd unit, {}", action, err);
+                false
+            }
+        }
+    }
+
+    /// Check whether the configured unit is active, or activating.
+    async fn is_running(config: &Config) -> Result<bool, String> {
+        let args = Self::args(config, &["is-active"]);
+        let output = Command::new(&args[0])
+            .args(&args[1..])
+            .output()
+            .await
+            .map_err(|err| format!("failed to invoke systemctl: {err}"))?;
+        let state = String::from_utf8_lossy(&output.stdout);
+        Ok(matches!(
+            state.trim(),
+            "active" | "activating" | "reloading" | "deactivating"
+        ))
+    }
+}
+
+#[async_trait]
+impl ServerDriver for Systemd {
+    /// Start unit, and wait for it to become inactive.
+    async fn run(&self, config: &Arc<Config>, server: &Arc<Server>) -> Result<bool, Error> {
+        if let Err(err) = Self::invoke(config, &["start", "--no-block"]).await {
+            error!(target: "lazymc", "Failed to start server systemd unit");
+            return Err(Error::Preflight(format!("failed to start unit: {err}")));
+        }
+
+        let interval = config.driver.systemd.poll_interval;
+        let crashed =
+            super::wait_stopped(config, server, interval, || Self::is_running(config)).await;
+        Ok(crashed)
+    }
+
+    async fn stop(&self, config: &Config, _server: &Server) -> bool {
+        Self::invoke_logged(config, "stop", &["stop", "--no-block"]).await
+    }
+
+    async fn kill(&self, config: &Config, _server: &Server) -> bool {
+        Self::invoke_logged(config, "kill", &["kill", "--signal=SIGKILL"]).await
+    }
+
+    async fn freeze(&self, config: &Config, _server: &Server) -> bool {
+        Self::invoke_logged(config, "freeze", &["freeze"]).await
+    }
+
+    async fn thaw(&self, config: &Config, _server: &Server) -> bool {
+        Self::invoke_logged(config, "thaw", &["thaw"]).await
+    }
+}
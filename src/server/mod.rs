@@ -0,0 +1,1892 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use arc_swap::{ArcSwap, ArcSwapOption};
+use chrono::{DateTime, Local};
+use minecraft_protocol::data::server_status::ServerStatus;
+#[cfg(feature = "rcon")]
+use tokio::sync::Semaphore;
+use tokio::sync::{broadcast, watch};
+use tokio::sync::{Mutex, RwLock};
+use tokio::time;
+use uuid::Uuid;
+
+use crate::backup::BackupStatus;
+use crate::config::{
+    Config, Server as ConfigServer, Sleep as ConfigSleep, SleepAction, SleepActionKind, Time,
+};
+use crate::error::Error;
+use crate::event::{Event, EVENT_BUFFER};
+use crate::honeypot::Honeypot;
+use crate::login::LoginThrottle;
+use crate::mc::ban::{BannedIp, BannedIps};
+use crate::mc::brand::Brand;
+use crate::mc::profile::UuidResolver;
+use crate::mc::whitelist::Whitelist;
+use crate::metrics;
+use crate::monitor;
+#[cfg(windows)]
+use crate::os;
+use crate::proto::packets::play::join_game::JoinGameData;
+use crate::types;
+use crate::util;
+
+mod blue_green;
+pub mod driver;
+
+/// RCON cooldown. Required period between RCON invocations.
+///
+/// The Minecraft RCON implementation is very broken and brittle, this is used in the hopes to
+/// improve reliability.
+#[cfg(feature = "rcon")]
+const RCON_COOLDOWN: Duration = Duration::from_secs(15);
+
+/// Number of recent start and stop durations to learn timeouts from.
+const DURATION_HISTORY: usize = 20;
+
+/// Number of recent state transitions to remember for diagnostics.
+const TRANSITION_HISTORY: usize = 16;
+
+/// Maximum number of known player IPs to remember, least recently seen are forgotten first.
+const KNOWN_IPS_MAX: usize = 4096;
+
+/// Cached status response data and when it was fetched, `None` if fetching failed.
+type CachedStatus = (Instant, Option<Arc<Vec<u8>>>);
+
+/// Shared server state.
+#[derive(Debug)]
+pub struct Server {
+    /// Server state.
+    ///
+    /// Watch sender holding the current state, broadcasts state changes to receivers.
+    state: watch::Sender<State>,
+
+    /// Event sender, broadcast server events to subscribers.
+    events: broadcast::Sender<Event>,
+
+    /// Server process PID.
+    ///
+    /// Set if a server process is running.
+    pid: Mutex<Option<u32>>,
+
+    /// Last known server status.
+    ///
+    /// Will remain set once known, not cleared if server goes offline.
+    status: ArcSwapOption<ServerStatus>,
+
+    /// Last known secure chat flags from server status.
+    secure_chat: ArcSwap<SecureChat>,
+
+    /// Status response data last fetched from the started server.
+    ///
+    /// Shared by coalesced status requests.
+    coalesced_status: Mutex<Option<CachedStatus>>,
+
+    /// Server brand detected from status.
+    brand: RwLock<Option<Brand>>,
+
+    /// IPs of players that joined through lazymc, with the time they were last seen.
+    known_ips: RwLock<HashMap<IpAddr, Instant>>,
+
+    /// Suspicious request tracker.
+    honeypot: Honeypot,
+
+    /// Failed login throttle.
+    login_throttle: LoginThrottle,
+
+    /// Player UUID resolver.
+    uuids: UuidResolver,
+
+    /// Names of the players last seen online.
+    last_players: RwLock<Vec<String>>,
+
+    /// Recent start durations in seconds, oldest first.
+    start_durations: RwLock<VecDeque<u64>>,
+
+    /// Recent stop durations in seconds, oldest first.
+    stop_durations: RwLock<VecDeque<u64>>,
+
+    /// Players that tried to join while starting, in order.
+    join_queue: RwLock<Vec<String>>,
+
+    /// Last active time.
+    ///
+    /// The last time there was activity on the server. Also set at the moment the server comes
+    /// online.
+    last_active: RwLock<Option<Instant>>,
+
+    /// Time the server process was frozen at, if it is frozen.
+    frozen_since: RwLock<Option<Instant>>,
+
+    /// Force server to stay online until.
+    keep_online_until: RwLock<Option<Instant>>,
+
+    /// Keep server awake until, set externally.
+    keep_awake_until: RwLock<Option<Instant>>,
+
+    /// Time to force kill the server process at.
+    ///
+    /// Used as starting/stopping timeout.
+    kill_at: RwLock<Option<Instant>>,
+
+    /// List of banned IPs.
+    banned_ips: RwLock<BannedIps>,
+
+    /// Whitelist if enabled.
+    whitelist: RwLock<Option<Whitelist>>,
+
+    /// Last sampled resource usage of the server process.
+    resources: RwLock<Option<ResourceUsage>>,
+
+    /// Whether to start the server again once it has stopped.
+    restart: AtomicBool,
+
+    /// Whether a wake request arrived while stopping, to start again once stopped.
+    wake_pending: AtomicBool,
+
+    /// Whether the server failed to start the last time it was started.
+    failed_start: AtomicBool,
+
+    /// Whether maintenance mode is enabled.
+    maintenance: AtomicBool,
+
+    /// Time since the server has been unresponsive while started.
+    ///
+    /// Used by the hang watchdog.
+    unresponsive_since: RwLock<Option<Instant>>,
+
+    /// Status of the last backup.
+    backup_status: RwLock<Option<BackupStatus>>,
+
+    /// Time of the last state change.
+    state_since: RwLock<Option<Instant>>,
+
+    /// Last server error.
+    last_error: RwLock<Option<String>>,
+
+    /// Recent state transitions, oldest first.
+    transitions: RwLock<VecDeque<(DateTime<Local>, State, State)>>,
+
+    /// Whether the secondary blue/green instance is the active instance.
+    secondary: AtomicBool,
+
+    /// Newly started blue/green instance to adopt once the old instance quits.
+    handover: Mutex<Option<blue_green::Handover>>,
+
+    /// Job Object the server process runs in, if any.
+    #[cfg(windows)]
+    job: Mutex<Option<os::windows::Job>>,
+
+    /// cgroup the server process runs in, if any.
+    #[cfg(target_os = "linux")]
+    cgroup: Mutex<Option<std::path::PathBuf>>,
+
+    /// Lock for exclusive RCON operations.
+    #[cfg(feature = "rcon")]
+    rcon_lock: Semaphore,
+
+    /// Last time server was stopped over RCON.
+    #[cfg(feature = "rcon")]
+    rcon_last_stop: Mutex<Option<Instant>>,
+
+    /// Probed join game data.
+    pub probed_join_game: RwLock<Option<JoinGameData>>,
+
+    /// Forge payload.
+    ///
+    /// Sent to clients when they connect to lobby. Recorded from server by probe.
+    pub forge_payload: RwLock<Vec<Vec<u8>>>,
+}
+
+impl Server {
+    /// Get current state.
+    pub fn state(&self) -> State {
+        *self.state.borrow()
+    }
+
+    /// Get state receiver to subscribe on server state changes.
+    pub fn state_receiver(&self) -> watch::Receiver<State> {
+        self.state.subscribe()
+    }
+
+    /// Wait until the server reaches a state matching the predicate, return it.
+    ///
+    /// Returns immediately if the current state matches.
+    pub async fn wait_for_state(&self, predicate: impl Fn(State) -> bool) -> State {
+        let mut receiver = self.state_receiver();
+        loop {
+            let state = *receiver.borrow_and_update();
+            if predicate(state) {
+                return state;
+            }
+
+            // Sender lives as long as self, this never fails
+            let _ = receiver.changed().await;
+        }
+    }
+
+    /// Subscribe to server events.
+    ///
+    /// Subscribers that lag behind miss old events.
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    /// Publish event to all subscribers.
+    fn publish(&self, event: Event) {
+        let _ = self.events.send(event);
+    }
+
+    /// Set a new state.
+    ///
+    /// This updates various other internal things depending on how the state changes.
+    ///
+    /// Returns false if the state didn't change, in which case nothing happens.
+    async fn update_state(&self, state: State, config: &Config) -> bool {
+        self.update_state_from(None, state, config).await
+    }
+
+    /// Set new state, from a current state.
+    ///
+    /// This updates various other internal things depending on how the state changes.
+    ///
+    /// Returns false if current state didn't match `from`, if nothing changed, or if the transition
+    /// is not allowed, see `State::can_transition_to`.
+    async fn update_state_from(&self, from: Option<State>, new: State, config: &Config) -> bool {
+        // Atomically change state and broadcast, return if from doesn't match
+        let mut old = None;
+        self.state.send_if_modified(|state| {
+            if from.map(|from| from != *state).unwrap_or(false) {
+                return false;
+            }
+            if !state.can_transition_to(new) {
+                if *state != new {
+                    debug!(target: "lazymc", "Ignoring invalid server state change from {:?} to {:?}", state, new);
+                }
+                return false;
+            }
+            old = Some(*state);
+            *state = new;
+            true
+        });
+        let old = match old {
+            Some(old) => old,
+            None => return false,
+        };
+
+        trace!("Change server state from {:?} to {:?}", old, new);
+
+        self.publish(Event::State {
+            from: old.name(),
+            to: new.name(),
+        });
+
+        // Learn start and stop durations, timed out attempts took at least as long
+        if let Some(since) = *self.state_since.read().await {
+            let duration = since.elapsed().as_secs();
+            let timed_out = self
+                .kill_at
+                .read()
+                .await
+                .map(|kill_at| Instant::now() >= kill_at)
+                .unwrap_or(false);
+            match (old, new) {
+                (State::Starting, State::Started) => {
+                    push_duration(&mut *self.start_durations.write().await, duration)
+                }
+                (State::Starting, _) if timed_out => {
+                    push_duration(&mut *self.start_durations.write().await, duration)
+                }
+                (State::Stopping, State::Stopped) => {
+                    push_duration(&mut *self.stop_durations.write().await, duration)
+                }
+                _ => {}
+            }
+        }
+
+        // Players waiting for start are done waiting
+        if new != State::Starting {
+            self.join_queue.write().await.clear();
+        }
+
+        // Reset unresponsive state, remember state change time
+        self.mark_responsive().await;
+        self.state_since.write().await.replace(Instant::now());
+        {
+            let mut transitions = self.transitions.write().await;
+            if transitions.len() >= TRANSITION_HISTORY {
+                transitions.pop_front();
+            }
+            transitions.push_back((Local::now(), old, new));
+        }
+
+        // Update kill at time for starting/stopping state
+        let timeout = match new {
+            State::Starting => self.start_timeout(config).await,
+            State::Stopping => self.stop_timeout(config).await,
+            _ => 0,
+        };
+        *self.kill_at.write().await =
+            Some(Instant::now() + Duration::from_secs(timeout as u64)).filter(|_| timeout > 0);
+
+        // Online/offline messages
+        match new {
+            State::Started => info!(target: "lazymc::monitor", "Server is now online"),
+            State::Stopped => info!(target: "lazymc::monitor", "Server is now sleeping"),
+            _ => {}
+        }
+
+        // If Starting -> Started, update active time and keep it online for configured time
+        if old == State::Starting && new == State::Started {
+            self.update_last_active().await;
+            self.keep_online_for(Some(config.time.min_online_time))
+                .await;
+        }
+
+        true
+    }
+
+    /// Update status as obtained from the server.
+    ///
+    /// This updates various other internal things depending on the current state and the given
+    /// status.
+    pub async fn update_status(&self, config: &Config, status: Option<ServerStatus>) {
+        // Update state based on curren
+        match (self.state(), &status) {
+            (State::Stopped | State::Starting, Some(_)) => {
+                self.update_state(State::Started, config).await;
+            }
+            (State::Started, None) => {
+                self.update_state(State::Stopped, config).await;
+            }
+            _ => {}
+        }
+
+        // Update last status if known
+        if let Some(status) = status {
+            // Server is responsive
+            self.mark_responsive().await;
+
+            // Update last active time if there are online players
+            if status.players.online > 0 {
+                self.update_last_active().await;
+            }
+
+            // Report detected protocol version if it changed, overrides configured version
+            let last = self.status.load_full();
+            let protocol = status.version.protocol;
+            if last.as_ref().map(|last| last.version.protocol) != Some(protocol)
+                && protocol != config.public.protocol
+            {
+                info!(
+                    target: "lazymc::monitor",
+                    "Detected server version {} (protocol {}), using it instead of configured 'public.protocol' ({})",
+                    status.version.name,
+                    protocol,
+                    config.public.protocol,
+                );
+            }
+
+            // Remember players last seen online
+            if !status.players.sample.is_empty() {
+                *self.last_players.write().await = status
+                    .players
+                    .sample
+                    .iter()
+                    .map(|player| player.name.clone())
+                    .collect();
+            }
+
+            // Keep online for minimum time again when players join, if enabled
+            let joined = last
+                .as_ref()
+                .map_or(false, |last| status.players.online > last.players.online);
+            if joined && config.time.keep_online_on_join {
+                trace!(target: "lazymc", "Player joined, keeping server online for minimum time");
+                self.keep_online_for(Some(config.time.min_online_time))
+                    .await;
+            }
+
+            // Publish player count changes
+            if last.as_ref().map(|last| last.players.online) != Some(status.players.online) {
+                self.publish(Event::Players {
+                    online: status.players.online,
+                    max: status.players.max,
+                });
+            }
+            self.status.store(Some(Arc::new(status)));
+        }
+    }
+
+    /// Try to start the server.
+    ///
+    /// Does nothing if currently not in stopped state.
+    pub async fn start(config: Arc<Config>, server: Arc<Server>, username: Option<String>) -> bool {
+        // Never start on a partially restored world, also not for admins
+        #[cfg(unix)]
+        if crate::backup::restore::restoring() {
+            info!(target: "lazymc", "Not starting server, restoring backup");
+            return false;
+        }
+
+        // Must set state from stopped to starting, start again once stopped if stopping
+        if !server
+            .update_state_from(Some(State::Stopped), State::Starting, &config)
+            .await
+        {
+            if server.state() == State::Stopping
+                && !server.wake_pending.swap(true, Ordering::Relaxed)
+            {
+                info!(target: "lazymc", "Server is stopping, starting again once stopped");
+                Self::spawn_pending_wake(config, server, username);
+            }
+            return false;
+        }
+        server.failed_start.store(false, Ordering::Relaxed);
+        server.publish(Event::Wake {
+            username: username.clone(),
+        });
+
+        // Log starting message
+        match username {
+            Some(username) => info!(target: "lazymc", "Starting server for '{}'...", username),
+            None => info!(target: "lazymc", "Starting server..."),
+        }
+
+        // Only log in dry run, the monitor picks up a server that is already running
+        if config.dry_run {
+            info!(target: "lazymc", "Dry run, would start server: {}", config.server.command);
+            server.update_state(State::Stopped, &config).await;
+            return true;
+        }
+
+        // Unfreeze server if it is frozen
+        if config.server.freeze_process && server.thaw(&config).await {
+            return true;
+        }
+
+        // Spawn server in new task
+        Self::spawn_server_task(config, server);
+        true
+    }
+
+    /// Spawn task to start the server once it has stopped, for a wake request while stopping.
+    ///
+    /// This should not be called directly.
+    fn spawn_pending_wake(config: Arc<Config>, server: Arc<Server>, username: Option<String>) {
+        tokio::spawn(async move {
+            let state = server
+                .wait_for_state(|state| state != State::Stopping)
+                .await;
+            server.wake_pending.store(false, Ordering::Relaxed);
+
+            // Don't wake when stopped for maintenance, such as restoring a backup
+            if state == State::Stopped && !server.maintenance() {
+                info!(target: "lazymc", "Server stopped, starting again for pending wake...");
+                Server::start(config, server, username).await;
+            }
+        });
+    }
+
+    /// Spawn the server task, running the server through its driver until it stops.
+    ///
+    /// This should not be called directly.
+    fn spawn_server_task(config: Arc<Config>, server: Arc<Server>) {
+        tokio::spawn(async move {
+            let crashed = match driver::get(&config).run(&config, &server).await {
+                Ok(crashed) => crashed,
+                Err(err) => {
+                    server.start_failed(None, err.to_string()).await;
+                    server.update_state(State::Stopped, &config).await;
+                    return;
+                }
+            };
+
+            // Server quit while starting without a reported cause, it failed to start
+            if server.state() == State::Starting && !server.failed_to_start() {
+                server.crashed(None, "server quit").await;
+            }
+
+            // Set server state to stopped
+            server.update_state(State::Stopped, &config).await;
+
+            // Restart if requested
+            if server.restart.swap(false, Ordering::Relaxed) {
+                info!(target: "lazymc", "Server stopped, restarting...");
+                Server::start(config, server, None).await;
+                return;
+            }
+
+            // Restart on crash
+            if crashed && config.server.wake_on_crash {
+                warn!(target: "lazymc", "Server crashed, restarting...");
+                Server::start(config, server, None).await;
+            }
+        });
+    }
+
+    /// Report that the server quit unexpectedly, with its exit code if known.
+    ///
+    /// Reported as failed start if it was still starting, or as crash if it was started. Returns
+    /// whether it crashed.
+    async fn crashed(&self, code: Option<i32>, detail: &str) -> bool {
+        match self.state() {
+            State::Starting => {
+                self.start_failed(code, format!("server failed to start ({detail})"))
+                    .await;
+                false
+            }
+            State::Started => {
+                self.publish(Event::Crash { code });
+                self.set_last_error(format!("server crashed ({detail})"))
+                    .await;
+                true
+            }
+            State::Stopped | State::Stopping => false,
+        }
+    }
+
+    /// Report that the server failed to start, with its exit code if known.
+    async fn start_failed(&self, code: Option<i32>, err: String) {
+        error!(target: "lazymc", "Server failed to start: {}", err);
+        self.failed_start.store(true, Ordering::Relaxed);
+        self.publish(Event::StartFailed { code });
+        self.set_last_error(err).await;
+    }
+
+    /// Whether the server failed to start the last time it was started.
+    pub fn failed_to_start(&self) -> bool {
+        self.failed_start.load(Ordering::Relaxed)
+    }
+
+    /// Stop server through its driver.
+    ///
+    /// Returns `true` if the server is now stopping.
+    async fn stop_driver(&self, config: &Config) -> bool {
+        if !driver::get(config).stop(config, self).await {
+            return false;
+        }
+
+        self.update_state_from(Some(State::Starting), State::Stopping, config)
+            .await;
+        self.update_state_from(Some(State::Started), State::Stopping, config)
+            .await;
+        true
+    }
+
+    /// Freeze server through its driver.
+    ///
+    /// Returns `true` if the server is now frozen.
+    async fn freeze(&self, config: &Config) -> bool {
+        if !driver::get(config).freeze(config, self).await {
+            return false;
+        }
+        self.frozen_since.write().await.replace(Instant::now());
+
+        self.update_state_from(Some(State::Starting), State::Stopped, config)
+            .await;
+        self.update_state_from(Some(State::Started), State::Stopped, config)
+            .await;
+        true
+    }
+
+    /// Thaw frozen server through its driver, does not change server state.
+    ///
+    /// Returns `false` if the server is not frozen, or if it could not be thawed.
+    async fn thaw(&self, config: &Config) -> bool {
+        if self.frozen_since.read().await.is_none() {
+            return false;
+        }
+        if !driver::get(config).thaw(config, self).await {
+            return false;
+        }
+        self.frozen_since.write().await.take();
+        true
+    }
+
+    /// Stop running server.
+    ///
+    /// This will attempt to stop the server with all available methods.
+    pub async fn stop(&self, config: &Config) -> bool {
+        self.stop_with(config, config.server.freeze_process).await
+    }
+
+    /// Stop running server, optionally allowing to freeze it.
+    ///
+    /// This will attempt to stop the server with all available methods.
+    #[allow(unused_variables)]
+    async fn stop_with(&self, config: &Config, freeze: bool) -> bool {
+        // Only log in dry run, reset idle time to not repeat this right away
+        if config.dry_run {
+            let actions = ConfigSleep::actions(config)
+                .iter()
+                .map(|action| action.action.name())
+                .collect::<Vec<_>>();
+            info!(target: "lazymc", "Dry run, would stop server with sleep actions: {}", actions.join(", "));
+            self.update_last_active().await;
+            return true;
+        }
+
+        // Warn online players before stopping
+        #[cfg(feature = "rcon")]
+        if config.rcon.stop_countdown > 0
+            && self.state() == State::Started
+            && self.players_online().await > 0
+            && !self.stop_countdown(config).await
+        {
+            self.update_last_active().await;
+            return false;
+        }
+
+        // Invoke sleep actions in order until one succeeds, escalate if server doesn't stop in time
+        let actions = ConfigSleep::actions(config);
+        let mut stopping = false;
+        for (i, action) in actions.iter().enumerate() {
+            if !self.sleep_action(config, action, freeze).await {
+                continue;
+            }
+            stopping = true;
+
+            let last = i + 1 == actions.len();
+            if last || action.timeout == 0 || self.wait_stopped(action.timeout).await {
+                return true;
+            }
+            warn!(target: "lazymc", "Server did not stop within {}s after '{}' sleep action, trying next action", action.timeout, action.action.name());
+        }
+
+        if !stopping {
+            warn!(target: "lazymc", "Failed to stop server, no more suitable stopping method to use");
+        }
+        stopping
+    }
+
+    /// Invoke a single sleep action.
+    ///
+    /// Returns `true` if the action succeeded, and the server is now stopping or stopped.
+    async fn sleep_action(&self, config: &Config, action: &SleepAction, freeze: bool) -> bool {
+        match action.action {
+            SleepActionKind::Freeze => freeze && self.freeze(config).await,
+            SleepActionKind::Rcon => {
+                #[cfg(feature = "rcon")]
+                if self.state() == State::Started {
+                    return stop_server_rcon(config, self).await;
+                }
+                false
+            }
+            SleepActionKind::Signal => self.stop_driver(config).await,
+            SleepActionKind::Container | SleepActionKind::Hook => {
+                let args = match &action.action {
+                    SleepActionKind::Container => match &action.container {
+                        Some(container) => {
+                            vec![action.runtime.clone(), "stop".into(), container.clone()]
+                        }
+                        None => {
+                            warn!(target: "lazymc", "Skipping container sleep action, no container configured");
+                            return false;
+                        }
+                    },
+                    _ => match action
+                        .command
+                        .as_deref()
+                        .and_then(|command| ConfigServer::resolve_command(config, command))
+                    {
+                        Some(args) => args,
+                        None => {
+                            warn!(target: "lazymc", "Skipping hook sleep action, no valid command configured");
+                            return false;
+                        }
+                    },
+                };
+
+                let dir = ConfigServer::server_directory(config);
+                let timeout = Duration::from_secs(config.server.command_timeout as u64);
+                if let Err(err) = util::command::run(&args, dir.as_deref(), timeout).await {
+                    error!(target: "lazymc", "Failed '{}' sleep action, {}", action.action.name(), err);
+                    return false;
+                }
+
+                self.update_state_from(Some(State::Starting), State::Stopping, config)
+                    .await;
+                self.update_state_from(Some(State::Started), State::Stopping, config)
+                    .await;
+                true
+            }
+        }
+    }
+
+    /// Wait for server to reach stopped state, with timeout in seconds.
+    ///
+    /// Returns `true` if stopped.
+    async fn wait_stopped(&self, timeout: u32) -> bool {
+        let stopped = self.wait_for_state(|state| state == State::Stopped);
+        time::timeout(Duration::from_secs(timeout as u64), stopped)
+            .await
+            .is_ok()
+    }
+
+    /// Invoke a command on the server through RCON.
+    ///
+    /// Holds the RCON lock while the command is invoked. Errors are logged.
+    #[cfg(feature = "rcon")]
+    pub async fn rcon_command(&self, config: &Config, cmd: &str) -> Result<String, ()> {
+        self.rcon_commands(config, &[cmd])
+            .await
+            .map(|mut output| output.remove(0))
+    }
+
+    /// Invoke commands on the server through RCON, over a single connection.
+    ///
+    /// Holds the RCON lock while the commands are invoked. Stops at the first failing command.
+    /// Errors are logged.
+    #[cfg(feature = "rcon")]
+    pub async fn rcon_commands<S: AsRef<str>>(
+        &self,
+        config: &Config,
+        cmds: &[S],
+    ) -> Result<Vec<String>, ()> {
+        use crate::mc::rcon::Rcon;
+
+        // RCON must be enabled
+        if !config.rcon.enabled {
+            return Err(());
+        }
+
+        // Grab RCON lock
+        let rcon_lock = self.rcon_lock.acquire().await.unwrap();
+
+        // Create RCON client
+        let addr = self.rcon_address(config).to_string();
+        let mut rcon = Rcon::connect(config, &addr, &config.rcon.password)
+            .await
+            .map_err(|err| {
+                debug!(target: "lazymc::rcon", "Failed to connect to server over RCON: {}", err);
+            })?;
+
+        // Invoke commands, gracefully close connection
+        let mut outputs = Vec::with_capacity(cmds.len());
+        let mut result = Ok(());
+        for cmd in cmds {
+            let cmd = cmd.as_ref();
+            match rcon.cmd(cmd).await {
+                Ok(output) => outputs.push(output),
+                Err(err) => {
+                    debug!(target: "lazymc::rcon", "Failed to invoke '{}' through RCON: {}", cmd, err);
+                    result = Err(());
+                    break;
+                }
+            }
+        }
+        rcon.close().await;
+
+        drop(rcon_lock);
+
+        result.map(|_| outputs)
+    }
+
+    /// Invoke a vanilla command on the server through RCON.
+    ///
+    /// The command is adjusted for the server brand, see `vanilla_command`.
+    #[cfg(feature = "rcon")]
+    pub async fn rcon_vanilla_command(&self, config: &Config, cmd: &str) -> Result<String, ()> {
+        let cmd = self.vanilla_command(config, cmd).await;
+        self.rcon_command(config, &cmd).await
+    }
+
+    /// Invoke vanilla commands on the server through RCON, over a single connection.
+    ///
+    /// The commands are adjusted for the server brand, see `vanilla_command`.
+    #[cfg(feature = "rcon")]
+    pub async fn rcon_vanilla_commands<S: AsRef<str>>(
+        &self,
+        config: &Config,
+        cmds: &[S],
+    ) -> Result<Vec<String>, ()> {
+        let mut vanilla = Vec::with_capacity(cmds.len());
+        for cmd in cmds {
+            vanilla.push(self.vanilla_command(config, cmd.as_ref()).await);
+        }
+        self.rcon_commands(config, &vanilla).await
+    }
+
+    /// Get vanilla command for the server brand, if brand tuning is enabled.
+    #[cfg(feature = "rcon")]
+    pub async fn vanilla_command(&self, config: &Config, cmd: &str) -> String {
+        match self.brand(config).await {
+            Some(brand) if config.server.brand_tuning => brand.vanilla_command(cmd),
+            _ => cmd.into(),
+        }
+    }
+
+    /// Count down to stopping in titles shown to all online players through RCON.
+    ///
+    /// Uses a single RCON connection, holding the RCON lock. Returns `false` if aborted because
+    /// more players came online.
+    #[cfg(feature = "rcon")]
+    async fn stop_countdown(&self, config: &Config) -> bool {
+        use crate::mc::rcon::Rcon;
+
+        let rcon = &config.rcon;
+        let text = |text: &str, seconds: u64| serde_json::json!({ "text": text.replace("%seconds%", &seconds.to_string()) });
+
+        // Grab RCON lock, connect once for whole countdown
+        let rcon_lock = self.rcon_lock.acquire().await.unwrap();
+        let addr = self.rcon_address(config).to_string();
+        let mut con = match Rcon::connect(config, &addr, &rcon.password).await {
+            Ok(con) => con,
+            Err(err) => {
+                warn!(target: "lazymc", "Failed to connect to server over RCON for stop countdown: {}", err);
+                return true;
+            }
+        };
+
+        info!(target: "lazymc", "Counting down {}s to stop for online players", rcon.stop_countdown);
+        let players = self.players_online().await;
+        let _ = con
+            .cmd(&self.vanilla_command(config, "title @a times 0 30 10").await)
+            .await;
+        let deadline = Instant::now() + Duration::from_secs(rcon.stop_countdown as u64);
+        let mut aborted = false;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+
+            // Abort if more players came online
+            if let Ok(list) = con.cmd(&self.vanilla_command(config, "list").await).await {
+                if list_online(&list).map_or(false, |online| online > players) {
+                    aborted = true;
+                    break;
+                }
+            }
+
+            let seconds = (remaining.as_millis() as u64 + 999) / 1000;
+            let subtitle = text(&rcon.stop_countdown_subtitle, seconds);
+            let title = text(&rcon.stop_countdown_title, seconds);
+            for cmd in [
+                format!("title @a subtitle {subtitle}"),
+                format!("title @a title {title}"),
+            ] {
+                let _ = con.cmd(&self.vanilla_command(config, &cmd).await).await;
+            }
+
+            // Show next number at the next whole second
+            let next = Duration::from_secs(seconds - 1);
+            time::sleep_until((deadline - next).into()).await;
+        }
+
+        if aborted {
+            info!(target: "lazymc", "Player joined during stop countdown, not stopping");
+            let _ = con
+                .cmd(&self.vanilla_command(config, "title @a clear").await)
+                .await;
+        }
+        con.close().await;
+        drop(rcon_lock);
+
+        !aborted
+    }
+
+    /// Force kill running server, and start it again once it has stopped.
+    ///
+    /// Kills the server through its driver.
+    pub async fn force_restart(&self, config: &Config) -> bool {
+        // Only log in dry run
+        if config.dry_run {
+            info!(target: "lazymc", "Dry run, would force kill and restart server");
+            return true;
+        }
+
+        self.restart.store(true, Ordering::Relaxed);
+        if !self.force_kill(config).await {
+            self.restart.store(false, Ordering::Relaxed);
+            return false;
+        }
+        true
+    }
+
+    /// Restart running server.
+    ///
+    /// Fully stops the server, never freezing it, and starts it again once it has stopped.
+    pub async fn restart(&self, config: &Config) -> bool {
+        // Only log in dry run
+        if config.dry_run {
+            info!(target: "lazymc", "Dry run, would restart server");
+            return true;
+        }
+
+        self.restart.store(true, Ordering::Relaxed);
+        if !self.stop_with(config, false).await {
+            self.restart.store(false, Ordering::Relaxed);
+            return false;
+        }
+        true
+    }
+
+    /// Force kill running server.
+    ///
+    /// Kills the server through its driver.
+    pub async fn force_kill(&self, config: &Config) -> bool {
+        // Only log in dry run
+        if config.dry_run {
+            info!(target: "lazymc", "Dry run, would force kill server");
+            return true;
+        }
+
+        driver::get(config).kill(config, self).await
+    }
+
+    /// Decide whether the frozen server should be fully stopped.
+    ///
+    /// A frozen server is fully stopped to release its memory once it has been idle for the
+    /// configured time.
+    pub async fn should_stop_frozen(&self, config: &Config) -> bool {
+        if config.time.stop_after == 0 || self.state() != State::Stopped {
+            return false;
+        }
+
+        let frozen_since = match *self.frozen_since.read().await {
+            Some(frozen_since) => frozen_since,
+            None => return false,
+        };
+        let idle_since = self.last_active.read().await.unwrap_or(frozen_since);
+        idle_since.elapsed() >= Duration::from_secs(config.time.stop_after as u64)
+    }
+
+    /// Fully stop frozen server, thawing it first.
+    ///
+    /// Does nothing if the server is not frozen.
+    pub async fn stop_frozen(&self, config: &Config) -> bool {
+        if self.frozen_since.read().await.is_none() {
+            return false;
+        }
+
+        // Must set state from stopped to stopping
+        if !self
+            .update_state_from(Some(State::Stopped), State::Stopping, config)
+            .await
+        {
+            return false;
+        }
+
+        self.thaw(config).await;
+
+        // Try to stop through RCON, or driver
+        #[cfg(feature = "rcon")]
+        if stop_server_rcon(config, self).await {
+            return true;
+        }
+        if self.stop_driver(config).await {
+            return true;
+        }
+
+        warn!(target: "lazymc", "Failed to stop thawed server, no more suitable stopping method to use");
+        false
+    }
+
+    /// Decide whether the server should sleep.
+    ///
+    /// Always returns false if it is currently not online.
+    pub async fn should_sleep(&self, config: &Config) -> bool {
+        // Server must be online
+        if self.state() != State::Started {
+            return false;
+        }
+
+        // Never sleep if players are online
+        if self.players_online().await > 0 {
+            trace!(target: "lazymc", "Not sleeping because players are online");
+            return false;
+        }
+
+        // Don't sleep when keep online until isn't expired
+        let keep_online = self
+            .keep_online_until
+            .read()
+            .await
+            .map(|i| i >= Instant::now())
+            .unwrap_or(false);
+        if keep_online {
+            trace!(target: "lazymc", "Not sleeping because of keep online");
+            return false;
+        }
+
+        // Don't sleep when kept awake externally
+        if self.keep_awake_for().await.is_some() {
+            trace!(target: "lazymc", "Not sleeping because of keep awake");
+            return false;
+        }
+
+        // Never sleep while clients are proxied, if counting from last disconnect
+        if config.time.sleep_from_disconnect && metrics::active_sessions() > 0 {
+            trace!(target: "lazymc", "Not sleeping because clients are connected");
+            return false;
+        }
+
+        // Idle time must have passed sleep threshold
+        if let Some(idle_since) = self.idle_since(config).await {
+            let sleep_after = Time::sleep_after(config);
+            return idle_since.elapsed() >= Duration::from_secs(sleep_after as u64);
+        }
+
+        false
+    }
+
+    /// Get the time the server is idle since, to count the sleep threshold from.
+    ///
+    /// This is the last time players were seen online, or the time the last proxied client
+    /// disconnected if `time.sleep_from_disconnect` is enabled.
+    async fn idle_since(&self, config: &Config) -> Option<Instant> {
+        if !config.time.sleep_from_disconnect {
+            return *self.last_active.read().await;
+        }
+
+        // Count from server start if nobody disconnected since
+        let started = *self.state_since.read().await;
+        match (started, metrics::last_session_closed()) {
+            (Some(started), Some(closed)) => Some(started.max(closed)),
+            (started, closed) => started.or(closed),
+        }
+    }
+
+    /// Get time until the server will sleep.
+    ///
+    /// Returns `None` if the server is not online, or won't sleep because players are online.
+    pub async fn sleep_in(&self, config: &Config) -> Option<Duration> {
+        if self.state() != State::Started || self.players_online().await > 0 {
+            return None;
+        }
+        if config.time.sleep_from_disconnect && metrics::active_sessions() > 0 {
+            return None;
+        }
+
+        let now = Instant::now();
+        let keep_online = self.keep_online_until.read().await.unwrap_or(now);
+        let keep_awake = self.keep_awake_until.read().await.unwrap_or(now);
+        let idle = self
+            .idle_since(config)
+            .await
+            .map(|i| i + Duration::from_secs(Time::sleep_after(config) as u64))
+            .unwrap_or(now);
+        Some(
+            keep_online
+                .max(keep_awake)
+                .max(idle)
+                .saturating_duration_since(now),
+        )
+    }
+
+    /// Decide whether to force kill the server process.
+    pub async fn should_kill(&self) -> bool {
+        self.kill_at
+            .read()
+            .await
+            .map(|t| t <= Instant::now())
+            .unwrap_or(false)
+    }
+
+    /// Mark the server as unresponsive.
+    ///
+    /// Keeps the time it first became unresponsive. Reset once a status is received or when the
+    /// state changes.
+    pub async fn mark_unresponsive(&self) {
+        let mut since = self.unresponsive_since.write().await;
+        if since.is_none() {
+            since.replace(Instant::now());
+        }
+    }
+
+    /// Mark the server as responsive again.
+    pub async fn mark_responsive(&self) {
+        self.unresponsive_since.write().await.take();
+    }
+
+    /// Get how long the server has been unresponsive while started.
+    pub async fn unresponsive_for(&self) -> Option<Duration> {
+        self.unresponsive_since.read().await.map(|i| i.elapsed())
+    }
+
+    /// Get last known server status.
+    ///
+    /// Lock-free, as this is read on every status request.
+    pub fn status(&self) -> Option<Arc<ServerStatus>> {
+        self.status.load_full()
+    }
+
+    /// Get status response data of the started server, to relay to a client.
+    ///
+    /// Fetches the status from the server at most once per `proxy.status_coalesce` interval,
+    /// concurrent callers wait for a fetch in progress. Returns `None` if fetching failed.
+    pub async fn coalesced_status(&self, config: &Config) -> Option<Arc<Vec<u8>>> {
+        let interval = Duration::from_millis(config.proxy.status_coalesce);
+        let mut cached = self.coalesced_status.lock().await;
+        if let Some((fetched_at, data)) = &*cached {
+            if fetched_at.elapsed() < interval {
+                return data.clone();
+            }
+        }
+
+        // Relay raw status, keeps fields we don't know about
+        let addr = self.probe_address(config);
+        let protocol = self.protocol(config).await;
+        let data = match monitor::fetch_status(config, addr, protocol).await {
+            Ok((_, json)) => {
+                let json = json.to_string();
+                types::encode_var_int(json.len() as i32)
+                    .ok()
+                    .map(|mut data| {
+                        data.extend_from_slice(json.as_bytes());
+                        Arc::new(data)
+                    })
+            }
+            Err(_) => {
+                debug!(target: "lazymc", "Failed to fetch server status for coalesced status requests");
+                None
+            }
+        };
+        cached.replace((Instant::now(), data.clone()));
+        data
+    }
+
+    /// Get secure chat flags to report in status.
+    ///
+    /// Uses flags from the last known server status, overridden by configuration.
+    pub async fn secure_chat(&self, config: &Config) -> SecureChat {
+        let known = **self.secure_chat.load();
+        SecureChat {
+            enforces: config.public.enforces_secure_chat.or(known.enforces),
+            previews: config.public.previews_chat.or(known.previews),
+        }
+    }
+
+    /// Update details not covered by `ServerStatus` from raw server status JSON.
+    ///
+    /// This updates secure chat flags and the detected brand.
+    pub async fn update_status_json(&self, status: &serde_json::Value) {
+        self.secure_chat
+            .store(Arc::new(SecureChat::from_json(status)));
+
+        if let Some(brand) = Brand::from_status(status) {
+            let mut last = self.brand.write().await;
+            if *last != Some(brand) {
+                info!(target: "lazymc::monitor", "Detected server brand: {}", brand.name());
+                last.replace(brand);
+            }
+        }
+    }
+
+    /// Get server brand.
+    ///
+    /// Uses the brand detected from server status, falls back to detecting it from the server
+    /// command.
+    pub async fn brand(&self, config: &Config) -> Option<Brand> {
+        match *self.brand.read().await {
+            Some(brand) => Some(brand),
+            None => Brand::from_command(&config.server.command),
+        }
+    }
+
+    /// Seed learned start and stop durations, such as from recorded stats.
+    pub async fn seed_durations(&self, starts: Vec<u64>, stops: Vec<u64>) {
+        for (durations, seed) in [
+            (&self.start_durations, starts),
+            (&self.stop_durations, stops),
+        ] {
+            let mut durations = durations.write().await;
+            for duration in seed {
+                push_duration(&mut durations, duration);
+            }
+        }
+    }
+
+    /// Get estimated time until the server is started, based on observed start durations.
+    ///
+    /// Returns `None` if not starting, or if no start durations are observed yet.
+    pub async fn start_eta(&self) -> Option<Duration> {
+        if self.state() != State::Starting {
+            return None;
+        }
+
+        let mut durations: Vec<u64> = self.start_durations.read().await.iter().copied().collect();
+        if durations.is_empty() {
+            return None;
+        }
+        durations.sort_unstable();
+        let median = Duration::from_secs(durations[durations.len() / 2]);
+        Some(median.saturating_sub(self.state_for().await.unwrap_or_default()))
+    }
+
+    /// Get position of a player in the join queue while starting, starting at 1.
+    ///
+    /// Adds the player to the end of the queue if not queued yet.
+    pub async fn queue_position(&self, player: &str) -> usize {
+        let mut queue = self.join_queue.write().await;
+        match queue.iter().position(|p| p == player) {
+            Some(index) => index + 1,
+            None => {
+                queue.push(player.into());
+                queue.len()
+            }
+        }
+    }
+
+    /// Get server start timeout in seconds, 0 if disabled.
+    ///
+    /// The configured timeout, or the default raised to the minimum for the server brand if brand
+    /// tuning is enabled. Raised further if adaptive timeouts learned a longer one from observed
+    /// durations.
+    pub async fn start_timeout(&self, config: &Config) -> u32 {
+        let timeout = ConfigServer::start_timeout(config);
+        if timeout == 0 {
+            return 0;
+        }
+
+        let timeout = match self.brand(config).await {
+            Some(brand) if config.server.start_timeout.is_none() && config.server.brand_tuning => {
+                timeout.max(brand.min_start_timeout())
+            }
+            _ => timeout,
+        };
+        config
+            .server
+            .adaptive_timeouts
+            .learn(&*self.start_durations.read().await, timeout)
+            .unwrap_or(timeout)
+    }
+
+    /// Get server stop timeout in seconds, 0 if disabled.
+    ///
+    /// The configured timeout, or the default raised to the minimum for the server brand if brand
+    /// tuning is enabled. Raised further if adaptive timeouts learned a longer one from observed
+    /// durations.
+    pub async fn stop_timeout(&self, config: &Config) -> u32 {
+        let timeout = ConfigServer::stop_timeout(config);
+        if timeout == 0 {
+            return 0;
+        }
+
+        let timeout = match self.brand(config).await {
+            Some(brand) if config.server.stop_timeout.is_none() && config.server.brand_tuning => {
+                timeout.max(brand.min_stop_timeout())
+            }
+            _ => timeout,
+        };
+        config
+            .server
+            .adaptive_timeouts
+            .learn(&*self.stop_durations.read().await, timeout)
+            .unwrap_or(timeout)
+    }
+
+    /// Get protocol version of the server.
+    ///
+    /// Uses the version detected from the last known server status, falls back to the configured
+    /// version if unknown.
+    pub async fn protocol(&self, config: &Config) -> u32 {
+        self.status
+            .load()
+            .as_ref()
+            .map(|status| status.version.protocol)
+            .unwrap_or(config.public.protocol)
+    }
+
+    /// Remember IP of a player that joined through lazymc.
+    ///
+    /// Forgets the least recently seen IP if too many are known.
+    pub async fn remember_ip(&self, ip: IpAddr) {
+        let mut known_ips = self.known_ips.write().await;
+        if known_ips.len() >= KNOWN_IPS_MAX && !known_ips.contains_key(&ip) {
+            let oldest = known_ips
+                .iter()
+                .min_by_key(|(_, seen)| **seen)
+                .map(|(ip, _)| *ip);
+            if let Some(oldest) = oldest {
+                known_ips.remove(&oldest);
+            }
+        }
+        known_ips.insert(ip, Instant::now());
+    }
+
+    /// Check whether a player joined through lazymc from the given IP before.
+    pub async fn is_known_ip(&self, ip: &IpAddr) -> bool {
+        self.known_ips.read().await.contains_key(ip)
+    }
+
+    /// Get suspicious request tracker.
+    pub fn honeypot(&self) -> &Honeypot {
+        &self.honeypot
+    }
+
+    /// Get failed login throttle.
+    pub fn login_throttle(&self) -> &LoginThrottle {
+        &self.login_throttle
+    }
+
+    /// Get player UUID resolver.
+    pub fn uuids(&self) -> &UuidResolver {
+        &self.uuids
+    }
+
+    /// Get names of the players last seen online.
+    pub async fn last_players(&self) -> Vec<String> {
+        self.last_players.read().await.clone()
+    }
+
+    /// Get number of online players from last known server status.
+    ///
+    /// Returns 0 if unknown.
+    pub async fn players_online(&self) -> u32 {
+        self.status
+            .load()
+            .as_ref()
+            .map(|status| status.players.online)
+            .unwrap_or(0)
+    }
+
+    /// Get server process PID if running.
+    pub async fn pid(&self) -> Option<u32> {
+        *self.pid.lock().await
+    }
+
+    /// Read last sampled resource usage of the server process.
+    pub async fn resources(&self) -> Option<ResourceUsage> {
+        *self.resources.read().await
+    }
+
+    /// Update sampled resource usage of the server process.
+    pub async fn set_resources(&self, usage: Option<ResourceUsage>) {
+        *self.resources.write().await = usage;
+    }
+
+    /// Get status of the last backup.
+    pub async fn backup_status(&self) -> Option<BackupStatus> {
+        self.backup_status.read().await.clone()
+    }
+
+    /// Set status of the last backup.
+    pub async fn set_backup_status(&self, status: BackupStatus) {
+        self.backup_status.write().await.replace(status);
+    }
+
+    /// Whether a wake request is pending, to start the server again once it has stopped.
+    pub fn wake_pending(&self) -> bool {
+        self.wake_pending.load(Ordering::Relaxed)
+    }
+
+    /// Check whether maintenance mode is enabled.
+    pub fn maintenance(&self) -> bool {
+        self.maintenance.load(Ordering::Relaxed)
+    }
+
+    /// Enable or disable maintenance mode.
+    pub fn set_maintenance(&self, enabled: bool) {
+        if self.maintenance.swap(enabled, Ordering::Relaxed) != enabled {
+            match enabled {
+                true => info!(target: "lazymc", "Maintenance mode enabled"),
+                false => info!(target: "lazymc", "Maintenance mode disabled"),
+            }
+        }
+    }
+
+    /// Check whether the server reached the started state within the given duration.
+    pub fn started_within(&self, duration: Duration) -> bool {
+        self.state() == State::Started
+            && self
+                .state_since
+                .try_read()
+                .ok()
+                .and_then(|since| *since)
+                .map(|since| since.elapsed() < duration)
+                .unwrap_or(false)
+    }
+
+    /// Get how long the server has been in its current state.
+    pub async fn state_for(&self) -> Option<Duration> {
+        self.state_since.read().await.map(|i| i.elapsed())
+    }
+
+    /// Get the last server error.
+    pub async fn last_error(&self) -> Option<String> {
+        self.last_error.read().await.clone()
+    }
+
+    /// Set the last server error.
+    pub async fn set_last_error(&self, err: String) {
+        self.last_error.write().await.replace(err);
+    }
+
+    /// Build status snapshot, as reported to control clients.
+    pub async fn snapshot(&self, config: &Config) -> serde_json::Value {
+        let players = self.status().as_ref().map(|status| {
+            serde_json::json!({ "online": status.players.online, "max": status.players.max })
+        });
+        let resources = self
+            .resources()
+            .await
+            .map(|usage| serde_json::json!({ "memory": usage.memory, "cpu": usage.cpu }));
+        let backup = self.backup_status().await.map(|backup| {
+            serde_json::json!({
+                "time": backup.time.to_rfc3339(),
+                "success": backup.success,
+                "archive": backup.archive,
+                "uploaded": backup.uploaded,
+            })
+        });
+
+        serde_json::json!({
+            "state": self.state().name(),
+            "state_for": self.state_for().await.map(|d| d.as_secs()),
+            "pid": self.pid().await,
+            "players": players,
+            "resources": resources,
+            "backup": backup,
+            "sleep_in": self.sleep_in(config).await.map(|d| d.as_secs()),
+            "last_error": self.last_error().await,
+            "brand": self.brand(config).await.map(Brand::name),
+            "maintenance": self.maintenance(),
+            "keep_awake": self.keep_awake_for().await.map(|d| d.as_secs()),
+            "instance": if self.secondary_active() { "secondary" } else { "primary" },
+        })
+    }
+
+    /// Build diagnostic snapshot of internal state, timers and recent transitions.
+    pub async fn dump(&self, config: &Config) -> String {
+        let now = Instant::now();
+        let ago = |i: Option<Instant>| match i {
+            Some(i) if i <= now => format!("{}s ago", (now - i).as_secs()),
+            Some(i) => format!("in {}s", (i - now).as_secs()),
+            None => "-".into(),
+        };
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "State: {} ({})",
+            self.state().name(),
+            ago(*self.state_since.read().await)
+        );
+        let _ = writeln!(out, "PID: {:?}", self.pid().await);
+        let _ = writeln!(out, "Brand: {:?}", self.brand(config).await);
+        let _ = writeln!(out, "Last active: {}", ago(*self.last_active.read().await));
+        let _ = writeln!(
+            out,
+            "Keep online until: {}",
+            ago(*self.keep_online_until.read().await)
+        );
+        let _ = writeln!(
+            out,
+            "Keep awake until: {}",
+            ago(*self.keep_awake_until.read().await)
+        );
+        let _ = writeln!(out, "Kill at: {}", ago(*self.kill_at.read().await));
+        let _ = writeln!(
+            out,
+            "Frozen since: {}",
+            ago(*self.frozen_since.read().await)
+        );
+        let _ = writeln!(
+            out,
+            "Unresponsive since: {}",
+            ago(*self.unresponsive_since.read().await)
+        );
+        let _ = writeln!(
+            out,
+            "Sleep in: {:?}",
+            self.sleep_in(config).await.map(|d| d.as_secs())
+        );
+        let _ = writeln!(
+            out,
+            "Restart requested: {}",
+            self.restart.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(out, "Maintenance: {}", self.maintenance());
+        let _ = writeln!(
+            out,
+            "Active player sessions: {}",
+            metrics::active_sessions()
+        );
+        let _ = writeln!(
+            out,
+            "Last error: {}",
+            self.last_error().await.as_deref().unwrap_or("-")
+        );
+        match self.status().as_ref() {
+            Some(status) => {
+                let _ = writeln!(
+                    out,
+                    "Cached status: {} (protocol {}), {}/{} players",
+                    status.version.name,
+                    status.version.protocol,
+                    status.players.online,
+                    status.players.max,
+                );
+            }
+            None => {
+                let _ = writeln!(out, "Cached status: -");
+            }
+        }
+        let _ = writeln!(out, "Recent transitions:");
+        for (time, from, to) in self.transitions.read().await.iter() {
+            let _ = writeln!(
+                out,
+                "  {} {} -> {}",
+                time.format("%Y-%m-%d %H:%M:%S"),
+                from.name(),
+                to.name()
+            );
+        }
+        out
+    }
+
+    /// Update the last active time.
+    pub async fn update_last_active(&self) {
+        self.last_active.write().await.replace(Instant::now());
+    }
+
+    /// Force the server to be online for the given number of seconds.
+    async fn keep_online_for(&self, duration: Option<u32>) {
+        *self.keep_online_until.write().await = duration
+            .filter(|d| *d > 0)
+            .map(|d| Instant::now() + Duration::from_secs(d as u64));
+    }
+
+    /// Keep the server awake for the given duration regardless of occupancy.
+    ///
+    /// Starts the server if it is stopped, unless in maintenance mode. Cancels keeping awake if
+    /// `None` is given.
+    pub async fn keep_awake(config: Arc<Config>, server: Arc<Server>, duration: Option<Duration>) {
+        *server.keep_awake_until.write().await = duration.map(|d| Instant::now() + d);
+
+        match duration {
+            Some(duration) => {
+                info!(target: "lazymc", "Keeping server awake for {}s", duration.as_secs());
+                if server.maintenance() {
+                    info!(target: "lazymc", "Not starting server, maintenance mode is enabled");
+                    return;
+                }
+                Server::start(config, server, None).await;
+            }
+            None => info!(target: "lazymc", "Stopped keeping server awake"),
+        }
+    }
+
+    /// Get remaining time the server is kept awake externally, if any.
+    pub async fn keep_awake_for(&self) -> Option<Duration> {
+        self.keep_awake_until
+            .read()
+            .await
+            .and_then(|i| i.checked_duration_since(Instant::now()))
+    }
+
+    /// Check whether the given IP is banned.
+    ///
+    /// This uses the latest known `banned-ips.json` contents if known.
+    /// If this feature is disabled, this will always return false.
+    pub async fn is_banned_ip(&self, ip: &IpAddr) -> bool {
+        self.banned_ips.read().await.is_banned(ip)
+    }
+
+    /// Get user ban entry.
+    pub async fn ban_entry(&self, ip: &IpAddr) -> Option<BannedIp> {
+        self.banned_ips.read().await.get(ip)
+    }
+
+    /// Check whether the given IP is banned.
+    ///
+    /// This uses the latest known `banned-ips.json` contents if known.
+    /// If this feature is disabled, this will always return false.
+    ///
+    /// Must not be called from an async context, use `is_banned_ip` instead.
+    pub fn is_banned_ip_blocking(&self, ip: &IpAddr) -> bool {
+        self.banned_ips.blocking_read().is_banned(ip)
+    }
+
+    /// Check whether the given username is whitelisted.
+    ///
+    /// Returns `true` if no whitelist is currently used.
+    pub async fn is_whitelisted(&self, username: &str) -> bool {
+        self.whitelist
+            .read()
+            .await
+            .as_ref()
+            .map(|w| w.is_whitelisted(username))
+            .unwrap_or(true)
+    }
+
+    /// Check whether the given UUID is whitelisted.
+    ///
+    /// Returns `true` if no whitelist is currently used.
+    pub async fn is_whitelisted_uuid(&self, uuid: &Uuid) -> bool {
+        self.whitelist
+            .read()
+            .await
+            .as_ref()
+            .map(|w| w.is_whitelisted_uuid(uuid))
+            .unwrap_or(true)
+    }
+
+    /// Update the list of banned IPs.
+    pub async fn set_banned_ips(&self, ips: BannedIps) {
+        *self.banned_ips.write().await = ips;
+    }
+
+    /// Update the list of banned IPs.
+    ///
+    /// Must not be called from an async context, use `set_banned_ips` instead.
+    pub fn set_banned_ips_blocking(&self, ips: BannedIps) {
+        *self.banned_ips.blocking_write() = ips;
+    }
+
+    /// Update the whitelist.
+    pub async fn set_whitelist(&self, whitelist: Option<Whitelist>) {
+        *self.whitelist.write().await = whitelist;
+    }
+
+    /// Update the whitelist.
+    ///
+    /// Must not be called from an async context, use `set_whitelist` instead.
+    pub fn set_whitelist_blocking(&self, whitelist: Option<Whitelist>) {
+        *self.whitelist.blocking_write() = whitelist;
+    }
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self {
+            state: watch::channel(State::Stopped).0,
+            events: broadcast::channel(EVENT_BUFFER).0,
+            pid: Default::default(),
+            status: Default::default(),
+            secure_chat: Default::default(),
+            coalesced_status: Default::default(),
+            brand: Default::default(),
+            known_ips: Default::default(),
+            honeypot: Default::default(),
+            login_throttle: Default::default(),
+            uuids: Default::default(),
+            last_players: Default::default(),
+            start_durations: Default::default(),
+            stop_durations: Default::default(),
+            join_queue: Default::default(),
+            last_active: Default::default(),
+            frozen_since: Default::default(),
+            keep_online_until: Default::default(),
+            keep_awake_until: Default::default(),
+            kill_at: Default::default(),
+            banned_ips: Default::default(),
+            whitelist: Default::default(),
+            resources: Default::default(),
+            restart: AtomicBool::new(false),
+            wake_pending: AtomicBool::new(false),
+            failed_start: AtomicBool::new(false),
+            maintenance: AtomicBool::new(false),
+            unresponsive_since: Default::default(),
+            backup_status: Default::default(),
+            state_since: Default::default(),
+            last_error: Default::default(),
+            transitions: Default::default(),
+            secondary: AtomicBool::new(false),
+            handover: Default::default(),
+            #[cfg(windows)]
+            job: Default::default(),
+            #[cfg(target_os = "linux")]
+            cgroup: Default::default(),
+            #[cfg(feature = "rcon")]
+            rcon_lock: Semaphore::new(1),
+            #[cfg(feature = "rcon")]
+            rcon_last_stop: Default::default(),
+            probed_join_game: Default::default(),
+            forge_payload: Default::default(),
+        }
+    }
+}
+
+/// Remember duration in bounded history, dropping the oldest.
+fn push_duration(durations: &mut VecDeque<u64>, duration: u64) {
+    if durations.len() >= DURATION_HISTORY {
+        durations.pop_front();
+    }
+    durations.push_back(duration);
+}
+
+/// Secure chat flags in server status, for 1.19.1+ clients.
+///
+/// These are not part of `ServerStatus`, and are tracked separately.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SecureChat {
+    /// Whether the server enforces secure chat, `enforcesSecureChat`.
+    pub enforces: Option<bool>,
+
+    /// Whether the server previews chat, `previewsChat`.
+    pub previews: Option<bool>,
+}
+
+impl SecureChat {
+    /// Read flags from server status JSON.
+    pub fn from_json(status: &serde_json::Value) -> Self {
+        Self {
+            enforces: status["enforcesSecureChat"].as_bool(),
+            previews: status["previewsChat"].as_bool(),
+        }
+    }
+
+    /// Write set flags to server status JSON.
+    pub fn apply(&self, status: &mut serde_json::Value) {
+        if let Some(status) = status.as_object_mut() {
+            if let Some(enforces) = self.enforces {
+                status.insert("enforcesSecureChat".into(), enforces.into());
+            }
+            if let Some(previews) = self.previews {
+                status.insert("previewsChat".into(), previews.into());
+            }
+        }
+    }
+}
+
+/// Server state.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum State {
+    /// Server is stopped.
+    Stopped,
+
+    /// Server is starting.
+    Starting,
+
+    /// Server is online and responding.
+    Started,
+
+    /// Server is stopping.
+    Stopping,
+}
+
+impl State {
+    /// From u8.
+    pub fn from_u8(state: u8) -> Result<Self, Error> {
+        match state {
+            0 => Ok(Self::Stopped),
+            1 => Ok(Self::Starting),
+            2 => Ok(Self::Started),
+            3 => Ok(Self::Stopping),
+            _ => Err(Error::InvalidState(state)),
+        }
+    }
+
+    /// Check whether a transition from this to the given state is allowed.
+    ///
+    /// - `Stopped` to `Started` is allowed, when a server that is already running is found.
+    /// - `Stopping` to `Started` is not allowed, a stopping server may still respond to status
+    ///   requests, which must not flap the state.
+    pub fn can_transition_to(self, new: State) -> bool {
+        use State::*;
+        matches!(
+            (self, new),
+            (Stopped, Starting | Started | Stopping)
+                | (Starting, Started | Stopping | Stopped)
+                | (Started, Stopping | Stopped)
+                | (Stopping, Stopped | Starting)
+        )
+    }
+
+    /// To u8.
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Self::Stopped => 0,
+            Self::Starting => 1,
+            Self::Started => 2,
+            Self::Stopping => 3,
+        }
+    }
+
+    /// Get lowercase state name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Stopped => "stopped",
+            Self::Starting => "starting",
+            Self::Started => "started",
+            Self::Stopping => "stopping",
+        }
+    }
+}
+
+/// Sampled resource usage of the server process.
+#[derive(Debug, Copy, Clone)]
+pub struct ResourceUsage {
+    /// Resident memory in bytes.
+    pub memory: u64,
+
+    /// CPU usage as percentage of a single core since the previous sample.
+    pub cpu: f32,
+
+    /// Total CPU time consumed by the process.
+    pub cpu_time: Duration,
+
+    /// Time this was sampled at.
+    pub sampled_at: Instant,
+}
+
+/// Parse number of online players from RCON `list` command output.
+#[cfg(feature = "rcon")]
+fn list_online(output: &str) -> Option<u32> {
+    output.split_whitespace().find_map(|word| word.parse().ok())
+}
+
+/// Stop server through RCON.
+#[cfg(feature = "rcon")]
+async fn stop_server_rcon(config: &Config, server: &Server) -> bool {
+    use crate::mc::rcon::Rcon;
+
+    // RCON must be enabled
+    if !config.rcon.enabled {
+        trace!(target: "lazymc", "Not using RCON to stop server, disabled in config");
+        return false;
+    }
+
+    // Grab RCON lock
+    let rcon_lock = server.rcon_lock.acquire().await.unwrap();
+
+    // Ensure RCON has cooled down
+    let rcon_cooled_down = server
+        .rcon_last_stop
+        .lock()
+        .await
+        .map(|t| t.elapsed() >= RCON_COOLDOWN)
+        .unwrap_or(true);
+    if !rcon_cooled_down {
+        debug!(target: "lazymc", "Not using RCON to stop server, in cooldown, used too recently");
+        return false;
+    }
+
+    // Create RCON client
+    let addr = server.rcon_address(config).to_string();
+    let mut rcon = match Rcon::connect(config, &addr, &config.rcon.password).await {
+        Ok(rcon) => rcon,
+        Err(err) => {
+            error!(target: "lazymc", "Failed to RCON server to sleep: {}", err);
+            return false;
+        }
+    };
+
+    // Invoke stop
+    if let Err(err) = rcon
+        .cmd(&server.vanilla_command(config, "stop").await)
+        .await
+    {
+        error!(target: "lazymc", "Failed to invoke stop through RCON: {}", err);
+        return false;
+    }
+
+    // Set server to stopping state, update last RCON time
+    server.rcon_last_stop.lock().await.replace(Instant::now());
+    server.update_state(State::Stopping, config).await;
+
+    // Gracefully close connection
+    rcon.close().await;
+
+    drop(rcon_lock);
+
+    true
+}
@@ -0,0 +1,145 @@
+use std::fs::{self, Permissions};
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use once_cell::sync::OnceCell;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::backup;
+use crate::config::{Config, Control};
+use crate::os;
+use crate::server::Server;
+
+/// Bound control socket path, removed when quitting.
+static SOCKET: OnceCell<PathBuf> = OnceCell::new();
+
+/// Control socket task.
+///
+/// Serves commands over a Unix socket, one command and JSON response per connection.
+pub async fn service(config: Arc<Config>, server: Arc<Server>) {
+    // Control socket must be enabled
+    if !config.control.enabled {
+        return;
+    }
+
+    // Remove stale socket, don't take over socket of running instance
+    let path = Control::socket_path(&config);
+    if path.exists() {
+        if UnixStream::connect(&path).await.is_ok() {
+            warn!(target: "lazymc::control", "Control socket {} is used by another lazymc instance, not serving", path.display());
+            return;
+        }
+        let _ = fs::remove_file(&path);
+    }
+
+    // Bind with restrictive umask so the socket is never accessible by others, it is not
+    // authenticated
+    let listener = match os::with_umask(0o077, || UnixListener::bind(&path)) {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!(target: "lazymc::control", "Failed to bind control socket {}: {}", path.display(), err);
+            return;
+        }
+    };
+    if let Err(err) = fs::set_permissions(&path, Permissions::from_mode(0o600)) {
+        error!(target: "lazymc::control", "Failed to restrict control socket permissions, not serving: {}", err);
+        let _ = fs::remove_file(&path);
+        return;
+    }
+    let _ = SOCKET.set(path);
+
+    while let Ok((stream, _)) = listener.accept().await {
+        let (config, server) = (config.clone(), server.clone());
+        tokio::spawn(async move {
+            if let Err(err) = handle(stream, config, server).await {
+                debug!(target: "lazymc::control", "Control connection failed: {}", err);
+            }
+        });
+    }
+}
+
+/// Handle a single control connection.
+async fn handle(stream: UnixStream, config: Arc<Config>, server: Arc<Server>) -> io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    BufReader::new(reader).read_line(&mut line).await?;
+
+    let response = match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["status"] => server.snapshot(&config).await,
+        ["maintenance"] => json!({ "maintenance": server.maintenance() }),
+        ["maintenance", mode @ ("on" | "off")] => {
+            server.set_maintenance(*mode == "on");
+            json!({ "maintenance": server.maintenance() })
+        }
+        ["keep-awake"] => keep_awake(&server).await,
+        ["keep-awake", "cancel"] => {
+            Server::keep_awake(config, server.clone(), None).await;
+            keep_awake(&server).await
+        }
+        ["keep-awake", _] if server.maintenance() => {
+            json!({ "error": "server is under maintenance" })
+        }
+        ["keep-awake", seconds] => match seconds.parse() {
+            Ok(seconds) => {
+                let duration = Some(Duration::from_secs(seconds));
+                Server::keep_awake(config, server.clone(), duration).await;
+                keep_awake(&server).await
+            }
+            Err(_) => json!({ "error": format!("invalid number of seconds: {seconds}") }),
+        },
+        ["switch"] if !config.blue_green.enabled => {
+            json!({ "error": "blue/green switching is not enabled (blue_green.enabled)" })
+        }
+        ["switch"] => {
+            // Switching waits for the other instance to come online, don't block the response
+            tokio::spawn(async move {
+                if let Err(err) = Server::switch(config, server).await {
+                    error!(target: "lazymc::control", "Failed to switch server instance: {}", err);
+                }
+            });
+            json!({ "switching": true })
+        }
+        ["restore", id] => match backup::restore::find_archive(&config, id) {
+            Ok(archive) => {
+                info!(target: "lazymc::audit", "Backup {} restore requested over control socket", id);
+                let id = id.to_string();
+                tokio::spawn(backup::restore::restore(config, server, archive));
+                json!({ "restoring": id })
+            }
+            Err(err) => json!({ "error": err }),
+        },
+        _ => json!({ "error": format!("unknown command: {}", line.trim()) }),
+    };
+
+    let mut data = serde_json::to_vec(&response)?;
+    data.push(b'\n');
+    writer.write_all(&data).await
+}
+
+/// Build keep awake response.
+async fn keep_awake(server: &Server) -> Value {
+    json!({ "keep_awake": server.keep_awake_for().await.map(|d| d.as_secs()) })
+}
+
+/// Send command to control socket, return JSON response.
+pub async fn send(path: &Path, command: &str) -> io::Result<Value> {
+    let stream = UnixStream::connect(path).await?;
+    let (reader, mut writer) = stream.into_split();
+    writer.write_all(format!("{command}\n").as_bytes()).await?;
+
+    let mut line = String::new();
+    BufReader::new(reader).read_line(&mut line).await?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// Remove bound control socket, if any.
+pub fn remove_socket() {
+    if let Some(path) = SOCKET.get() {
+        let _ = fs::remove_file(path);
+    }
+}
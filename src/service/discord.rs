@@ -0,0 +1,194 @@
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::State as Extract;
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde_json::{json, Value};
+
+use crate::config::Config;
+use crate::server::Server;
+use crate::util::unhex;
+
+/// Discord API base URL.
+const API_URL: &str = "https://discord.com/api/v10";
+
+/// Interaction type of a ping.
+const INTERACTION_PING: u64 = 1;
+
+/// Interaction type of a slash command.
+const INTERACTION_COMMAND: u64 = 2;
+
+/// Response type to respond with a message.
+const RESPONSE_MESSAGE: u64 = 4;
+
+/// Discord bot task.
+///
+/// Registers the `/mc` slash command. Interactions are received on the `/discord` HTTP endpoint.
+pub async fn service(config: Arc<Config>) {
+    // Discord bot must be enabled
+    if !config.discord.enabled {
+        return;
+    }
+    if !config.http.enabled {
+        warn!(target: "lazymc::discord", "Discord bot requires HTTP endpoints to receive interactions, enable 'http.enabled'");
+    }
+
+    let command = json!([{
+        "name": "mc",
+        "description": "Manage the Minecraft server",
+        "options": [
+            { "type": 1, "name": "wake", "description": "Wake the server" },
+            { "type": 1, "name": "status", "description": "Show server status" },
+            { "type": 1, "name": "sleep", "description": "Put the server to sleep" },
+        ],
+    }]);
+
+    let url = format!(
+        "{API_URL}/applications/{}/commands",
+        config.discord.application_id
+    );
+    let result = reqwest::Client::new()
+        .put(url)
+        .header("Authorization", format!("Bot {}", config.discord.token))
+        .json(&command)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+
+    match result {
+        Ok(_) => info!(target: "lazymc::discord", "Registered Discord slash commands"),
+        Err(err) => {
+            error!(target: "lazymc::discord", "Failed to register Discord slash commands: {}", err)
+        }
+    }
+}
+
+/// Handle Discord interaction, received on HTTP endpoint.
+pub async fn interactions(
+    Extract((config, server)): Extract<(Arc<Config>, Arc<Server>)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, Json<Value>) {
+    // Interactions must be signed by Discord
+    if !verify(&config, &headers, &body) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "invalid request signature" })),
+        );
+    }
+
+    let interaction: Value = match serde_json::from_slice(&body) {
+        Ok(interaction) => interaction,
+        Err(_) => return (StatusCode::BAD_REQUEST, Json(json!({}))),
+    };
+
+    match interaction["type"].as_u64() {
+        Some(INTERACTION_PING) => (StatusCode::OK, Json(json!({ "type": INTERACTION_PING }))),
+        Some(INTERACTION_COMMAND) => {
+            let content = command(config, server, &interaction).await;
+            (
+                StatusCode::OK,
+                Json(json!({ "type": RESPONSE_MESSAGE, "data": { "content": content } })),
+            )
+        }
+        _ => (StatusCode::BAD_REQUEST, Json(json!({}))),
+    }
+}
+
+/// Invoke slash command, return response message.
+async fn command(config: Arc<Config>, server: Arc<Server>, interaction: &Value) -> String {
+    let subcommand = interaction["data"]["options"][0]["name"]
+        .as_str()
+        .unwrap_or_default();
+
+    match subcommand {
+        "status" => {
+            let status = server.snapshot(&config).await;
+            let players = match (
+                status["players"]["online"].as_u64(),
+                status["players"]["max"].as_u64(),
+            ) {
+                (Some(online), Some(max)) => format!(", {online}/{max} players online"),
+                _ => String::new(),
+            };
+            format!(
+                "Server is {}{}",
+                status["state"].as_str().unwrap_or("unknown"),
+                players
+            )
+        }
+        "wake" | "sleep" if !is_allowed(&config, interaction) => {
+            "You are not allowed to do that.".into()
+        }
+        "wake" if server.maintenance() => "Server is under maintenance.".into(),
+        "wake" => {
+            info!(target: "lazymc::discord", "Waking server through Discord");
+            if Server::start(config, server, None).await {
+                "Waking server...".into()
+            } else {
+                "Server is already awake.".into()
+            }
+        }
+        "sleep" => {
+            info!(target: "lazymc::discord", "Putting server to sleep through Discord");
+            tokio::spawn(async move { server.stop(&config).await });
+            "Putting server to sleep...".into()
+        }
+        _ => "Unknown command.".into(),
+    }
+}
+
+/// Check whether the invoking user may wake or sleep the server.
+///
+/// Nobody is allowed if no users or roles are configured.
+fn is_allowed(config: &Config, interaction: &Value) -> bool {
+    let discord = &config.discord;
+    let user = interaction["member"]["user"]["id"]
+        .as_str()
+        .or_else(|| interaction["user"]["id"].as_str());
+    if user.map_or(false, |user| {
+        discord.allowed_users.iter().any(|u| u == user)
+    }) {
+        return true;
+    }
+
+    interaction["member"]["roles"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .any(|role| discord.allowed_roles.iter().any(|r| r == role))
+}
+
+/// Verify interaction request signature with the application public key.
+fn verify(config: &Config, headers: &HeaderMap, body: &[u8]) -> bool {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    let (signature, timestamp) = match (
+        header("X-Signature-Ed25519"),
+        header("X-Signature-Timestamp"),
+    ) {
+        (Some(signature), Some(timestamp)) => (signature, timestamp),
+        _ => return false,
+    };
+
+    let key = match unhex(&config.discord.public_key)
+        .and_then(|key| key.try_into().ok())
+        .and_then(|key: [u8; 32]| VerifyingKey::from_bytes(&key).ok())
+    {
+        Some(key) => key,
+        None => {
+            error!(target: "lazymc::discord", "Invalid Discord public key configured");
+            return false;
+        }
+    };
+    let signature = match unhex(signature).and_then(|s| s.try_into().ok()) {
+        Some(signature) => Signature::from_bytes(&signature),
+        None => return false,
+    };
+
+    let mut message = timestamp.as_bytes().to_vec();
+    message.extend_from_slice(body);
+    key.verify(&message, &signature).is_ok()
+}
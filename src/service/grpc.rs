@@ -0,0 +1,158 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status};
+
+use crate::config::Config;
+use crate::event;
+use crate::server::Server;
+use crate::util;
+
+/// Generated gRPC protocol.
+pub mod proto {
+    tonic::include_proto!("lazymc");
+}
+
+use proto::lazymc_server::{Lazymc, LazymcServer};
+
+/// gRPC server task.
+///
+/// Serves the control API with Start, Stop, Status and Watch calls.
+pub async fn service(config: Arc<Config>, server: Arc<Server>) {
+    // gRPC must be enabled
+    if !config.grpc.enabled {
+        return;
+    }
+
+    // Require token when reachable from other hosts
+    let address = config.grpc.address;
+    let token = config.grpc.token.clone().filter(|token| !token.is_empty());
+    if token.is_none() && !address.ip().is_loopback() {
+        error!(target: "lazymc::grpc", "Not serving gRPC API on {}, a token is required on non-loopback addresses", address);
+        return;
+    }
+
+    info!(target: "lazymc::grpc", "Serving gRPC API on {}", address);
+
+    #[allow(clippy::result_large_err)]
+    let interceptor = move |request| authorize(token.as_deref(), request);
+    let service = LazymcServer::with_interceptor(Service { config, server }, interceptor);
+    let result = tonic::transport::Server::builder()
+        .add_service(service)
+        .serve(address)
+        .await;
+    if let Err(err) = result {
+        error!(target: "lazymc::grpc", "gRPC server failed on {}: {}", address, err);
+    }
+}
+
+/// Check request bearer token, if a token is configured.
+#[allow(clippy::result_large_err)]
+fn authorize(token: Option<&str>, request: Request<()>) -> Result<Request<()>, Status> {
+    let token = match token {
+        Some(token) => token,
+        None => return Ok(request),
+    };
+
+    let authorized = request
+        .metadata()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|value| util::token_eq(value, token))
+        .unwrap_or(false);
+    if authorized {
+        Ok(request)
+    } else {
+        Err(Status::unauthenticated("invalid token"))
+    }
+}
+
+/// gRPC control service.
+struct Service {
+    config: Arc<Config>,
+    server: Arc<Server>,
+}
+
+#[tonic::async_trait]
+impl Lazymc for Service {
+    async fn start(
+        &self,
+        _: Request<proto::StartRequest>,
+    ) -> Result<Response<proto::StartReply>, Status> {
+        // Don't wake server in maintenance mode
+        if self.server.maintenance() {
+            info!(target: "lazymc::grpc", "Not starting server, maintenance mode is enabled");
+            return Ok(Response::new(proto::StartReply { started: false }));
+        }
+
+        let started = Server::start(self.config.clone(), self.server.clone(), None).await;
+        Ok(Response::new(proto::StartReply { started }))
+    }
+
+    async fn stop(
+        &self,
+        _: Request<proto::StopRequest>,
+    ) -> Result<Response<proto::StopReply>, Status> {
+        let stopped = self.server.stop(&self.config).await;
+        Ok(Response::new(proto::StopReply { stopped }))
+    }
+
+    async fn status(
+        &self,
+        _: Request<proto::StatusRequest>,
+    ) -> Result<Response<proto::StatusReply>, Status> {
+        let (players_online, players_max) = match self.server.status().as_ref() {
+            Some(status) => (status.players.online, status.players.max),
+            None => (0, 0),
+        };
+
+        Ok(Response::new(proto::StatusReply {
+            state: self.server.state().name().into(),
+            players_online,
+            players_max,
+            pid: self.server.pid().await,
+        }))
+    }
+
+    type WatchStream = Pin<Box<dyn Stream<Item = Result<proto::Event, Status>> + Send>>;
+
+    #[allow(clippy::result_large_err)]
+    async fn watch(
+        &self,
+        _: Request<proto::WatchRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        // Skip events lagging subscribers missed
+        let stream = BroadcastStream::new(self.server.subscribe())
+            .filter_map(|event| event.ok().map(|event| Ok(proto::Event::from(event))));
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+impl From<event::Event> for proto::Event {
+    fn from(event: event::Event) -> Self {
+        use proto::event::Kind;
+
+        let kind = match event {
+            event::Event::State { from, to } => Kind::State(proto::StateChange {
+                from: from.into(),
+                to: to.into(),
+            }),
+            event::Event::Wake { username } => Kind::Wake(proto::Wake { username }),
+            event::Event::Players { online, max } => Kind::Players(proto::Players { online, max }),
+            event::Event::Crash { code } => Kind::Crash(proto::Crash { code }),
+            event::Event::StartFailed { code } => Kind::StartFailed(proto::StartFailed { code }),
+        };
+
+        proto::Event {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            kind: Some(kind),
+        }
+    }
+}
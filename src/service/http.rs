@@ -0,0 +1,297 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, State as Extract};
+use axum::http::{header, Request, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::config::{Config, ReadyWhen, Stats};
+use crate::server::{Server, State};
+use crate::stats;
+use crate::util;
+use crate::wake_link;
+
+/// Wake link page, showing startup progress.
+const WAKE_PAGE: &str = include_str!("../../res/wake.html");
+
+/// Shared state for HTTP handlers.
+type Shared = (Arc<Config>, Arc<Server>);
+
+/// HTTP server task.
+///
+/// Serves health and readiness endpoints, and a WebSocket event stream.
+///
+/// Control endpoints require the configured token. Without a token they are only served on a
+/// loopback address.
+pub async fn service(config: Arc<Config>, server: Arc<Server>) {
+    // HTTP server must be enabled
+    if !config.http.enabled {
+        return;
+    }
+
+    let address = config.http.address;
+    let router = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/wake/:token", get(wake));
+
+    // Control endpoints, require token when reachable from other hosts
+    let control = Router::new()
+        .route("/events", get(events))
+        .route("/stats", get(stats))
+        .route("/maintenance", get(maintenance).post(set_maintenance))
+        .route(
+            "/keep-awake",
+            get(keep_awake)
+                .post(set_keep_awake)
+                .delete(cancel_keep_awake),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            (config.clone(), server.clone()),
+            authorize,
+        ));
+    let router = if has_token(&config) || address.ip().is_loopback() {
+        router.merge(control)
+    } else {
+        warn!(target: "lazymc::http", "Not serving HTTP control endpoints on {}, a token is required on non-loopback addresses", address);
+        router
+    };
+
+    // Discord interactions, authenticated by their request signature
+    #[cfg(feature = "discord")]
+    let router = if config.discord.enabled {
+        router.route(
+            "/discord",
+            axum::routing::post(super::discord::interactions),
+        )
+    } else {
+        router
+    };
+    let router = router.with_state((config, server));
+
+    let builder = match axum::Server::try_bind(&address) {
+        Ok(builder) => builder,
+        Err(err) => {
+            error!(target: "lazymc::http", "Failed to start HTTP server on {}: {}", address, err);
+            return;
+        }
+    };
+
+    info!(target: "lazymc::http", "Serving HTTP endpoints on {}", address);
+    if let Err(err) = builder.serve(router.into_make_service()).await {
+        error!(target: "lazymc::http", "HTTP server failed: {}", err);
+    }
+}
+
+/// Whether a token is configured for control endpoints.
+fn has_token(config: &Config) -> bool {
+    config
+        .http
+        .token
+        .as_ref()
+        .map(|token| !token.is_empty())
+        .unwrap_or(false)
+}
+
+/// Check request bearer token, if a token is configured.
+async fn authorize(
+    Extract((config, _)): Extract<Shared>,
+    request: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let token = match config
+        .http
+        .token
+        .as_deref()
+        .filter(|token| !token.is_empty())
+    {
+        Some(token) => token,
+        None => return next.run(request).await,
+    };
+
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|value| util::token_eq(value, token))
+        .unwrap_or(false);
+    if !authorized {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "invalid token" })),
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
+
+/// Liveness, lazymc is up and accepting connections.
+async fn healthz(Extract((config, server)): Extract<Shared>) -> (StatusCode, Json<Value>) {
+    (
+        status_code(config.http.healthy_status),
+        Json(json!({ "status": "ok", "state": server.state().name() })),
+    )
+}
+
+/// Readiness, depending on configuration lazymc or the backend server is ready.
+async fn readyz(Extract((config, server)): Extract<Shared>) -> (StatusCode, Json<Value>) {
+    let state = server.state();
+    let ready = match config.http.ready_when {
+        ReadyWhen::Listening => true,
+        ReadyWhen::Started => state == State::Started,
+    };
+
+    let code = if ready {
+        config.http.ready_status
+    } else {
+        config.http.not_ready_status
+    };
+
+    (
+        status_code(code),
+        Json(json!({ "ready": ready, "state": state.name() })),
+    )
+}
+
+/// Recorded player statistics.
+async fn stats(Extract((config, _)): Extract<Shared>) -> (StatusCode, Json<Value>) {
+    if !config.stats.enabled {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "stats are disabled" })),
+        );
+    }
+
+    let data = tokio::fs::read_to_string(Stats::file_path(&config))
+        .await
+        .unwrap_or_default();
+    (StatusCode::OK, Json(json!(stats::summarize(&data))))
+}
+
+/// Redeem wake link, wake server and show startup progress.
+async fn wake(
+    Extract((config, server)): Extract<Shared>,
+    Path(token): Path<String>,
+) -> (StatusCode, Html<String>) {
+    if server.maintenance() {
+        let page = WAKE_PAGE.replace("{{message}}", "Server is under maintenance");
+        return (StatusCode::SERVICE_UNAVAILABLE, Html(page));
+    }
+
+    let (code, message) = match wake_link::redeem(&config, &token) {
+        Ok(()) => {
+            info!(target: "lazymc::http", "Waking server through wake link");
+            Server::start(config, server, None).await;
+            (StatusCode::OK, "Waking server")
+        }
+        Err(err) => (StatusCode::FORBIDDEN, err),
+    };
+
+    (code, Html(WAKE_PAGE.replace("{{message}}", message)))
+}
+
+/// Maintenance mode request.
+#[derive(Deserialize)]
+struct MaintenanceRequest {
+    enabled: bool,
+}
+
+/// Get maintenance mode.
+async fn maintenance(Extract((_, server)): Extract<Shared>) -> Json<Value> {
+    Json(json!({ "maintenance": server.maintenance() }))
+}
+
+/// Enable or disable maintenance mode.
+async fn set_maintenance(
+    Extract((_, server)): Extract<Shared>,
+    Json(request): Json<MaintenanceRequest>,
+) -> Json<Value> {
+    server.set_maintenance(request.enabled);
+    Json(json!({ "maintenance": server.maintenance() }))
+}
+
+/// Keep awake request.
+#[derive(Deserialize)]
+struct KeepAwakeRequest {
+    seconds: u64,
+}
+
+/// Get remaining keep awake time.
+async fn keep_awake(Extract((_, server)): Extract<Shared>) -> Json<Value> {
+    keep_awake_response(&server).await
+}
+
+/// Keep server awake for given time.
+async fn set_keep_awake(
+    Extract((config, server)): Extract<Shared>,
+    Json(request): Json<KeepAwakeRequest>,
+) -> (StatusCode, Json<Value>) {
+    if server.maintenance() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "error": "server is under maintenance" })),
+        );
+    }
+
+    let duration = Some(Duration::from_secs(request.seconds));
+    Server::keep_awake(config, server.clone(), duration).await;
+    (StatusCode::OK, keep_awake_response(&server).await)
+}
+
+/// Cancel keeping server awake.
+async fn cancel_keep_awake(Extract((config, server)): Extract<Shared>) -> Json<Value> {
+    Server::keep_awake(config, server.clone(), None).await;
+    keep_awake_response(&server).await
+}
+
+/// Build keep awake response.
+async fn keep_awake_response(server: &Server) -> Json<Value> {
+    Json(json!({ "keep_awake": server.keep_awake_for().await.map(|d| d.as_secs()) }))
+}
+
+/// WebSocket streaming server events as JSON.
+async fn events(ws: WebSocketUpgrade, Extract((_, server)): Extract<Shared>) -> Response {
+    ws.on_upgrade(|socket| stream_events(socket, server))
+}
+
+/// Stream events to WebSocket until it closes.
+async fn stream_events(mut socket: WebSocket, server: Arc<Server>) {
+    let mut events = server.subscribe();
+
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        debug!(target: "lazymc::http", "Event stream lagging, skipped {} events", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if socket.send(Message::Text(event.to_json().to_string())).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// Get status code, falls back to internal server error if invalid.
+fn status_code(code: u16) -> StatusCode {
+    StatusCode::from_u16(code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
@@ -0,0 +1,158 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::net::UdpSocket;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time;
+
+use crate::config::{Config, MetricsFormat};
+use crate::event::Event;
+use crate::metrics::{self, Counters};
+use crate::server::{ResourceUsage, Server};
+
+/// Metrics push task.
+///
+/// Records server events, and periodically pushes metrics to StatsD or InfluxDB.
+pub async fn service(config: Arc<Config>, server: Arc<Server>) {
+    tokio::spawn(record_events(server.clone()));
+
+    // Push must be enabled
+    if !config.metrics.enabled {
+        return;
+    }
+
+    let client = reqwest::Client::new();
+    let mut previous = Counters::default();
+    let mut push_interval =
+        time::interval(Duration::from_secs(config.metrics.interval.max(1) as u64));
+
+    loop {
+        push_interval.tick().await;
+
+        let counters = Counters::snapshot();
+        let players = server.players_online().await;
+        let state = server.state().to_u8();
+        let resources = server.resources().await;
+
+        let result = match config.metrics.format {
+            MetricsFormat::Statsd => {
+                let delta = counters.since(&previous);
+                let data = statsd_lines(&config, state, players, resources, &delta);
+                push_statsd(&config, &data).await
+            }
+            MetricsFormat::Influx => {
+                let data = influx_line(&config, state, players, resources, &counters);
+                push_influx(&config, &client, data).await
+            }
+        };
+
+        match result {
+            Ok(()) => previous = counters,
+            Err(err) => {
+                warn!(target: "lazymc::metrics", "Failed to push metrics to {}: {}", config.metrics.address, err)
+            }
+        }
+    }
+}
+
+/// Record metrics from server events.
+async fn record_events(server: Arc<Server>) {
+    let mut events = server.subscribe();
+    loop {
+        match events.recv().await {
+            Ok(Event::Wake { .. }) => metrics::record_wake(),
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Build StatsD lines, counters are sent as delta since last push.
+fn statsd_lines(
+    config: &Config,
+    state: u8,
+    players: u32,
+    resources: Option<ResourceUsage>,
+    delta: &Counters,
+) -> String {
+    let prefix = &config.metrics.prefix;
+    let mut lines = vec![
+        format!("{prefix}.state:{state}|g"),
+        format!("{prefix}.players:{players}|g"),
+        format!("{prefix}.wakes:{}|c", delta.wakes),
+        format!("{prefix}.connections:{}|c", delta.connections),
+        format!("{prefix}.proxy.bytes_to_server:{}|c", delta.bytes_to_server),
+        format!("{prefix}.proxy.bytes_to_client:{}|c", delta.bytes_to_client),
+        format!("{prefix}.proxy.sessions:{}|c", delta.sessions),
+        format!("{prefix}.proxy.session_seconds:{}|c", delta.session_seconds),
+    ];
+    if let Some(usage) = resources {
+        lines.push(format!("{prefix}.memory:{}|g", usage.memory));
+        lines.push(format!("{prefix}.cpu:{:.1}|g", usage.cpu));
+    }
+    lines.join("\n")
+}
+
+/// Build InfluxDB line, counters are sent as totals.
+fn influx_line(
+    config: &Config,
+    state: u8,
+    players: u32,
+    resources: Option<ResourceUsage>,
+    counters: &Counters,
+) -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let name = config.log.name.as_deref().unwrap_or("lazymc");
+    let resources = match resources {
+        Some(usage) => format!(",memory={}i,cpu={:.1}", usage.memory, usage.cpu),
+        None => String::new(),
+    };
+    format!(
+        "{},name={} state={}i,players={}i,wakes={}i,connections={}i,bytes_to_server={}i,bytes_to_client={}i,sessions={}i,session_seconds={}i{} {}",
+        config.metrics.prefix,
+        name.replace(' ', "\\ ").replace(',', "\\,").replace('=', "\\="),
+        state,
+        players,
+        counters.wakes,
+        counters.connections,
+        counters.bytes_to_server,
+        counters.bytes_to_client,
+        counters.sessions,
+        counters.session_seconds,
+        resources,
+        timestamp,
+    )
+}
+
+/// Send StatsD data over UDP.
+async fn push_statsd(config: &Config, data: &str) -> Result<(), String> {
+    let socket = UdpSocket::bind("0.0.0.0:0")
+        .await
+        .map_err(|err| err.to_string())?;
+    socket
+        .send_to(data.as_bytes(), &config.metrics.address)
+        .await
+        .map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+/// Send InfluxDB line protocol data over HTTP.
+async fn push_influx(
+    config: &Config,
+    client: &reqwest::Client,
+    data: String,
+) -> Result<(), String> {
+    let mut request = client.post(&config.metrics.address).body(data);
+    if let Some(token) = &config.metrics.token {
+        request = request.header("Authorization", format!("Token {token}"));
+    }
+    request
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| err.to_string())?;
+    Ok(())
+}
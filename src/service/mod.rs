@@ -1,5 +1,22 @@
+#[cfg(unix)]
+pub mod control;
+#[cfg(feature = "discord")]
+pub mod discord;
 pub mod file_watcher;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod http;
+pub mod metrics;
 pub mod monitor;
+pub mod prewarm;
 pub mod probe;
+pub mod resources;
 pub mod server;
 pub mod signal;
+#[cfg(unix)]
+pub mod socket;
+pub mod state_file;
+pub mod tcp;
+pub mod telegram;
+pub mod udp;
+pub mod updater;
@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use chrono::Local;
+
+use crate::config::Config;
+use crate::server::{Server, State};
+
+/// Pre-warm task.
+///
+/// Proactively wakes the server at configured times. Normal sleep rules apply afterwards.
+pub async fn service(config: Arc<Config>, server: Arc<Server>) {
+    if config.time.prewarm.is_empty() {
+        return;
+    }
+
+    loop {
+        // Find next pre-warm time
+        let now = Local::now();
+        let next = match config
+            .time
+            .prewarm
+            .iter()
+            .filter_map(|time| time.next_after(now))
+            .min()
+        {
+            Some(next) => next,
+            None => return,
+        };
+        debug!(target: "lazymc::prewarm", "Next server pre-warm at {}", next.format("%a %H:%M"));
+
+        // Wait until pre-warm time, recheck clock if it's changed in the meantime
+        let wait = (next - now).to_std().unwrap_or_default();
+        tokio::time::sleep(wait).await;
+        if Local::now() < next {
+            continue;
+        }
+
+        if server.maintenance() {
+            info!(target: "lazymc::prewarm", "Not pre-warming server because maintenance mode is enabled");
+            continue;
+        }
+        if server.state() != State::Started {
+            info!(target: "lazymc::prewarm", "Pre-warming server as scheduled");
+        }
+        Server::start(config.clone(), server.clone(), None).await;
+    }
+}
@@ -0,0 +1,108 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::time;
+
+use crate::config::{Config, MemoryAction};
+use crate::os;
+use crate::server::{ResourceUsage, Server, State};
+
+/// Number of bytes in a megabyte.
+const MEGABYTE: u64 = 1024 * 1024;
+
+/// Resource monitor task.
+///
+/// Periodically samples memory and CPU usage of the server process while it is running.
+pub async fn service(config: Arc<Config>, server: Arc<Server>) {
+    // Resource monitoring must be enabled
+    if !config.resources.enabled {
+        return;
+    }
+
+    let mut poll_interval =
+        time::interval(Duration::from_secs(config.resources.interval.max(1) as u64));
+
+    loop {
+        poll_interval.tick().await;
+
+        // Only sample while the server is online
+        if server.state() != State::Started {
+            continue;
+        }
+
+        // Sample process, skip if not supported or not running
+        let usage = match sample(&server).await {
+            Some(usage) => usage,
+            None => continue,
+        };
+        trace!(
+            target: "lazymc::resources",
+            "Server process uses {}MB memory, {:.1}% CPU",
+            usage.memory / MEGABYTE,
+            usage.cpu,
+        );
+        server.set_resources(Some(usage)).await;
+
+        // Handle memory ceiling
+        if config.resources.memory_limit > 0
+            && usage.memory / MEGABYTE >= config.resources.memory_limit
+        {
+            exceeded_memory(&config, &server, usage).await;
+        }
+    }
+}
+
+/// Sample resource usage of the server process.
+///
+/// Returns `None` if the server process is not running or if it could not be sampled.
+async fn sample(server: &Server) -> Option<ResourceUsage> {
+    let pid = server.pid().await?;
+    let stats = os::process_stats(pid)?;
+    let now = Instant::now();
+
+    // Determine CPU usage since previous sample
+    let cpu = match server.resources().await {
+        Some(prev) if stats.cpu_time >= prev.cpu_time => {
+            let elapsed = now.duration_since(prev.sampled_at).as_secs_f32();
+            if elapsed > 0.0 {
+                (stats.cpu_time - prev.cpu_time).as_secs_f32() / elapsed * 100.0
+            } else {
+                prev.cpu
+            }
+        }
+        _ => 0.0,
+    };
+
+    Some(ResourceUsage {
+        memory: stats.memory,
+        cpu,
+        cpu_time: stats.cpu_time,
+        sampled_at: now,
+    })
+}
+
+/// Handle server process exceeding the configured memory ceiling.
+async fn exceeded_memory(config: &Config, server: &Server, usage: ResourceUsage) {
+    warn!(
+        target: "lazymc::resources",
+        "Server process exceeds memory limit ({}MB >= {}MB)",
+        usage.memory / MEGABYTE,
+        config.resources.memory_limit,
+    );
+
+    match config.resources.memory_action {
+        MemoryAction::Warn => {}
+        MemoryAction::Restart => {
+            // Only restart once nobody is playing
+            if server.players_online().await > 0 {
+                debug!(target: "lazymc::resources", "Not restarting server for memory limit, players are online");
+                return;
+            }
+
+            info!(target: "lazymc::resources", "Restarting server to release memory...");
+            if !server.restart(config).await {
+                warn!(target: "lazymc::resources", "Failed to restart server");
+            }
+        }
+    }
+}
@@ -4,10 +4,14 @@ use std::sync::Arc;
 use bytes::BytesMut;
 use futures::FutureExt;
 use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::{self, Runtime};
+use tracing::Instrument;
 
-use crate::config::Config;
+use crate::config::{Config, Runtime as ConfigRuntime, RuntimeFlavor, Stats};
+use crate::metrics;
+use crate::net;
 use crate::proto::client::Client;
-use crate::proxy::{self, ProxyHeader};
+use crate::proxy::{self, ProxyHeader, ProxyOptions, Target};
 use crate::server::{self, Server};
 use crate::service;
 use crate::status;
@@ -17,21 +21,96 @@ use crate::util::error::{quit_error, ErrorHints};
 ///
 /// Main entrypoint to start all server/status/proxy logic.
 ///
-/// Spawns a tokio runtime to complete all work on.
-#[tokio::main(flavor = "multi_thread")]
-pub async fn service(config: Arc<Config>) -> Result<(), ()> {
-    // Load server state
-    let server = Arc::new(Server::default());
+/// Spawns a tokio runtime to complete all work on, configured through `runtime`.
+pub fn service(config: Arc<Config>) -> Result<(), ()> {
+    let runtime = match build_runtime(&config.runtime) {
+        Ok(runtime) => runtime,
+        Err(err) => quit_error(
+            anyhow!(err).context("Failed to start tokio runtime"),
+            ErrorHints::default(),
+        ),
+    };
+    runtime.block_on(serve(config, Arc::new(Server::default())))
+}
+
+/// Build tokio runtime with the configured flavor and worker threads.
+fn build_runtime(config: &ConfigRuntime) -> std::io::Result<Runtime> {
+    let mut builder = match config.flavor {
+        RuntimeFlavor::CurrentThread => runtime::Builder::new_current_thread(),
+        RuntimeFlavor::MultiThread => {
+            let mut builder = runtime::Builder::new_multi_thread();
+            if let Some(threads) = config.worker_threads {
+                builder.worker_threads(threads.max(1));
+            }
+            builder
+        }
+    };
+    if let Some(threads) = config.max_blocking_threads {
+        builder.max_blocking_threads(threads.max(1));
+    }
+    debug!(target: "lazymc", "Starting {:?} tokio runtime", config.flavor);
+    builder.enable_all().build()
+}
+
+/// Serve lazymc for the given server.
+///
+/// Binds the public address, spawns all services and routes incoming connections until the
+/// listener fails. Must be called from within a tokio runtime.
+///
+/// This also installs a signal handler that stops the server and quits the process on CTRL+C.
+pub async fn serve(config: Arc<Config>, server: Arc<Server>) -> Result<(), ()> {
+    // Initialize tracing exporter
+    #[cfg(feature = "otel")]
+    crate::telemetry::init(&config.telemetry);
+    #[cfg(not(feature = "otel"))]
+    if config.telemetry.enabled {
+        warn!(target: "lazymc", "Telemetry is enabled in config, but lazymc is built without 'otel' feature, ignoring");
+    }
+
+    // Load plugins
+    #[cfg(feature = "wasm")]
+    {
+        crate::plugin::load(&config);
+        tokio::spawn(crate::plugin::service(server.clone()));
+    }
+    #[cfg(not(feature = "wasm"))]
+    if !config.plugins.wasm.is_empty() {
+        warn!(target: "lazymc", "Plugins are configured, but lazymc is built without 'wasm' feature, ignoring");
+    }
+
+    // Load scripts
+    #[cfg(feature = "rhai")]
+    {
+        crate::script::load(&config);
+        tokio::spawn(crate::script::service(server.clone()));
+    }
+    #[cfg(not(feature = "rhai"))]
+    if !config.scripts.files.is_empty() {
+        warn!(target: "lazymc", "Scripts are configured, but lazymc is built without 'rhai' feature, ignoring");
+    }
+
+    // Start in maintenance mode if configured
+    server.set_maintenance(config.maintenance.enabled);
+
+    // Record player statistics
+    crate::stats::init(&config);
+    tokio::spawn(crate::stats::service(server.clone()));
+
+    // Seed adaptive timeouts from recorded stats
+    if config.server.adaptive_timeouts.enabled && crate::stats::enabled() {
+        if let Ok(data) = tokio::fs::read_to_string(Stats::file_path(&config)).await {
+            let (starts, stops) = crate::stats::durations(&data);
+            server.seed_durations(starts, stops).await;
+        }
+    }
 
     // Listen for new connections
-    let listener = TcpListener::bind(config.public.address)
-        .await
-        .map_err(|err| {
-            quit_error(
-                anyhow!(err).context("Failed to start proxy server"),
-                ErrorHints::default(),
-            );
-        })?;
+    let mut listeners = bind(&config).await.map_err(|err| {
+        quit_error(
+            anyhow!(err).context("Failed to start proxy server"),
+            ErrorHints::default(),
+        );
+    })?;
 
     info!(
         target: "lazymc",
@@ -46,33 +125,101 @@ pub async fn service(config: Arc<Config>) -> Result<(), ()> {
         );
     }
 
-    // Spawn services: monitor, signal handler
+    // Spawn services: monitor, signal handlers
     tokio::spawn(service::monitor::service(config.clone(), server.clone()));
     tokio::spawn(service::signal::service(config.clone(), server.clone()));
+    #[cfg(unix)]
+    tokio::spawn(service::signal::dump_service(
+        config.clone(),
+        server.clone(),
+    ));
 
-    // Initiate server start
-    if config.server.wake_on_start {
+    // Initiate server start, unless in maintenance
+    if config.server.wake_on_start && !server.maintenance() {
         Server::start(config.clone(), server.clone(), None).await;
     }
 
-    // Spawn additional services: probe and ban manager
+    // Spawn additional services: probe, pre-warm, resource monitor, updater, metrics, HTTP, gRPC, control, state file, TCP and UDP forwards, Telegram, Discord and ban manager
     tokio::spawn(service::probe::service(config.clone(), server.clone()));
+    tokio::spawn(service::prewarm::service(config.clone(), server.clone()));
+    tokio::spawn(service::resources::service(config.clone(), server.clone()));
+    tokio::spawn(service::updater::service(config.clone(), server.clone()));
+    tokio::spawn(service::metrics::service(config.clone(), server.clone()));
+    tokio::spawn(service::http::service(config.clone(), server.clone()));
+    #[cfg(unix)]
+    tokio::spawn(service::control::service(config.clone(), server.clone()));
+    #[cfg(unix)]
+    tokio::spawn(service::socket::service(config.clone()));
+    #[cfg(not(unix))]
+    if config.public.socket.is_some() {
+        warn!(target: "lazymc", "Public Unix socket is only supported on Unix, ignoring");
+    }
+    #[cfg(feature = "grpc")]
+    tokio::spawn(service::grpc::service(config.clone(), server.clone()));
+    #[cfg(not(feature = "grpc"))]
+    if config.grpc.enabled {
+        warn!(target: "lazymc", "gRPC is enabled in config, but lazymc is built without 'grpc' feature, ignoring");
+    }
+    tokio::spawn(service::state_file::service(config.clone(), server.clone()));
+    tokio::spawn(service::udp::service(config.clone(), server.clone()));
+    tokio::spawn(service::tcp::service(config.clone(), server.clone()));
+    tokio::spawn(service::telegram::service(config.clone(), server.clone()));
+    #[cfg(feature = "discord")]
+    tokio::spawn(service::discord::service(config.clone()));
+    #[cfg(not(feature = "discord"))]
+    if config.discord.enabled {
+        warn!(target: "lazymc", "Discord bot is enabled in config, but lazymc is built without 'discord' feature, ignoring");
+    }
     tokio::task::spawn_blocking({
         let (config, server) = (config.clone(), server.clone());
         || service::file_watcher::service(config, server)
     });
 
-    // Route all incomming connections
-    while let Ok((inbound, _)) = listener.accept().await {
-        route(inbound, config.clone(), server.clone());
+    // Route all incomming connections, spawn additional accept loops
+    let listener = listeners.pop().unwrap();
+    for listener in listeners {
+        tokio::spawn(accept(listener, config.clone(), server.clone()));
     }
+    accept(listener, config, server).await;
 
     Ok(())
 }
 
+/// Bind listeners on the public address.
+///
+/// Binds multiple listeners with SO_REUSEPORT if more than one acceptor is configured. Always
+/// returns at least one listener.
+async fn bind(config: &Config) -> std::io::Result<Vec<TcpListener>> {
+    let acceptors = config.public.acceptors.max(1);
+    if acceptors == 1 {
+        return Ok(vec![TcpListener::bind(config.public.address).await?]);
+    }
+
+    #[cfg(unix)]
+    {
+        debug!(target: "lazymc", "Binding {} acceptors with SO_REUSEPORT", acceptors);
+        (0..acceptors)
+            .map(|_| net::bind_reuseport(config.public.address))
+            .collect()
+    }
+
+    #[cfg(not(unix))]
+    {
+        warn!(target: "lazymc", "Multiple acceptors are only supported on Unix, using one");
+        Ok(vec![TcpListener::bind(config.public.address).await?])
+    }
+}
+
+/// Accept and route all incoming connections on the given listener.
+async fn accept(listener: TcpListener, config: Arc<Config>, server: Arc<Server>) {
+    while let Ok((inbound, _)) = listener.accept().await {
+        route(inbound, config.clone(), server.clone()).await;
+    }
+}
+
 /// Route inbound TCP stream to correct service, spawning a new task.
 #[inline]
-fn route(inbound: TcpStream, config: Arc<Config>, server: Arc<Server>) {
+async fn route(inbound: TcpStream, config: Arc<Config>, server: Arc<Server>) {
     // Get user peer address
     let peer = match inbound.peer_addr() {
         Ok(peer) => peer,
@@ -82,18 +229,45 @@ fn route(inbound: TcpStream, config: Arc<Config>, server: Arc<Server>) {
         }
     };
 
+    // Apply client socket options
+    if let Err(err) = net::configure_socket(&inbound, &config.proxy.client) {
+        warn!(target: "lazymc", "Failed to set client socket options: {}", err);
+    }
+
     // Check ban state, just drop connection if enabled
-    let banned = server.is_banned_ip_blocking(&peer.ip());
+    let banned = server.is_banned_ip(&peer.ip()).await;
     if banned && config.server.drop_banned_ips {
         info!(target: "lazymc", "Connection from banned IP {}, dropping", peer.ip());
         return;
     }
 
+    // Drop connections from IPs temporarily denied by the honeypot
+    if server.honeypot().is_denied(&peer.ip()).await {
+        debug!(target: "lazymc", "Connection from denied IP {}, dropping", peer.ip());
+        return;
+    }
+
+    metrics::record_connection();
+
+    // Trace connection lifecycle, routes below spawn tasks in this span
+    let span = tracing::info_span!(
+        "connection",
+        peer = %peer,
+        route = tracing::field::Empty,
+        protocol = tracing::field::Empty,
+        next_state = tracing::field::Empty,
+    );
+    let _span = span.enter();
+
     // Route connection through proper channel
-    let should_proxy =
-        !banned && server.state() == server::State::Started && !config.lockout.enabled;
-    if should_proxy {
-        route_proxy(inbound, config)
+    let should_proxy = !banned
+        && server.state() == server::State::Started
+        && !config.lockout.enabled
+        && !server.maintenance();
+    if should_proxy && config.proxy.status_coalesce > 0 {
+        route_status_coalesced(inbound, config, server, peer)
+    } else if should_proxy {
+        route_proxy(inbound, config, &server)
     } else {
         route_status(inbound, config, server, peer)
     }
@@ -103,6 +277,7 @@ fn route(inbound: TcpStream, config: Arc<Config>, server: Arc<Server>) {
 #[inline]
 fn route_status(inbound: TcpStream, config: Arc<Config>, server: Arc<Server>, peer: SocketAddr) {
     // When server is not online, spawn a status server
+    tracing::Span::current().record("route", "status");
     let client = Client::new(peer);
     let service = status::serve(client, inbound, config, server).map(|r| {
         if let Err(err) = r {
@@ -110,17 +285,42 @@ fn route_status(inbound: TcpStream, config: Arc<Config>, server: Arc<Server>, pe
         }
     });
 
-    tokio::spawn(service);
+    tokio::spawn(service.in_current_span());
+}
+
+/// Route inbound TCP stream to coalescing status server, spawning a new task.
+///
+/// Proxies the stream once it turns out not to be a status request.
+#[inline]
+fn route_status_coalesced(
+    inbound: TcpStream,
+    config: Arc<Config>,
+    server: Arc<Server>,
+    peer: SocketAddr,
+) {
+    tracing::Span::current().record("route", "status_coalesced");
+    let client = Client::new(peer);
+    let service = status::serve_coalesced(client, inbound, config, server).map(|r| {
+        if let Err(err) = r {
+            warn!(target: "lazymc", "Failed to serve coalesced status: {:?}", err);
+        }
+    });
+
+    tokio::spawn(service.in_current_span());
 }
 
 /// Route inbound TCP stream to proxy, spawning a new task.
 #[inline]
-fn route_proxy(inbound: TcpStream, config: Arc<Config>) {
+fn route_proxy(inbound: TcpStream, config: Arc<Config>, server: &Server) {
     // When server is online, proxy all
+    tracing::Span::current().record("route", "proxy");
+    let target = Target::server(&config, server);
+    let span = tracing::info_span!("proxy", server = %target);
     let service = proxy::proxy(
         inbound,
         ProxyHeader::Proxy.not_none(config.server.send_proxy_v2),
-        config.server.address,
+        target,
+        ProxyOptions::for_server(&config, server),
     )
     .map(|r| {
         if let Err(err) = r {
@@ -128,31 +328,39 @@ fn route_proxy(inbound: TcpStream, config: Arc<Config>) {
         }
     });
 
-    tokio::spawn(service);
+    tokio::spawn(service.instrument(span));
 }
 
 /// Route inbound TCP stream to proxy with queued data, spawning a new task.
 #[inline]
-pub fn route_proxy_queue(inbound: TcpStream, config: Arc<Config>, queue: BytesMut) {
+pub fn route_proxy_queue(
+    inbound: TcpStream,
+    config: Arc<Config>,
+    server: &Server,
+    queue: BytesMut,
+) {
     route_proxy_address_queue(
         inbound,
         ProxyHeader::Proxy.not_none(config.server.send_proxy_v2),
-        config.server.address,
+        Target::server(&config, server),
         queue,
+        ProxyOptions::for_server(&config, server),
     );
 }
 
-/// Route inbound TCP stream to proxy with given address and queued data, spawning a new task.
+/// Route inbound TCP stream to proxy with given target and queued data, spawning a new task.
 #[inline]
 pub fn route_proxy_address_queue(
     inbound: TcpStream,
     proxy_header: ProxyHeader,
-    addr: SocketAddr,
+    target: Target,
     queue: BytesMut,
+    options: ProxyOptions,
 ) {
     // When server is online, proxy all
+    let span = tracing::info_span!("proxy", server = %target);
     let service = async move {
-        proxy::proxy_with_queue(inbound, proxy_header, addr, &queue)
+        proxy::proxy_with_queue(inbound, proxy_header, target, &queue, options)
             .map(|r| {
                 if let Err(err) = r {
                     warn!(target: "lazymc", "Failed to proxy: {}", err);
@@ -161,5 +369,5 @@ pub fn route_proxy_address_queue(
             .await
     };
 
-    tokio::spawn(service);
+    tokio::spawn(service.instrument(span));
 }
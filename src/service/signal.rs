@@ -1,14 +1,30 @@
 use std::sync::Arc;
+use std::time::Duration;
+
+use once_cell::sync::OnceCell;
+use tokio::sync::Notify;
+use tokio::time;
 
 use crate::config::Config;
+use crate::daemon;
 use crate::server::{self, Server};
 use crate::util::error;
 
+/// Notifier for shutdown requests from outside of a signal.
+static SHUTDOWN: OnceCell<Notify> = OnceCell::new();
+
 /// Signal handler task.
 pub async fn service(config: Arc<Config>, server: Arc<Server>) {
+    let shutdown = SHUTDOWN.get_or_init(Notify::new);
+
     loop {
-        // Wait for SIGTERM/SIGINT signal
-        tokio::signal::ctrl_c().await.unwrap();
+        // Wait for SIGTERM/SIGINT signal or shutdown request
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {},
+            _ = shutdown.notified() => {
+                shutdown_gracefully(&config, &server).await;
+            },
+        }
 
         // Quit if stopped
         if server.state() == server::State::Stopped {
@@ -25,8 +41,70 @@ pub async fn service(config: Arc<Config>, server: Arc<Server>) {
     }
 }
 
+/// Diagnostics signal handler task.
+///
+/// Dumps a diagnostic snapshot to the log on SIGUSR1.
+#[cfg(unix)]
+pub async fn dump_service(config: Arc<Config>, server: Arc<Server>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut signal = match signal(SignalKind::user_defined1()) {
+        Ok(signal) => signal,
+        Err(err) => {
+            warn!(target: "lazymc::diagnostics", "Failed to listen for SIGUSR1, diagnostic dumps unavailable: {}", err);
+            return;
+        }
+    };
+
+    while signal.recv().await.is_some() {
+        info!(target: "lazymc::diagnostics", "Diagnostic dump requested through SIGUSR1:");
+        for line in server.dump(&config).await.lines() {
+            info!(target: "lazymc::diagnostics", "{}", line);
+        }
+    }
+}
+
+/// Request lazymc to stop the server and quit.
+///
+/// Used when running as service, where there is no signal to handle.
+#[allow(unused)]
+pub fn request_shutdown() {
+    SHUTDOWN.get_or_init(Notify::new).notify_one();
+}
+
+/// Stop server and wait for it to be stopped, then quit.
+async fn shutdown_gracefully(config: &Config, server: &Server) -> ! {
+    if server.state() != server::State::Stopped {
+        info!(target: "lazymc", "Stopping server before quitting...");
+        server.stop(config).await;
+
+        // Wait for server to stop, server is force killed after the stop timeout
+        let timeout = Duration::from_secs(server.stop_timeout(config).await as u64 + 10);
+        let _ = time::timeout(
+            timeout,
+            server.wait_for_state(|state| state == server::State::Stopped),
+        )
+        .await;
+    }
+
+    quit();
+}
+
 /// Gracefully quit.
 fn quit() -> ! {
     // TODO: gracefully quit self
+
+    daemon::remove_pid_file();
+    #[cfg(unix)]
+    crate::service::control::remove_socket();
+    #[cfg(unix)]
+    crate::service::socket::remove_socket();
+
+    #[cfg(feature = "otel")]
+    crate::telemetry::shutdown();
+
+    #[cfg(windows)]
+    crate::action::service::report_stopped();
+
     error::quit();
 }
@@ -0,0 +1,81 @@
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use once_cell::sync::OnceCell;
+use tokio::io;
+use tokio::net::{TcpStream, UnixListener, UnixStream};
+use tokio::time;
+
+use crate::config::{Config, Public};
+
+/// Delay before accepting again after failing to accept a connection, such as when out of file
+/// descriptors.
+const ACCEPT_ERROR_DELAY: Duration = Duration::from_millis(100);
+
+/// Bound public socket path, removed when quitting.
+static SOCKET: OnceCell<PathBuf> = OnceCell::new();
+
+/// Public Unix socket task.
+///
+/// Accepts clients on the public Unix socket and bridges them to the public TCP listener, so they
+/// go through the same routing. Bridged clients appear to connect from the loopback address.
+pub async fn service(config: Arc<Config>) {
+    let path = match Public::socket_path(&config) {
+        Some(path) => path,
+        None => return,
+    };
+
+    // Remove stale socket, don't take over socket of running instance
+    if path.exists() {
+        if UnixStream::connect(&path).await.is_ok() {
+            warn!(target: "lazymc::socket", "Public socket {} is used by another process, not serving", path.display());
+            return;
+        }
+        let _ = fs::remove_file(&path);
+    }
+
+    let listener = match UnixListener::bind(&path) {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!(target: "lazymc::socket", "Failed to bind public socket {}: {}", path.display(), err);
+            return;
+        }
+    };
+    let target = Public::local_address(&config);
+    info!(target: "lazymc", "Proxying public socket {} to {}", path.display(), target);
+    let _ = SOCKET.set(path);
+
+    loop {
+        let inbound = match listener.accept().await {
+            Ok((inbound, _)) => inbound,
+            Err(err) => {
+                error!(target: "lazymc::socket", "Failed to accept connection: {}", err);
+                time::sleep(ACCEPT_ERROR_DELAY).await;
+                continue;
+            }
+        };
+
+        tokio::spawn(async move {
+            if let Err(err) = bridge(inbound, target).await {
+                debug!(target: "lazymc::socket", "Public socket connection failed: {}", err);
+            }
+        });
+    }
+}
+
+/// Bridge Unix socket client to the public listener.
+async fn bridge(mut inbound: UnixStream, target: SocketAddr) -> io::Result<()> {
+    let mut outbound = TcpStream::connect(target).await?;
+    io::copy_bidirectional(&mut inbound, &mut outbound).await?;
+    Ok(())
+}
+
+/// Remove bound public socket, if any.
+pub fn remove_socket() {
+    if let Some(path) = SOCKET.get() {
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,60 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tokio::fs;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::config::{Config, StateFile};
+use crate::event::Event;
+use crate::server::Server;
+
+/// State file task.
+///
+/// Writes a JSON snapshot of the server state to a file on every state or player count change.
+pub async fn service(config: Arc<Config>, server: Arc<Server>) {
+    // State file must be enabled
+    if !config.state_file.enabled {
+        return;
+    }
+
+    let path = StateFile::file_path(&config);
+    let mut events = server.subscribe();
+    loop {
+        write(&config, &server, &path).await;
+
+        match events.recv().await {
+            Ok(Event::State { .. } | Event::Players { .. }) | Err(RecvError::Lagged(_)) => {}
+            Ok(_) => continue,
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Atomically write state snapshot to file.
+async fn write(config: &Config, server: &Server, path: &Path) {
+    let mut snapshot = server.snapshot(config).await;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    if let Some(object) = snapshot.as_object_mut() {
+        let since = object
+            .get("state_for")
+            .and_then(|state_for| state_for.as_u64())
+            .map(|secs| now.saturating_sub(secs));
+        object.insert("since".into(), since.into());
+        object.insert("time".into(), now.into());
+    }
+
+    // Write to temporary file and rename, so readers never see a partial file
+    let tmp = path.with_extension("tmp");
+    let data = snapshot.to_string();
+    let result = match fs::write(&tmp, data).await {
+        Ok(()) => fs::rename(&tmp, path).await,
+        Err(err) => Err(err),
+    };
+    if let Err(err) = result {
+        warn!(target: "lazymc", "Failed to write state file {}: {}", path.display(), err);
+    }
+}
@@ -0,0 +1,133 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{self, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time;
+
+use crate::config::{Config, TcpForward};
+use crate::net;
+use crate::server::{Server, State};
+
+/// Default placeholder page, served while the server is not started.
+const PLACEHOLDER_PAGE: &str = include_str!("../../res/sleeping.html");
+
+/// Delay before accepting again after failing to accept a connection, such as when out of file
+/// descriptors.
+const ACCEPT_ERROR_DELAY: Duration = Duration::from_millis(100);
+
+/// Time to wait for a request before serving the placeholder page anyway.
+const PLACEHOLDER_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// TCP forwarding task.
+///
+/// Forwards configured TCP ports while the server is started, and serves a placeholder page
+/// otherwise.
+pub async fn service(config: Arc<Config>, server: Arc<Server>) {
+    for forward in &config.proxy.tcp {
+        let page = match placeholder(&config, forward).await {
+            Some(page) => Arc::new(page),
+            None => continue,
+        };
+
+        let listener = match TcpListener::bind(forward.public).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                error!(target: "lazymc::tcp", "Failed to bind TCP forward on {}: {}", forward.public, err);
+                continue;
+            }
+        };
+        debug!(target: "lazymc::tcp", "Forwarding TCP {} to {}", forward.public, forward.server);
+
+        tokio::spawn(serve(
+            listener,
+            forward.server,
+            page,
+            config.clone(),
+            server.clone(),
+        ));
+    }
+}
+
+/// Load placeholder page for forward.
+async fn placeholder(config: &Config, forward: &TcpForward) -> Option<String> {
+    let path = match &forward.placeholder {
+        Some(path) => match config.path.as_ref().and_then(|p| p.parent()) {
+            Some(config_dir) => config_dir.join(path),
+            None => path.clone(),
+        },
+        None => return Some(PLACEHOLDER_PAGE.into()),
+    };
+
+    match tokio::fs::read_to_string(&path).await {
+        Ok(page) => Some(page),
+        Err(err) => {
+            error!(target: "lazymc::tcp", "Failed to load placeholder page {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+/// Accept and route connections for single TCP forward.
+async fn serve(
+    listener: TcpListener,
+    target: SocketAddr,
+    page: Arc<String>,
+    config: Arc<Config>,
+    server: Arc<Server>,
+) {
+    loop {
+        let inbound = match listener.accept().await {
+            Ok((inbound, _)) => inbound,
+            Err(err) => {
+                error!(target: "lazymc::tcp", "Failed to accept connection: {}", err);
+                time::sleep(ACCEPT_ERROR_DELAY).await;
+                continue;
+            }
+        };
+        if let Err(err) = net::configure_socket(&inbound, &config.proxy.client) {
+            debug!(target: "lazymc::tcp", "Failed to configure client socket: {}", err);
+        }
+
+        let (config, page) = (config.clone(), page.clone());
+        let started = server.state() == State::Started;
+        tokio::spawn(async move {
+            let result = if started {
+                forward(inbound, target, &config).await
+            } else {
+                respond_placeholder(inbound, &page).await
+            };
+            if let Err(err) = result {
+                debug!(target: "lazymc::tcp", "TCP forward connection failed: {}", err);
+            }
+        });
+    }
+}
+
+/// Forward connection to server.
+async fn forward(mut inbound: TcpStream, target: SocketAddr, config: &Config) -> io::Result<()> {
+    let mut outbound = TcpStream::connect(target).await?;
+    if let Err(err) = net::configure_socket(&outbound, &config.proxy.server) {
+        debug!(target: "lazymc::tcp", "Failed to configure server socket: {}", err);
+    }
+    io::copy_bidirectional(&mut inbound, &mut outbound).await?;
+    Ok(())
+}
+
+/// Respond with placeholder HTTP page and close connection.
+async fn respond_placeholder(mut inbound: TcpStream, page: &str) -> io::Result<()> {
+    // Consume request, we respond the same regardless
+    let mut buf = [0; 1024];
+    if let Ok(result) = time::timeout(PLACEHOLDER_READ_TIMEOUT, inbound.read(&mut buf)).await {
+        result?;
+    }
+
+    let response = format!(
+        "HTTP/1.1 503 Service Unavailable\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nRetry-After: 30\r\nConnection: close\r\n\r\n{}",
+        page.len(),
+        page
+    );
+    inbound.write_all(response.as_bytes()).await?;
+    inbound.shutdown().await
+}
@@ -0,0 +1,179 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::config::Config;
+use crate::event::Event;
+use crate::server::Server;
+
+/// Telegram Bot API base URL.
+const API_URL: &str = "https://api.telegram.org";
+
+/// Long polling timeout in seconds when fetching updates.
+const POLL_TIMEOUT: u64 = 30;
+
+/// Delay before retrying after failing to fetch updates.
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// Telegram bot task.
+///
+/// Pushes server state changes to the configured chat, and handles commands.
+pub async fn service(config: Arc<Config>, server: Arc<Server>) {
+    // Telegram bot must be enabled
+    if !config.telegram.enabled {
+        return;
+    }
+
+    info!(target: "lazymc::telegram", "Starting Telegram bot");
+
+    let client = reqwest::Client::new();
+    tokio::join!(
+        notify(&client, &config, &server),
+        commands(&client, config.clone(), server.clone()),
+    );
+}
+
+/// Push server events to configured chat.
+async fn notify(client: &reqwest::Client, config: &Config, server: &Server) {
+    let chat_id = match config.telegram.chat_id {
+        Some(chat_id) => chat_id,
+        None => return,
+    };
+
+    let mut events = server.subscribe();
+    loop {
+        let text = match events.recv().await {
+            Ok(Event::State { to, .. }) => format!("Server is now {to}"),
+            Ok(Event::Wake {
+                username: Some(username),
+            }) => format!("Server is woken by {username}"),
+            Ok(Event::Crash { code }) => match code {
+                Some(code) => format!("Server crashed with exit code {code}"),
+                None => "Server crashed".into(),
+            },
+            Ok(Event::StartFailed { code }) => match code {
+                Some(code) => format!("Server failed to start with exit code {code}"),
+                None => "Server failed to start".into(),
+            },
+            Ok(_) | Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        };
+        send(client, config, chat_id, &text).await;
+    }
+}
+
+/// Poll for and handle bot commands.
+async fn commands(client: &reqwest::Client, config: Arc<Config>, server: Arc<Server>) {
+    let url = api_url(&config, "getUpdates");
+    let mut offset = 0;
+
+    loop {
+        let updates = client
+            .get(&url)
+            .query(&[("offset", offset), ("timeout", POLL_TIMEOUT)])
+            .timeout(Duration::from_secs(POLL_TIMEOUT) + RETRY_DELAY)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        let updates: Value = match updates {
+            Ok(response) => match response.json().await {
+                Ok(updates) => updates,
+                Err(err) => {
+                    warn!(target: "lazymc::telegram", "Failed to parse Telegram updates: {}", err.without_url());
+                    tokio::time::sleep(RETRY_DELAY).await;
+                    continue;
+                }
+            },
+            Err(err) => {
+                warn!(target: "lazymc::telegram", "Failed to fetch Telegram updates: {}", err.without_url());
+                tokio::time::sleep(RETRY_DELAY).await;
+                continue;
+            }
+        };
+
+        for update in updates["result"].as_array().into_iter().flatten() {
+            if let Some(id) = update["update_id"].as_u64() {
+                offset = offset.max(id + 1);
+            }
+
+            let message = &update["message"];
+            let (chat_id, text) = match (message["chat"]["id"].as_i64(), message["text"].as_str()) {
+                (Some(chat_id), Some(text)) => (chat_id, text),
+                _ => continue,
+            };
+
+            // Strip bot name suffix from command, as used in group chats
+            let command = text.split_whitespace().next().unwrap_or_default();
+            let command = command.split('@').next().unwrap_or_default();
+
+            let reply = match command {
+                "/status" => Some(status(&config, &server).await),
+                "/wake" => {
+                    let user = message["from"]["id"].as_i64();
+                    Some(wake(config.clone(), server.clone(), user).await)
+                }
+                _ => None,
+            };
+            if let Some(reply) = reply {
+                send(client, &config, chat_id, &reply).await;
+            }
+        }
+    }
+}
+
+/// Build server status message.
+async fn status(config: &Config, server: &Server) -> String {
+    let status = server.snapshot(config).await;
+    let mut text = format!(
+        "Server is {}",
+        status["state"].as_str().unwrap_or("unknown")
+    );
+    if let (Some(online), Some(max)) = (
+        status["players"]["online"].as_u64(),
+        status["players"]["max"].as_u64(),
+    ) {
+        text.push_str(&format!(", {online}/{max} players online"));
+    }
+    if status["maintenance"].as_bool() == Some(true) {
+        text.push_str(", under maintenance");
+    }
+    text
+}
+
+/// Wake server on behalf of the given user.
+async fn wake(config: Arc<Config>, server: Arc<Server>, user: Option<i64>) -> String {
+    let allowed = &config.telegram.allowed_users;
+    if !user.map_or(false, |user| allowed.contains(&user)) {
+        return "You are not allowed to do that.".into();
+    }
+    if server.maintenance() {
+        return "Server is under maintenance.".into();
+    }
+
+    info!(target: "lazymc::telegram", "Waking server through Telegram");
+    if Server::start(config, server, None).await {
+        "Waking server...".into()
+    } else {
+        "Server is already awake.".into()
+    }
+}
+
+/// Send message to chat.
+async fn send(client: &reqwest::Client, config: &Config, chat_id: i64, text: &str) {
+    let result = client
+        .post(api_url(config, "sendMessage"))
+        .json(&json!({ "chat_id": chat_id, "text": text }))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    if let Err(err) = result {
+        warn!(target: "lazymc::telegram", "Failed to send Telegram message: {}", err.without_url());
+    }
+}
+
+/// Build Bot API method URL.
+fn api_url(config: &Config, method: &str) -> String {
+    format!("{API_URL}/bot{}/{method}", config.telegram.token)
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::net::UdpSocket;
+use tokio::task::{AbortHandle, JoinSet};
+use tokio::time::{self, Instant};
+
+use crate::config::{Config, UdpForward};
+use crate::server::{Server, State};
+
+/// Maximum UDP datagram size.
+const MAX_DATAGRAM_SIZE: usize = 65536;
+
+/// Time after which a client session without server responses or client datagrams is dropped.
+const SESSION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Maximum number of concurrent client sessions per forward.
+///
+/// Datagrams from new clients are dropped while at this limit.
+const MAX_SESSIONS: usize = 1024;
+
+/// Delay before retrying to bind a public UDP address.
+const BIND_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// UDP forwarding task.
+///
+/// Forwards configured UDP ports while the server is started. Public sockets are closed when it
+/// isn't, so clients don't reach anything.
+pub async fn service(config: Arc<Config>, server: Arc<Server>) {
+    for forward in &config.proxy.udp {
+        tokio::spawn(serve(*forward, server.clone()));
+    }
+}
+
+/// Serve single UDP forward.
+async fn serve(forward: UdpForward, server: Arc<Server>) {
+    loop {
+        server.wait_for_state(|state| state == State::Started).await;
+
+        let socket = match UdpSocket::bind(forward.public).await {
+            Ok(socket) => socket,
+            Err(err) => {
+                error!(target: "lazymc::udp", "Failed to bind UDP forward on {}: {}", forward.public, err);
+                time::sleep(BIND_RETRY_DELAY).await;
+                continue;
+            }
+        };
+
+        debug!(target: "lazymc::udp", "Forwarding UDP {} to {}", forward.public, forward.server);
+        tokio::select! {
+            result = relay(socket, forward.server) => {
+                if let Err(err) = result {
+                    error!(target: "lazymc::udp", "UDP forward on {} failed: {}", forward.public, err);
+                    time::sleep(BIND_RETRY_DELAY).await;
+                }
+            }
+            _ = server.wait_for_state(|state| state != State::Started) => {}
+        }
+        debug!(target: "lazymc::udp", "Stopped forwarding UDP {}", forward.public);
+    }
+}
+
+/// Client session, relaying to the server through its own socket.
+struct Session {
+    /// Socket connected to the server.
+    outbound: Arc<UdpSocket>,
+
+    /// Handle to abort the task relaying server responses.
+    task: AbortHandle,
+
+    /// When the client last sent a datagram.
+    last_seen: Instant,
+}
+
+/// Relay datagrams between clients on the public socket and the server.
+///
+/// Each client gets its own socket to the server, up to `MAX_SESSIONS`. Sessions are dropped when
+/// idle. Dropping this future closes all sockets.
+async fn relay(public: UdpSocket, server: SocketAddr) -> io::Result<()> {
+    let public = Arc::new(public);
+    let mut sessions: HashMap<SocketAddr, Session> = HashMap::new();
+    let mut tasks = JoinSet::new();
+    let mut sweep = time::interval(SESSION_TIMEOUT);
+    let mut buf = vec![0; MAX_DATAGRAM_SIZE];
+
+    loop {
+        tokio::select! {
+            received = public.recv_from(&mut buf) => {
+                let (len, peer) = received?;
+                let full = sessions.len() >= MAX_SESSIONS;
+                let outbound = match sessions.get_mut(&peer) {
+                    Some(session) => {
+                        session.last_seen = Instant::now();
+                        session.outbound.clone()
+                    }
+                    None if full => {
+                        trace!(target: "lazymc::udp", "Too many UDP sessions, dropping datagram from {}", peer);
+                        continue;
+                    }
+                    None => {
+                        let outbound = match connect(server).await {
+                            Ok(outbound) => Arc::new(outbound),
+                            Err(err) => {
+                                warn!(target: "lazymc::udp", "Failed to open UDP socket to server: {}", err);
+                                continue;
+                            }
+                        };
+                        let task = tasks.spawn(session(public.clone(), outbound.clone(), peer));
+                        sessions.insert(peer, Session {
+                            outbound: outbound.clone(),
+                            task,
+                            last_seen: Instant::now(),
+                        });
+                        outbound
+                    }
+                };
+                if let Err(err) = outbound.send(&buf[..len]).await {
+                    trace!(target: "lazymc::udp", "Failed to forward UDP datagram to server: {}", err);
+                }
+            }
+            Some(Ok(peer)) = tasks.join_next() => {
+                sessions.remove(&peer);
+            }
+            _ = sweep.tick() => {
+                // Drop sessions of clients that went quiet
+                sessions.retain(|_, session| {
+                    let idle = session.last_seen.elapsed() >= SESSION_TIMEOUT;
+                    if idle {
+                        session.task.abort();
+                    }
+                    !idle
+                });
+            }
+        }
+    }
+}
+
+/// Relay server responses back to a client, until the session times out.
+///
+/// Returns the client address.
+async fn session(public: Arc<UdpSocket>, outbound: Arc<UdpSocket>, peer: SocketAddr) -> SocketAddr {
+    let mut buf = vec![0; MAX_DATAGRAM_SIZE];
+    while let Ok(Ok(len)) = time::timeout(SESSION_TIMEOUT, outbound.recv(&mut buf)).await {
+        if let Err(err) = public.send_to(&buf[..len], peer).await {
+            trace!(target: "lazymc::udp", "Failed to forward UDP datagram to client: {}", err);
+        }
+    }
+    peer
+}
+
+/// Open UDP socket connected to the server.
+async fn connect(server: SocketAddr) -> io::Result<UdpSocket> {
+    let local: SocketAddr = if server.is_ipv4() {
+        ([0, 0, 0, 0], 0).into()
+    } else {
+        ([0u16; 8], 0).into()
+    };
+    let socket = UdpSocket::bind(local).await?;
+    socket.connect(server).await?;
+    Ok(socket)
+}
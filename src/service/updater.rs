@@ -0,0 +1,35 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::time;
+
+use crate::config::Config;
+use crate::server::{Server, State};
+use crate::updater;
+
+/// Server jar updater task.
+///
+/// Periodically checks for and downloads server jar updates while the server is sleeping.
+pub async fn service(config: Arc<Config>, server: Arc<Server>) {
+    // Updater must be enabled
+    if !config.updater.enabled {
+        return;
+    }
+
+    let mut check_interval =
+        time::interval(Duration::from_secs(config.updater.interval.max(60) as u64));
+
+    loop {
+        check_interval.tick().await;
+
+        // Only check while sleeping, don't compete with a running server
+        if server.state() != State::Stopped {
+            continue;
+        }
+
+        debug!(target: "lazymc::updater", "Checking for server jar updates...");
+        if let Err(err) = updater::check(&config).await {
+            warn!(target: "lazymc::updater", "Failed to check for server jar updates: {}", err);
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::config::{Config, Stats};
+use crate::event::Event;
+use crate::server::{Server, State};
+
+/// Stats file to record to, set if stats are enabled.
+static FILE: OnceCell<PathBuf> = OnceCell::new();
+
+/// Stats entry.
+///
+/// Stored as one JSON object per line in the stats file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Entry {
+    /// Finished player session.
+    Session {
+        username: String,
+        ip: IpAddr,
+        time: u64,
+        duration: u64,
+        to_server: u64,
+        to_client: u64,
+    },
+
+    /// Server woken, optionally by a player.
+    Wake { username: Option<String>, time: u64 },
+
+    /// Server state changed.
+    State { from: String, to: String, time: u64 },
+
+    /// Number of online players changed.
+    Players { online: u32, time: u64 },
+}
+
+impl Entry {
+    /// Time of this entry.
+    pub fn time(&self) -> u64 {
+        match self {
+            Entry::Session { time, .. }
+            | Entry::Wake { time, .. }
+            | Entry::State { time, .. }
+            | Entry::Players { time, .. } => *time,
+        }
+    }
+}
+
+/// Statistics for a single player.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Player {
+    /// Number of sessions.
+    pub sessions: u64,
+
+    /// Total session duration in seconds.
+    pub playtime: u64,
+
+    /// Number of times the player woke the server.
+    pub wakes: u64,
+
+    /// Total bytes proxied for the player.
+    pub bytes: u64,
+
+    /// Time the last session started.
+    pub last_seen: u64,
+}
+
+/// Uptime and occupancy report over a period.
+///
+/// All durations are in seconds.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Report {
+    /// Total time the server was not stopped.
+    pub awake: u64,
+
+    /// Total time the server was stopped.
+    pub asleep: u64,
+
+    /// Number of wakes.
+    pub wakes: u64,
+
+    /// Number of completed starts.
+    pub starts: u64,
+
+    /// Total time spent starting, for completed starts.
+    pub starting: u64,
+
+    /// Total time the server was started without players online.
+    pub empty: u64,
+}
+
+impl Report {
+    /// Account elapsed time in given state with given number of online players.
+    fn account(&mut self, state: &str, online: u32, elapsed: u64) {
+        if state == State::Stopped.name() {
+            self.asleep += elapsed;
+        } else {
+            self.awake += elapsed;
+        }
+        if state == State::Started.name() && online == 0 {
+            self.empty += elapsed;
+        }
+    }
+
+    /// Average start duration in seconds, if any start completed.
+    pub fn average_start(&self) -> Option<u64> {
+        (self.starts > 0).then(|| self.starting / self.starts)
+    }
+}
+
+/// Enable recording stats if configured.
+///
+/// Must be called once before recording.
+pub fn init(config: &Config) {
+    if config.stats.enabled {
+        let _ = FILE.set(Stats::file_path(config));
+    }
+}
+
+/// Check whether stats are recorded.
+pub fn enabled() -> bool {
+    FILE.get().is_some()
+}
+
+/// Current time as UNIX timestamp.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Record an entry, if stats are enabled.
+pub async fn record(entry: Entry) {
+    let path = match FILE.get() {
+        Some(path) => path,
+        None => return,
+    };
+
+    if let Err(err) = append(path, &entry).await {
+        warn!(target: "lazymc::stats", "Failed to record stats to {}: {}", path.display(), err);
+    }
+}
+
+/// Append entry as line to stats file.
+async fn append(path: &Path, entry: &Entry) -> io::Result<()> {
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?
+        .write_all(&line)
+        .await
+}
+
+/// Summarize stats file contents per player.
+///
+/// Invalid lines are skipped.
+pub fn summarize(data: &str) -> BTreeMap<String, Player> {
+    let mut players: BTreeMap<String, Player> = BTreeMap::new();
+    for entry in data
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+    {
+        match entry {
+            Entry::Session {
+                username,
+                time,
+                duration,
+                to_server,
+                to_client,
+                ..
+            } => {
+                let player = players.entry(username).or_default();
+                player.sessions += 1;
+                player.playtime += duration;
+                player.bytes += to_server + to_client;
+                player.last_seen = player.last_seen.max(time);
+            }
+            Entry::Wake {
+                username: Some(username),
+                ..
+            } => players.entry(username).or_default().wakes += 1,
+            Entry::Wake { username: None, .. } | Entry::State { .. } | Entry::Players { .. } => {}
+        }
+    }
+    players
+}
+
+/// Build report over the given period from stats file contents.
+///
+/// Entries must be in chronological order. The server is assumed to be stopped before the first
+/// recorded state change.
+pub fn report(data: &str, since: u64, until: u64) -> Report {
+    let mut report = Report::default();
+    let mut state = State::Stopped.name().to_string();
+    let mut online = 0;
+    let mut starting_since = None;
+    let mut cursor = since;
+
+    for entry in data
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Entry>(line).ok())
+    {
+        let in_period = (since..=until).contains(&entry.time());
+        let time = entry.time().clamp(since, until);
+        report.account(&state, online, time.saturating_sub(cursor));
+        cursor = cursor.max(time);
+
+        match entry {
+            Entry::State { to, .. } => {
+                if to == State::Starting.name() {
+                    starting_since = in_period.then_some(time);
+                } else if to == State::Started.name() {
+                    if let Some(started) = starting_since.take() {
+                        report.starts += 1;
+                        report.starting += time - started;
+                    }
+                } else if to == State::Stopped.name() {
+                    online = 0;
+                }
+                state = to;
+            }
+            Entry::Players { online: count, .. } => online = count,
+            Entry::Wake { .. } if in_period => report.wakes += 1,
+            Entry::Wake { .. } | Entry::Session { .. } => {}
+        }
+    }
+    report.account(&state, online, until.saturating_sub(cursor));
+
+    report
+}
+
+/// Collect server start and stop durations in seconds from stats file contents.
+///
+/// Entries must be in chronological order. Returns start and stop durations, oldest first.
+pub fn durations(data: &str) -> (Vec<u64>, Vec<u64>) {
+    let (mut starts, mut stops) = (vec![], vec![]);
+    let mut last: Option<(String, u64)> = None;
+
+    for (to, time) in data
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Entry>(line).ok())
+        .filter_map(|entry| match entry {
+            Entry::State { to, time, .. } => Some((to, time)),
+            _ => None,
+        })
+    {
+        if let Some((from, since)) = &last {
+            let duration = time.saturating_sub(*since);
+            if from == State::Starting.name() && to == State::Started.name() {
+                starts.push(duration);
+            } else if from == State::Stopping.name() && to == State::Stopped.name() {
+                stops.push(duration);
+            }
+        }
+        last = Some((to, time));
+    }
+
+    (starts, stops)
+}
+
+/// Stats task.
+///
+/// Records server wakes, state changes and player counts.
+pub async fn service(server: Arc<Server>) {
+    if !enabled() {
+        return;
+    }
+
+    // Record current state, lazymc may have been restarted
+    let mut events = server.subscribe();
+    record(Entry::State {
+        from: "unknown".into(),
+        to: server.state().name().into(),
+        time: now(),
+    })
+    .await;
+
+    loop {
+        let entry = match events.recv().await {
+            Ok(Event::Wake { username }) => Entry::Wake {
+                username,
+                time: now(),
+            },
+            Ok(Event::State { from, to }) => Entry::State {
+                from: from.into(),
+                to: to.into(),
+                time: now(),
+            },
+            Ok(Event::Players { online, .. }) => Entry::Players {
+                online,
+                time: now(),
+            },
+            Ok(_) | Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        };
+        record(entry).await;
+    }
+}
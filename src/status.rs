@@ -1,25 +1,37 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use bytes::BytesMut;
 use minecraft_protocol::data::chat::{Message, Payload};
 use minecraft_protocol::data::server_status::*;
 use minecraft_protocol::decoder::Decoder;
-use minecraft_protocol::encoder::Encoder;
 use minecraft_protocol::version::v1_14_4::handshake::Handshake;
 use minecraft_protocol::version::v1_14_4::login::LoginStart;
-use minecraft_protocol::version::v1_14_4::status::StatusResponse;
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
 use tokio::net::TcpStream;
+use tokio::time;
+use tracing::Instrument;
+use uuid::Uuid;
 
 use crate::config::{Config, Server as ConfigServer};
+use crate::honeypot::Hit;
 use crate::join;
-use crate::mc::favicon;
+use crate::login;
+use crate::mc::{self, favicon};
+#[cfg(feature = "wasm")]
+use crate::plugin;
 use crate::proto::action;
 use crate::proto::client::{Client, ClientInfo, ClientState};
+use crate::proto::debug::{self, Direction};
 use crate::proto::packet::{self, RawPacket};
 use crate::proto::packets;
-use crate::server::{self, Server};
+#[cfg(feature = "rhai")]
+use crate::script;
+use crate::server::{self, SecureChat, Server};
+use crate::service;
+use crate::types;
+use crate::util::cli::format_duration;
 
 /// The ban message prefix.
 const BAN_MESSAGE_PREFIX: &str = "Your IP address is banned from this server.\nReason: ";
@@ -51,18 +63,33 @@ pub async fn serve(
     let mut client_info = ClientInfo::empty();
 
     loop {
-        // Read packet from stream
-        let (packet, raw) = match packet::read_packet(&client, &mut buf, &mut reader).await {
-            Ok(Some(packet)) => packet,
-            Ok(None) => break,
-            Err(_) => {
+        // Read packet from stream, limit size and time per state to drop stalling clients early
+        let timeout = match client.state() {
+            ClientState::Handshake => config.public.handshake_timeout,
+            _ => config.public.request_timeout,
+        };
+        let read = packet::read_packet_max(
+            &client,
+            &mut buf,
+            &mut reader,
+            config.public.max_packet_size,
+        );
+        let (packet, raw) = match time::timeout(Duration::from_secs(timeout as u64), read).await {
+            Ok(Ok(Some(packet))) => packet,
+            Ok(Ok(None)) => break,
+            Ok(Err(_)) => {
                 error!(target: "lazymc", "Closing connection, error occurred");
                 break;
             }
+            Err(_) => {
+                debug!(target: "lazymc", "Closing connection, client timed out in {:?} state", client.state());
+                break;
+            }
         };
 
         // Grab client state
         let client_state = client.state();
+        debug::log(&client, Direction::Serverbound, &packet);
 
         // Hijack handshake
         if client_state == ClientState::Handshake
@@ -73,6 +100,7 @@ pub async fn serve(
                 Ok(handshake) => handshake,
                 Err(_) => {
                     debug!(target: "lazymc", "Got malformed handshake from client, disconnecting");
+                    record_hit(&client, &config, &server, Hit::Malformed).await;
                     break;
                 }
             };
@@ -82,10 +110,16 @@ pub async fn serve(
                 Some(state) => state,
                 None => {
                     error!(target: "lazymc", "Client tried to switch into unknown protcol state ({}), disconnecting", handshake.next_state);
+                    record_hit(&client, &config, &server, Hit::Malformed).await;
                     break;
                 }
             };
 
+            // Record handshake on connection span
+            let span = tracing::Span::current();
+            span.record("protocol", handshake.protocol_version);
+            span.record("next_state", tracing::field::debug(&new_state));
+
             // Update client info and client state
             client_info
                 .protocol
@@ -101,15 +135,33 @@ pub async fn serve(
             continue;
         }
 
+        // Client must start with handshake, disconnect early otherwise
+        if client_state == ClientState::Handshake {
+            debug!(target: "lazymc", "Client sent packet {} before handshake, disconnecting", packet.id);
+            record_hit(&client, &config, &server, Hit::Malformed).await;
+            break;
+        }
+
         // Hijack server status packet
         if client_state == ClientState::Status && packet.id == packets::status::SERVER_STATUS {
-            let server_status = server_status(&client_info, &config, &server).await;
-            let packet = StatusResponse { server_status };
+            // Track status requests while offline to detect scanners
+            if server.state() != server::State::Started {
+                record_hit(&client, &config, &server, Hit::Status).await;
+            }
 
-            let mut data = Vec::new();
-            packet.encode(&mut data).map_err(|_| ())?;
+            // Drop status requests from unknown IPs in stealth mode
+            if config.stealth.enabled && !is_stealth_allowed(&client, &config, &server).await {
+                debug!(target: "lazymc", "Dropping status request from unknown IP {} in stealth mode", client.peer.ip());
+                break;
+            }
 
-            let response = RawPacket::new(0, data).encode_with_len(&client)?;
+            let server_status = server_status(&client, &client_info, &config, &server).await;
+            let secure_chat = server.secure_chat(&config).await;
+            let data = encode_status(&server_status, secure_chat)?;
+
+            let response = RawPacket::new(packets::status::CLIENT_STATUS, data);
+            debug::log(&client, Direction::Clientbound, &response);
+            let response = response.encode_with_len(&client)?;
             writer.write_all(&response).await.map_err(|_| ())?;
 
             continue;
@@ -129,6 +181,35 @@ pub async fn serve(
                 .ok()
                 .map(|p| p.name);
             client_info.username = username.clone();
+            client_info.login_start = Some(packet.data.clone());
+            let span = tracing::info_span!(
+                "login",
+                username = username.as_deref().unwrap_or_default(),
+                decision = tracing::field::Empty,
+            );
+
+            // Track login attempts to detect spam, forgiven once the login proceeds
+            record_hit(&client, &config, &server, Hit::Login).await;
+
+            // Kick if too many logins failed authorization recently from this IP
+            let throttle = server.login_throttle();
+            if throttle.is_throttled(&config, client.peer.ip()).await {
+                info!(target: "lazymc", "Kicked login from {} because of too many failed logins", client.peer.ip());
+                span.record("decision", "throttled");
+                action::kick(&client, &config.login.throttle_message, &mut writer).await?;
+                break;
+            }
+
+            // Kick if username is invalid, before any wake decision
+            let valid = username.as_deref().map_or(false, |username| {
+                mc::username::is_valid(username, &config.login.username_prefixes)
+            });
+            if config.login.validate_username && !valid {
+                info!(target: "lazymc", "Kicked login from {} because of invalid username {:?}", client.peer.ip(), username.as_deref().unwrap_or_default());
+                span.record("decision", "invalid_username");
+                action::kick(&client, &config.login.invalid_username_message, &mut writer).await?;
+                break;
+            }
 
             // Kick if lockout is enabled
             if config.lockout.enabled {
@@ -138,10 +219,41 @@ pub async fn serve(
                     }
                     None => info!(target: "lazymc", "Kicked player because lockout is enabled"),
                 }
+                span.record("decision", "lockout");
                 action::kick(&client, &config.lockout.message, &mut writer).await?;
                 break;
             }
 
+            // Kick if in maintenance mode, unless admin
+            if server.maintenance() {
+                let admin = username
+                    .as_deref()
+                    .map(|username| config.maintenance.is_admin(username))
+                    .unwrap_or(false);
+                if !admin {
+                    match username {
+                        Some(username) => {
+                            info!(target: "lazymc", "Kicked '{}' because maintenance mode is enabled", username)
+                        }
+                        None => {
+                            info!(target: "lazymc", "Kicked player because maintenance mode is enabled")
+                        }
+                    }
+                    span.record("decision", "maintenance");
+                    action::kick(&client, &config.maintenance.message, &mut writer).await?;
+                    break;
+                }
+
+                // Admin joins online server, proxy directly
+                if server.state() == server::State::Started {
+                    inbound_history.extend(&raw);
+                    inbound_history.extend(&buf);
+                    span.record("decision", "proxy");
+                    service::server::route_proxy_queue(inbound, config, &server, inbound_history);
+                    return Ok(());
+                }
+            }
+
             // Kick if client is banned
             if let Some(ban) = server.ban_entry(&client.peer.ip()).await {
                 if ban.is_banned() {
@@ -152,6 +264,7 @@ pub async fn serve(
                         info!(target: "lazymc", "Login from banned IP {}, disconnecting", client.peer.ip());
                         DEFAULT_BAN_REASON.to_string()
                     };
+                    span.record("decision", "banned");
                     action::kick(&client, &format!("{BAN_MESSAGE_PREFIX}{msg}"), &mut writer)
                         .await?;
                     break;
@@ -160,13 +273,45 @@ pub async fn serve(
 
             // Kick if client is not whitelisted to wake server
             if let Some(ref username) = username {
-                if !server.is_whitelisted(username).await {
+                if !may_wake(username, &config, &server).await {
                     info!(target: "lazymc", "User '{}' tried to wake server but is not whitelisted, disconnecting", username);
+                    span.record("decision", "not_whitelisted");
                     action::kick(&client, WHITELIST_MESSAGE, &mut writer).await?;
                     break;
                 }
             }
 
+            // Kick if plugin vetoes login
+            #[cfg(feature = "wasm")]
+            if let Some(message) =
+                plugin::on_login_attempt(username.as_deref(), client.peer.ip(), server.state())
+                    .await
+            {
+                info!(target: "lazymc", "Login from {} vetoed by plugin, disconnecting", client.peer.ip());
+                span.record("decision", "plugin");
+                action::kick(&client, &message, &mut writer).await?;
+                break;
+            }
+
+            // Kick if authorization hooks deny waking
+            if let Err(message) =
+                login::authorize(&config, &server, username.as_deref(), client.peer.ip()).await
+            {
+                span.record("decision", "unauthorized");
+                throttle.fail(&config, client.peer.ip()).await;
+                action::kick(&client, &message, &mut writer).await?;
+                break;
+            }
+
+            // Login proceeds, forget suspicious requests from this IP
+            server.honeypot().forgive(&client.peer.ip()).await;
+            throttle.succeed(client.peer.ip()).await;
+
+            // Remember IP for stealth mode
+            if config.stealth.learn {
+                server.remember_ip(client.peer.ip()).await;
+            }
+
             // Start server if not starting yet
             Server::start(config.clone(), server.clone(), username).await;
 
@@ -183,6 +328,7 @@ pub async fn serve(
             buf.clear();
 
             // Start occupying client
+            span.record("decision", "occupy");
             join::occupy(
                 client,
                 client_info,
@@ -192,6 +338,7 @@ pub async fn serve(
                 inbound_history,
                 login_queue,
             )
+            .instrument(span)
             .await?;
             return Ok(());
         }
@@ -205,18 +352,115 @@ pub async fn serve(
     Ok(())
 }
 
+/// Serve status requests from a status shared between clients, proxy anything else.
+///
+/// Used while the server is started to coalesce status requests into a single request to the
+/// server per interval. Falls back to proxying if fetching the status fails.
+pub async fn serve_coalesced(
+    client: Client,
+    mut inbound: TcpStream,
+    config: Arc<Config>,
+    server: Arc<Server>,
+) -> Result<(), ()> {
+    let (mut reader, mut writer) = inbound.split();
+
+    // Incoming buffer, remember inbound packets to proxy
+    let mut buf = BytesMut::new();
+    let mut inbound_history = BytesMut::new();
+
+    loop {
+        // Read packet from stream, limit size and time per state to drop stalling clients early
+        let timeout = match client.state() {
+            ClientState::Handshake => config.public.handshake_timeout,
+            _ => config.public.request_timeout,
+        };
+        let read = packet::read_packet_max(
+            &client,
+            &mut buf,
+            &mut reader,
+            config.public.max_packet_size,
+        );
+        let (packet, raw) = match time::timeout(Duration::from_secs(timeout as u64), read).await {
+            Ok(Ok(Some(packet))) => packet,
+            Ok(Ok(None)) => return Ok(()),
+            Ok(Err(_)) => {
+                error!(target: "lazymc", "Closing connection, error occurred");
+                return Ok(());
+            }
+            Err(_) => {
+                debug!(target: "lazymc", "Closing connection, client timed out in {:?} state", client.state());
+                return Ok(());
+            }
+        };
+
+        let client_state = client.state();
+        debug::log(&client, Direction::Serverbound, &packet);
+        inbound_history.extend(&raw);
+
+        // Proxy anything but status requests
+        if client_state == ClientState::Handshake {
+            let handshake = match Handshake::decode(&mut packet.data.as_slice()) {
+                Ok(handshake) if packet.id == packets::handshake::SERVER_HANDSHAKE => handshake,
+                _ => break,
+            };
+            match ClientState::from_id(handshake.next_state) {
+                Some(ClientState::Status) => {
+                    client.set_state(ClientState::Status);
+                    continue;
+                }
+                _ => break,
+            }
+        }
+
+        // Respond with coalesced status, proxy if unavailable
+        if client_state == ClientState::Status && packet.id == packets::status::SERVER_STATUS {
+            let data = match server.coalesced_status(&config).await {
+                Some(data) => data,
+                None => break,
+            };
+
+            let response = RawPacket::new(packets::status::CLIENT_STATUS, data.to_vec());
+            debug::log(&client, Direction::Clientbound, &response);
+            let response = response.encode_with_len(&client)?;
+            writer.write_all(&response).await.map_err(|_| ())?;
+            continue;
+        }
+
+        // Echo ping packet
+        if client_state == ClientState::Status && packet.id == packets::status::SERVER_PING {
+            writer.write_all(&raw).await.map_err(|_| ())?;
+            continue;
+        }
+
+        break;
+    }
+
+    // Proxy to server with everything received so far
+    inbound_history.extend_from_slice(&buf);
+    service::server::route_proxy_queue(inbound, config, &server, inbound_history);
+    Ok(())
+}
+
 /// Build server status object to respond to client with.
-async fn server_status(client_info: &ClientInfo, config: &Config, server: &Server) -> ServerStatus {
-    let status = server.status().await;
+async fn server_status(
+    #[allow(unused_variables)] client: &Client,
+    client_info: &ClientInfo,
+    config: &Config,
+    server: &Server,
+) -> ServerStatus {
+    let status = server.status();
     let server_state = server.state();
 
     // Respond with real server status if started
-    if server_state == server::State::Started && status.is_some() {
-        return status.as_ref().unwrap().clone();
+    let maintenance = server.maintenance();
+    if server_state == server::State::Started && !maintenance {
+        if let Some(status) = status.as_ref() {
+            return ServerStatus::clone(status);
+        }
     }
 
     // Select version and player max from last known server status
-    let (version, max) = match status.as_ref() {
+    let (mut version, max) = match status.as_ref() {
         Some(status) => (status.version.clone(), status.players.max),
         None => (
             ServerVersion {
@@ -227,24 +471,58 @@ async fn server_status(client_info: &ClientInfo, config: &Config, server: &Serve
         ),
     };
 
+    // Override version if configured
+    if let Some(name) = &config.motd.version {
+        version.name = name.clone();
+    }
+    if let Some(protocol) = config.motd.protocol {
+        version.protocol = protocol;
+    }
+
     // Select description, use server MOTD if enabled, or use configured
     let description = {
-        if config.motd.from_server && status.is_some() {
+        if maintenance {
+            Message::new(Payload::text(&config.maintenance.motd))
+        } else if config.motd.from_server && status.is_some() {
             status.as_ref().unwrap().description.clone()
         } else {
-            Message::new(Payload::text(match server_state {
-                server::State::Stopped | server::State::Started => &config.motd.sleeping,
-                server::State::Starting => &config.motd.starting,
-                server::State::Stopping => &config.motd.stopping,
-            }))
+            #[allow(unused_mut)]
+            let mut motd = match server_state {
+                server::State::Stopped | server::State::Started => {
+                    let motd = config.motd.sleeping.pick(config.motd.rotation);
+                    motd_placeholders(motd, server).await
+                }
+                server::State::Starting => config.motd.starting.pick(config.motd.rotation).into(),
+                server::State::Stopping => config.motd.stopping.pick(config.motd.rotation).into(),
+            };
+
+            // Let plugins change MOTD
+            #[cfg(feature = "wasm")]
+            if let Some(changed) =
+                plugin::on_status_request(client.peer.ip(), server_state, &motd).await
+            {
+                motd = changed;
+            }
+
+            // Let scripts compute MOTD
+            #[cfg(feature = "rhai")]
+            if let Some(changed) = script::motd(
+                server_state,
+                status.as_ref().map(|s| s.players.online).unwrap_or(0),
+                &motd,
+            ) {
+                motd = changed;
+            }
+
+            Message::new(Payload::text(&motd))
         }
     };
 
     // Extract favicon from real server status, load from disk, or use default
     let mut favicon = None;
     if favicon::supports_favicon(client_info) {
-        if config.motd.from_server && status.is_some() {
-            favicon = status.as_ref().unwrap().favicon.clone()
+        if let Some(status) = status.as_ref().filter(|_| config.motd.from_server) {
+            favicon = status.favicon.clone()
         }
         if favicon.is_none() {
             favicon = Some(server_favicon(config).await);
@@ -264,6 +542,81 @@ async fn server_status(client_info: &ClientInfo, config: &Config, server: &Serve
     }
 }
 
+/// Check whether the given user may wake the server.
+///
+/// Checks the server whitelist by username, and by UUID to match renamed players. Also checks
+/// the configured wake UUIDs.
+async fn may_wake(username: &str, config: &Config, server: &Server) -> bool {
+    let whitelisted = server.is_whitelisted(username).await;
+    if whitelisted && config.login.wake_uuids.is_empty() {
+        return true;
+    }
+
+    let uuid = server.uuids().resolve(config, username).await;
+    if !whitelisted && !server.is_whitelisted_uuid(&uuid).await {
+        return false;
+    }
+    config.login.wake_uuids.is_empty()
+        || config
+            .login
+            .wake_uuids
+            .iter()
+            .any(|allowed| Uuid::parse_str(allowed).ok() == Some(uuid))
+}
+
+/// Record suspicious request from client in the honeypot.
+async fn record_hit(client: &Client, config: &Config, server: &Server, hit: Hit) {
+    server
+        .honeypot()
+        .record(config, client.peer.ip(), hit)
+        .await;
+}
+
+/// Check whether client may see status in stealth mode.
+async fn is_stealth_allowed(client: &Client, config: &Config, server: &Server) -> bool {
+    let ip = client.peer.ip();
+    config.stealth.is_allowed(ip) || (config.stealth.learn && server.is_known_ip(&ip).await)
+}
+
+/// Replace sleeping MOTD placeholders.
+///
+/// Supports `%asleep_for%` with time since the server went to sleep, and `%last_players%` with
+/// the players last seen online.
+async fn motd_placeholders(motd: &str, server: &Server) -> String {
+    if !motd.contains('%') {
+        return motd.into();
+    }
+
+    let asleep_for = match server.state_for().await {
+        Some(duration) if server.state() == server::State::Stopped => {
+            format_duration(duration.as_secs())
+        }
+        _ => "a while".into(),
+    };
+    let last_players = server.last_players().await;
+    let last_players = if last_players.is_empty() {
+        "nobody".into()
+    } else {
+        last_players.join(", ")
+    };
+
+    motd.replace("%asleep_for%", &asleep_for)
+        .replace("%last_players%", &last_players)
+}
+
+/// Encode status response packet data, with secure chat flags.
+///
+/// `StatusResponse` can't hold these flags, so the JSON is encoded here.
+fn encode_status(server_status: &ServerStatus, secure_chat: SecureChat) -> Result<Vec<u8>, ()> {
+    let mut json = serde_json::to_value(server_status).map_err(|_| ())?;
+    secure_chat.apply(&mut json);
+    let json = json.to_string();
+
+    let mut data = types::encode_var_int(json.len() as i32)?;
+    data.extend_from_slice(json.as_bytes());
+    Ok(data)
+}
+
 /// Get server status favicon.
 ///
 /// This always returns a favicon, returning the default one if none is set.
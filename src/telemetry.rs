@@ -0,0 +1,51 @@
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{runtime, trace, Resource};
+use tracing_subscriber::layer::SubscriberExt;
+
+use crate::config::Telemetry;
+
+/// Initialize OpenTelemetry tracing with OTLP exporter.
+///
+/// Must be called from within the tokio runtime.
+pub fn init(config: &Telemetry) {
+    if !config.enabled {
+        return;
+    }
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(&config.endpoint),
+        )
+        .with_trace_config(
+            trace::config().with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                config.service_name.clone(),
+            )])),
+        )
+        .install_batch(runtime::Tokio);
+    let tracer = match tracer {
+        Ok(tracer) => tracer,
+        Err(err) => {
+            error!(target: "lazymc", "Failed to initialize OpenTelemetry exporter: {}", err);
+            return;
+        }
+    };
+
+    let subscriber =
+        tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
+    if let Err(err) = tracing::subscriber::set_global_default(subscriber) {
+        error!(target: "lazymc", "Failed to initialize OpenTelemetry tracing: {}", err);
+        return;
+    }
+
+    info!(target: "lazymc", "Exporting traces to {}", config.endpoint);
+}
+
+/// Flush and shut down exporter.
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
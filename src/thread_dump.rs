@@ -0,0 +1,107 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::Local;
+use tokio::fs;
+use tokio::process::Command;
+use tokio::time;
+
+use crate::config::{Config, Server as ConfigServer, ThreadDumpMethod};
+use crate::os;
+
+/// Maximum time to wait for `jcmd` to produce a thread dump.
+const JCMD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Capture a JVM thread dump of the server process.
+///
+/// Uses the configured method. Returns the path of the saved thread dump if it was written to a
+/// file.
+pub async fn capture(config: &Config, pid: u32) -> Option<PathBuf> {
+    // Only log in dry run
+    if config.dry_run {
+        info!(target: "lazymc", "Dry run, would capture thread dump of server process {}", pid);
+        return None;
+    }
+
+    match config.diagnostics.thread_dump_method {
+        ThreadDumpMethod::Jcmd => capture_jcmd(config, pid).await,
+        ThreadDumpMethod::Signal => {
+            capture_signal(pid);
+            None
+        }
+    }
+}
+
+/// Capture thread dump through `jcmd`, save it to a file.
+async fn capture_jcmd(config: &Config, pid: u32) -> Option<PathBuf> {
+    debug!(target: "lazymc::diagnostics", "Capturing thread dump of server process through jcmd...");
+
+    // Invoke jcmd, collect output
+    let mut cmd = Command::new(&config.diagnostics.jcmd);
+    cmd.args([pid.to_string().as_str(), "Thread.print"]);
+    cmd.kill_on_drop(true);
+    let output = match time::timeout(JCMD_TIMEOUT, cmd.output()).await {
+        Ok(Ok(output)) if output.status.success() => output,
+        Ok(Ok(output)) => {
+            error!(target: "lazymc::diagnostics", "Failed to capture thread dump, jcmd exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim());
+            return None;
+        }
+        Ok(Err(err)) => {
+            error!(target: "lazymc::diagnostics", "Failed to capture thread dump, could not invoke jcmd: {}", err);
+            return None;
+        }
+        Err(_) => {
+            error!(target: "lazymc::diagnostics", "Failed to capture thread dump, jcmd timed out after {}s", JCMD_TIMEOUT.as_secs());
+            return None;
+        }
+    };
+
+    // Ensure output directory exists
+    let dir = dump_directory(config);
+    if let Err(err) = fs::create_dir_all(&dir).await {
+        error!(target: "lazymc::diagnostics", "Failed to create directory for thread dump at {}: {}", dir.display(), err);
+        return None;
+    }
+
+    // Write thread dump to file
+    let path = dir.join(format!(
+        "thread-dump-{}.txt",
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    match fs::write(&path, &output.stdout).await {
+        Ok(_) => {
+            info!(target: "lazymc::diagnostics", "Saved thread dump of server process to {}", path.display());
+            Some(path)
+        }
+        Err(err) => {
+            error!(target: "lazymc::diagnostics", "Failed to save thread dump to {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+/// Capture thread dump by sending `SIGQUIT`.
+///
+/// The JVM writes the thread dump to its own console output. Only available on Unix.
+fn capture_signal(pid: u32) {
+    if cfg!(not(unix)) {
+        warn!(target: "lazymc::diagnostics", "Cannot request thread dump through signal on this platform, use jcmd instead");
+        return;
+    }
+
+    if os::dump_threads(pid) {
+        info!(target: "lazymc::diagnostics", "Requested thread dump from server process, see server output");
+    } else {
+        error!(target: "lazymc::diagnostics", "Failed to request thread dump from server process");
+    }
+}
+
+/// Get the directory to write thread dumps to.
+///
+/// Relative to the server directory if known.
+fn dump_directory(config: &Config) -> PathBuf {
+    match ConfigServer::server_directory(config) {
+        Some(dir) => dir.join(&config.diagnostics.directory),
+        None => config.diagnostics.directory.clone(),
+    }
+}
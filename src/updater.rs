@@ -0,0 +1,212 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::config::{Config, Server as ConfigServer, Updater, UpdaterProject};
+
+/// PaperMC download API base URL.
+const PAPER_API: &str = "https://api.papermc.io/v2";
+
+/// PurpurMC download API base URL.
+const PURPUR_API: &str = "https://api.purpurmc.org/v2";
+
+/// Suffix for downloaded jar waiting to be swapped in.
+const UPDATE_SUFFIX: &str = ".update";
+
+/// Suffix for jar that is still being downloaded.
+const PART_SUFFIX: &str = ".part";
+
+/// Suffix for file describing the build of a jar.
+const BUILD_SUFFIX: &str = ".build";
+
+/// Server jar build available for download.
+struct Build {
+    /// Build identifier, unique for project and Minecraft version.
+    id: String,
+
+    /// Download URL.
+    url: String,
+
+    /// Expected SHA-256 checksum of the jar, if known.
+    sha256: Option<String>,
+}
+
+/// Check for a newer server jar build, download it if available.
+///
+/// The downloaded jar is not swapped in until `apply` is called.
+/// Returns `true` if a new build was downloaded.
+pub async fn check(config: &Config) -> Result<bool, Box<dyn Error>> {
+    let updater = &config.updater;
+    if updater.version.trim().is_empty() {
+        return Err("no Minecraft version configured (updater.version)".into());
+    }
+    let jar = jar_path(config).ok_or("server directory not configured (server.directory)")?;
+    let update = with_suffix(&jar, UPDATE_SUFFIX);
+
+    // Find latest build, skip if already installed or downloaded
+    let build = latest_build(updater).await?;
+    let tag = build_tag(updater, &build);
+    if read_tag(&jar).as_deref() == Some(&tag) || read_tag(&update).as_deref() == Some(&tag) {
+        trace!(target: "lazymc::updater", "Server jar is up-to-date ({})", tag);
+        return Ok(false);
+    }
+
+    // Download and verify jar
+    info!(target: "lazymc::updater", "Downloading server jar update ({})...", tag);
+    let response = reqwest::get(&build.url).await?.error_for_status()?;
+    let data = response.bytes().await?;
+    if let Some(expected) = &build.sha256 {
+        let actual: String = Sha256::digest(&data)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(format!("checksum mismatch for {}", build.url).into());
+        }
+    }
+
+    // Store as pending update, move into place last so a partial download is never applied
+    let part = with_suffix(&update, PART_SUFFIX);
+    tokio::fs::write(&part, &data).await?;
+    tokio::fs::write(with_suffix(&update, BUILD_SUFFIX), &tag).await?;
+    tokio::fs::rename(&part, &update).await?;
+    info!(target: "lazymc::updater", "Server jar update downloaded, applying on next start");
+
+    Ok(true)
+}
+
+/// Swap in downloaded server jar update, if there is any.
+///
+/// Must only be called while the server is not running.
+pub fn apply(config: &Config) {
+    let jar = match jar_path(config) {
+        Some(jar) => jar,
+        None => return,
+    };
+    let update = with_suffix(&jar, UPDATE_SUFFIX);
+    if !update.is_file() {
+        return;
+    }
+
+    let result = fs::rename(&update, &jar).and_then(|_| {
+        fs::rename(
+            with_suffix(&update, BUILD_SUFFIX),
+            with_suffix(&jar, BUILD_SUFFIX),
+        )
+    });
+    match result {
+        Ok(()) => info!(
+            target: "lazymc::updater",
+            "Updated server jar to {}",
+            read_tag(&jar).unwrap_or_else(|| "unknown build".into()),
+        ),
+        Err(err) => {
+            error!(target: "lazymc::updater", "Failed to swap in server jar update: {}", err)
+        }
+    }
+}
+
+/// Get path to server jar.
+fn jar_path(config: &Config) -> Option<PathBuf> {
+    ConfigServer::server_directory(config).map(|dir| dir.join(&config.updater.jar))
+}
+
+/// Append suffix to file name of path.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    path.into()
+}
+
+/// Build tag, identifying a build across projects and versions.
+fn build_tag(updater: &Updater, build: &Build) -> String {
+    format!(
+        "{} {} {}",
+        updater.project.name(),
+        updater.version.trim(),
+        build.id
+    )
+}
+
+/// Read build tag of given jar.
+fn read_tag(jar: &Path) -> Option<String> {
+    fs::read_to_string(with_suffix(jar, BUILD_SUFFIX))
+        .ok()
+        .map(|tag| tag.trim().to_string())
+}
+
+/// Find latest build for configured project and version.
+async fn latest_build(updater: &Updater) -> Result<Build, Box<dyn Error>> {
+    let version = updater.version.trim();
+    match updater.project {
+        UpdaterProject::Paper => latest_paper(version).await,
+        UpdaterProject::Purpur => latest_purpur(version).await,
+    }
+}
+
+/// Find latest Paper build.
+async fn latest_paper(version: &str) -> Result<Build, Box<dyn Error>> {
+    #[derive(Deserialize)]
+    struct Builds {
+        builds: Vec<PaperBuild>,
+    }
+
+    #[derive(Deserialize)]
+    struct PaperBuild {
+        build: u32,
+        channel: String,
+        downloads: PaperDownloads,
+    }
+
+    #[derive(Deserialize)]
+    struct PaperDownloads {
+        application: PaperDownload,
+    }
+
+    #[derive(Deserialize)]
+    struct PaperDownload {
+        name: String,
+        sha256: String,
+    }
+
+    let url = format!("{PAPER_API}/projects/paper/versions/{version}/builds");
+    let builds: Builds = reqwest::get(url).await?.error_for_status()?.json().await?;
+
+    // Prefer latest stable build
+    let build = builds
+        .builds
+        .iter()
+        .rev()
+        .find(|b| b.channel == "default")
+        .or_else(|| builds.builds.last())
+        .ok_or("no builds available")?;
+
+    Ok(Build {
+        id: build.build.to_string(),
+        url: format!(
+            "{PAPER_API}/projects/paper/versions/{version}/builds/{}/downloads/{}",
+            build.build, build.downloads.application.name
+        ),
+        sha256: Some(build.downloads.application.sha256.clone()),
+    })
+}
+
+/// Find latest Purpur build.
+async fn latest_purpur(version: &str) -> Result<Build, Box<dyn Error>> {
+    #[derive(Deserialize)]
+    struct PurpurBuild {
+        build: String,
+    }
+
+    let url = format!("{PURPUR_API}/purpur/{version}/latest");
+    let build: PurpurBuild = reqwest::get(url).await?.error_for_status()?.json().await?;
+
+    Ok(Build {
+        url: format!("{PURPUR_API}/purpur/{version}/{}/download", build.build),
+        id: build.build,
+        sha256: None,
+    })
+}
@@ -29,6 +29,17 @@ pub fn prompt(msg: &str) -> String {
     input.trim().to_owned()
 }
 
+/// Prompt the user to enter some value, with a default.
+/// The default is chosen if enter was pressed without entering anything.
+pub fn prompt_default(msg: &str, def: &str) -> String {
+    let answer = prompt(&format!("{msg} [{def}]"));
+    if answer.is_empty() {
+        def.to_owned()
+    } else {
+        answer
+    }
+}
+
 /// Prompt the user for a question, allowing a yes or now answer.
 /// True is returned if yes was answered, false if no.
 ///
@@ -89,3 +100,27 @@ fn derive_bool(input: &str) -> Option<bool> {
     // The answer could not be determined, return none
     None
 }
+
+/// Format seconds as short human readable duration.
+pub fn format_duration(seconds: u64) -> String {
+    match seconds {
+        s if s < 60 => format!("{s}s"),
+        s if s < 60 * 60 => format!("{}m{:02}s", s / 60, s % 60),
+        s => format!("{}h{:02}m", s / 3600, s / 60 % 60),
+    }
+}
+
+/// Parse duration such as `30m`, `12h` or `7d` into seconds.
+pub fn parse_duration(duration: &str) -> Option<u64> {
+    let duration = duration.trim();
+    let (value, unit) = duration.split_at(duration.find(|c: char| !c.is_ascii_digit())?);
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    value.parse::<u64>().ok()?.checked_mul(multiplier)
+}
@@ -0,0 +1,37 @@
+use std::path::Path;
+use std::time::Duration;
+
+use tokio::process::Command;
+use tokio::time;
+
+/// Run command with arguments to completion, killing it if it takes longer than the timeout.
+///
+/// Returns an error describing why it failed if it did not complete successfully.
+pub async fn run(args: &[String], dir: Option<&Path>, timeout: Duration) -> Result<(), String> {
+    let (program, args) = args.split_first().ok_or("empty command")?;
+
+    // Configure command
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    cmd.kill_on_drop(true);
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+
+    // Spawn and wait for completion with timeout
+    let mut child = cmd
+        .spawn()
+        .map_err(|err| format!("failed to start command: {err}"))?;
+    match time::timeout(timeout, child.wait()).await {
+        Ok(Ok(status)) if status.success() => Ok(()),
+        Ok(Ok(status)) => Err(format!("command failed ({status})")),
+        Ok(Err(err)) => Err(format!("failed to wait for command: {err}")),
+        Err(_) => {
+            let _ = child.kill().await;
+            Err(format!(
+                "command timed out after {}s, killed it",
+                timeout.as_secs()
+            ))
+        }
+    }
+}
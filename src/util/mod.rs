@@ -1,7 +1,11 @@
 pub mod cli;
+pub mod command;
 pub mod error;
+pub mod pool;
+pub mod schedule;
 pub mod serde;
 pub mod style;
+pub mod throttle;
 
 use std::env;
 use std::path::PathBuf;
@@ -24,3 +28,30 @@ pub fn bin_name() -> String {
         .and_then(|n| n.into_string().ok())
         .unwrap_or_else(|| crate_name!().into())
 }
+
+/// Encode bytes as lowercase hex.
+pub fn hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Decode hex into bytes.
+///
+/// Returns `None` if the input is not valid hex.
+pub fn unhex(data: &str) -> Option<Vec<u8>> {
+    if data.len() % 2 != 0 {
+        return None;
+    }
+    (0..data.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(data.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Compare secret tokens in constant time.
+pub fn token_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
@@ -0,0 +1,77 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+
+/// How to pick a message from a pool.
+#[derive(Debug, Default, Deserialize, JsonSchema, Copy, Clone, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Rotation {
+    /// Pick messages in order, one per request.
+    #[default]
+    Sequential,
+
+    /// Pick a random message on each request.
+    Random,
+}
+
+/// Pool of messages to pick from.
+///
+/// Deserializes from a single message, or a list of messages.
+#[derive(Debug)]
+pub struct MessagePool {
+    /// Messages, never empty.
+    messages: Vec<String>,
+
+    /// Index of the next message for sequential rotation.
+    next: AtomicUsize,
+}
+
+impl MessagePool {
+    /// Pick a message.
+    pub fn pick(&self, rotation: Rotation) -> &str {
+        let index = match rotation {
+            _ if self.messages.len() == 1 => 0,
+            Rotation::Sequential => self.next.fetch_add(1, Ordering::Relaxed),
+            Rotation::Random => rand::thread_rng().gen_range(0..self.messages.len()),
+        };
+        &self.messages[index % self.messages.len()]
+    }
+}
+
+impl From<&str> for MessagePool {
+    fn from(message: &str) -> Self {
+        Self {
+            messages: vec![message.into()],
+            next: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MessagePool {
+    fn deserialize<D>(d: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Messages {
+            One(String),
+            Many(Vec<String>),
+        }
+
+        let messages = match Messages::deserialize(d)? {
+            Messages::One(message) => vec![message],
+            Messages::Many(messages) if messages.is_empty() => {
+                return Err(Error::custom("message list can't be empty"))
+            }
+            Messages::Many(messages) => messages,
+        };
+        Ok(Self {
+            messages,
+            next: AtomicUsize::new(0),
+        })
+    }
+}
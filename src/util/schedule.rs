@@ -0,0 +1,137 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, TimeZone, Weekday};
+use serde::Deserialize;
+
+/// Set of weekdays.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Days(u8);
+
+impl Days {
+    /// All days of the week.
+    pub const ALL: Days = Days(0b111_1111);
+
+    /// Check whether the given weekday is in this set.
+    pub fn contains(self, day: Weekday) -> bool {
+        self.0 & (1 << day.num_days_from_monday()) != 0
+    }
+}
+
+impl Default for Days {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl FromStr for Days {
+    type Err = String;
+
+    /// Parse days such as `Fri`, `Mon-Fri` or `Sat,Sun`. Ranges may wrap around, as in `Fri-Mon`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut days = 0;
+        for part in s.split(',') {
+            let (from, to) = part.split_once('-').unwrap_or((part, part));
+            let from = parse_weekday(from)?;
+            let to = parse_weekday(to)?;
+
+            let mut day = from;
+            loop {
+                days |= 1 << day.num_days_from_monday();
+                if day == to {
+                    break;
+                }
+                day = day.succ();
+            }
+        }
+        Ok(Days(days))
+    }
+}
+
+impl TryFrom<String> for Days {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Weekly recurring time, such as `Fri 18:45`.
+///
+/// Recurs daily if no days are given, such as `18:45`.
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct WeeklyTime {
+    /// Days to recur on.
+    pub days: Days,
+
+    /// Time of day.
+    pub time: NaiveTime,
+}
+
+impl WeeklyTime {
+    /// Get the next occurrence after the given time.
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        (0..=7)
+            .map(|offset| after.date_naive() + Duration::days(offset))
+            .filter(|date| self.days.contains(date.weekday()))
+            .filter_map(|date| {
+                Local
+                    .from_local_datetime(&date.and_time(self.time))
+                    .earliest()
+            })
+            .find(|time| *time > after)
+    }
+}
+
+impl FromStr for WeeklyTime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (days, time) = match s.trim().rsplit_once(' ') {
+            Some((days, time)) => (days.parse()?, time),
+            None => (Days::ALL, s),
+        };
+        Ok(Self {
+            days,
+            time: parse_time(time)?,
+        })
+    }
+}
+
+impl TryFrom<String> for WeeklyTime {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Check whether the given time is within a daily window on the given days.
+///
+/// The window wraps past midnight if `to` is before `from`, and then belongs to the day it starts
+/// on. It covers the whole day if `from` and `to` are equal.
+pub fn in_window(days: Days, from: NaiveTime, to: NaiveTime, at: DateTime<Local>) -> bool {
+    let (today, time) = (at.weekday(), at.time());
+    match from.cmp(&to) {
+        Ordering::Equal => days.contains(today),
+        Ordering::Less => days.contains(today) && from <= time && time < to,
+        Ordering::Greater => {
+            (days.contains(today) && time >= from) || (days.contains(today.pred()) && time < to)
+        }
+    }
+}
+
+/// Parse weekday, such as `Mon` or `Monday`.
+fn parse_weekday(s: &str) -> Result<Weekday, String> {
+    s.trim()
+        .parse()
+        .map_err(|_| format!("invalid weekday '{}'", s.trim()))
+}
+
+/// Parse time of day in `HH:MM` format.
+pub fn parse_time(s: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M")
+        .map_err(|_| format!("invalid time '{}', expected HH:MM", s.trim()))
+}
@@ -1,8 +1,11 @@
 use std::net::{SocketAddr, ToSocketAddrs};
 
+use chrono::NaiveTime;
 use serde::de::{Error, Unexpected};
 use serde::{Deserialize, Deserializer};
 
+use crate::util::schedule;
+
 /// Deserialize a `Vec` into a `HashMap` by key.
 pub fn to_socket_addrs<'de, D>(d: D) -> Result<SocketAddr, D::Error>
 where
@@ -10,7 +13,22 @@ where
 {
     // Deserialize string
     let addr = String::deserialize(d)?;
+    resolve_socket_addr(addr)
+}
 
+/// Deserialize an optional socket address, see `to_socket_addrs`.
+pub fn to_option_socket_addrs<'de, D>(d: D) -> Result<Option<SocketAddr>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<String>::deserialize(d)? {
+        Some(addr) => resolve_socket_addr(addr).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Resolve a socket address from a host or IP and port.
+fn resolve_socket_addr<E: Error>(addr: String) -> Result<SocketAddr, E> {
     // Try to socket address to resolve
     match addr.to_socket_addrs() {
         Ok(mut addr) => {
@@ -24,7 +42,15 @@ where
     }
 
     // Parse raw IP address
-    addr.parse().map_err(|_| {
-        Error::invalid_value(Unexpected::Str(&addr), &"IP or resolvable host and port")
-    })
+    addr.parse()
+        .map_err(|_| E::invalid_value(Unexpected::Str(&addr), &"IP or resolvable host and port"))
+}
+
+/// Deserialize time of day in `HH:MM` format.
+pub fn to_time<'de, D>(d: D) -> Result<NaiveTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let time = String::deserialize(d)?;
+    schedule::parse_time(&time).map_err(Error::custom)
 }
@@ -0,0 +1,77 @@
+use std::time::{Duration, Instant};
+
+use tokio::io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time;
+
+/// Token bucket rate limiter.
+///
+/// Allows bursts up to one second worth of data.
+pub struct TokenBucket {
+    /// Rate in bytes per second.
+    rate: f64,
+
+    /// Available tokens, may go negative to represent debt.
+    tokens: f64,
+
+    /// Last refill time.
+    last: Instant,
+}
+
+impl TokenBucket {
+    /// Construct new bucket with given rate in bytes per second.
+    pub fn new(rate: u64) -> Self {
+        Self {
+            rate: rate as f64,
+            tokens: rate as f64,
+            last: Instant::now(),
+        }
+    }
+
+    /// Take the given number of bytes, waits if over the rate limit.
+    pub async fn take(&mut self, bytes: usize) {
+        // Refill, don't exceed burst capacity
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.last = now;
+
+        self.tokens -= bytes as f64;
+        if self.tokens < 0.0 {
+            time::sleep(Duration::from_secs_f64(-self.tokens / self.rate)).await;
+        }
+    }
+}
+
+/// Copy reader into writer until EOF, limited to rate in bytes per second.
+///
+/// The rate is unlimited if 0. Copied bytes are added to `total` as they are written, so it is
+/// accurate even if an error occurs.
+pub async fn copy<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    rate: u64,
+    buffer_size: usize,
+    total: &mut u64,
+) -> io::Result<()>
+where
+    R: AsyncRead + Unpin + ?Sized,
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    let mut bucket = (rate > 0).then(|| TokenBucket::new(rate));
+    let buffer_size = match rate {
+        0 => buffer_size,
+        rate => buffer_size.min(rate as usize),
+    };
+    let mut buf = vec![0; buffer_size.max(1)];
+    loop {
+        let read = reader.read(&mut buf).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        if let Some(bucket) = bucket.as_mut() {
+            bucket.take(read).await;
+        }
+        writer.write_all(&buf[..read]).await?;
+        *total += read as u64;
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::Sha256;
+
+use crate::config::Config;
+use crate::util::{hex, unhex};
+
+/// Tokens of wake links that were used, with their expiry time.
+///
+/// Links can only be used once while lazymc runs, and expire afterwards. Expired tokens are
+/// forgotten.
+static USED: Mutex<Option<HashMap<String, u64>>> = Mutex::new(None);
+
+/// Generate signed wake link token, valid for the given number of seconds.
+///
+/// Returns `None` if no wake link secret is configured.
+pub fn generate(config: &Config, ttl: u64) -> Option<String> {
+    let expires = now() + ttl;
+    let nonce: u64 = rand::thread_rng().gen();
+    let payload = format!("{expires:x}-{nonce:016x}");
+    let signature = hex(&mac(config, &payload)?.finalize().into_bytes());
+    Some(format!("{payload}-{signature}"))
+}
+
+/// Generate full wake link URL, valid for the given number of seconds.
+///
+/// Returns `None` if no wake link secret is configured.
+pub fn url(config: &Config, ttl: u64) -> Option<String> {
+    let base = match &config.wake_links.url {
+        Some(url) => url.trim_end_matches('/').to_string(),
+        None => format!("http://{}", config.http.address),
+    };
+    Some(format!("{base}/wake/{}", generate(config, ttl)?))
+}
+
+/// Verify and use wake link token.
+///
+/// Returns an error message if the token is invalid, expired or already used.
+pub fn redeem(config: &Config, token: &str) -> Result<(), &'static str> {
+    let (payload, signature) = token.rsplit_once('-').ok_or("Invalid link")?;
+    let expires = payload
+        .split_once('-')
+        .and_then(|(expires, _)| u64::from_str_radix(expires, 16).ok())
+        .ok_or("Invalid link")?;
+
+    // Verify signature in constant time
+    let signature = unhex(signature).ok_or("Invalid link")?;
+    mac(config, payload)
+        .ok_or("Wake links are disabled")?
+        .verify_slice(&signature)
+        .map_err(|_| "Invalid link")?;
+
+    let now = now();
+    if expires < now {
+        return Err("This link has expired");
+    }
+
+    // Forget expired tokens, they can't be used again anyway
+    let mut used = USED.lock().unwrap();
+    let used = used.get_or_insert_with(HashMap::new);
+    used.retain(|_, until| *until >= now);
+    if used.insert(payload.to_string(), expires).is_some() {
+        return Err("This link was already used");
+    }
+
+    Ok(())
+}
+
+/// Build HMAC over payload with configured secret.
+fn mac(config: &Config, payload: &str) -> Option<Hmac<Sha256>> {
+    if config.wake_links.secret.is_empty() {
+        return None;
+    }
+    let mut mac = Hmac::<Sha256>::new_from_slice(config.wake_links.secret.as_bytes())
+        .expect("HMAC accepts keys of any size");
+    mac.update(payload.as_bytes());
+    Some(mac)
+}
+
+/// Current time as UNIX timestamp.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}